@@ -0,0 +1,95 @@
+#! /usr/bin/env python3
+# Generates letters.sdf.png: a stroke font for A-Z, space, dash, digits and percent,
+# in the same layout as numbers.sdf.png (96x128 cells stacked vertically, 12px SDF radius).
+# Distances are computed exactly from the stroke segments, so no rasterizer or font is needed.
+
+import math, struct, zlib
+
+CELL_W, CELL_H = 96, 128
+SDF_RAD = 12.0
+STROKE = 7.0 # half width in pixels
+
+# glyphs are drawn on a 4x6 grid (y down), mapped into the cell with a margin for the SDF falloff
+def to_px(p):
+    return (20 + p[0] * 14, 16 + p[1] * 16)
+
+def ell(cx, cy, rx, ry, a0, a1, steps=24):
+    return [(cx + rx * math.cos(math.radians(a0 + (a1 - a0) * i / steps)),
+             cy + ry * math.sin(math.radians(a0 + (a1 - a0) * i / steps))) for i in range(steps + 1)]
+
+GLYPHS = {
+    'A': [[(0, 6), (2, 0), (4, 6)], [(0.67, 4), (3.33, 4)]],
+    'B': [[(0, 3), (0, 0), (2.5, 0)] + ell(2.5, 1.5, 1.5, 1.5, -90, 90) + [(0, 3), (2.5, 3)] + ell(2.5, 4.5, 1.5, 1.5, -90, 90) + [(0, 6), (0, 3)]],
+    'C': [ell(2, 3, 2, 3, 40, 320)],
+    'D': [[(1.5, 6), (0, 6), (0, 0), (1.5, 0)] + ell(1.5, 3, 2.5, 3, -90, 90)],
+    'E': [[(4, 0), (0, 0), (0, 6), (4, 6)], [(0, 3), (3, 3)]],
+    'F': [[(4, 0), (0, 0), (0, 6)], [(0, 3), (3, 3)]],
+    'G': [ell(2, 3, 2, 3, -40, -360) + [(2.2, 3)]],
+    'H': [[(0, 0), (0, 6)], [(4, 0), (4, 6)], [(0, 3), (4, 3)]],
+    'I': [[(2, 0), (2, 6)], [(1, 0), (3, 0)], [(1, 6), (3, 6)]],
+    'J': [[(4, 0), (4, 4)] + ell(2, 4, 2, 2, 0, 180)],
+    'K': [[(0, 0), (0, 6)], [(4, 0), (0, 3.5)], [(1.3, 2.4), (4, 6)]],
+    'L': [[(0, 0), (0, 6), (4, 6)]],
+    'M': [[(0, 6), (0, 0), (2, 3.5), (4, 0), (4, 6)]],
+    'N': [[(0, 6), (0, 0), (4, 6), (4, 0)]],
+    'O': [ell(2, 3, 2, 3, 0, 360)],
+    'P': [[(0, 6), (0, 0), (2.5, 0)] + ell(2.5, 1.75, 1.5, 1.75, -90, 90) + [(0, 3.5)]],
+    'Q': [ell(2, 3, 2, 3, 0, 360), [(2.5, 4.5), (4, 6)]],
+    'R': [[(0, 6), (0, 0), (2.5, 0)] + ell(2.5, 1.75, 1.5, 1.75, -90, 90) + [(0, 3.5)], [(2, 3.5), (4, 6)]],
+    'S': [ell(2, 1.5, 2, 1.5, -30, -270) + ell(2, 4.5, 2, 1.5, -90, 150)],
+    'T': [[(0, 0), (4, 0)], [(2, 0), (2, 6)]],
+    'U': [[(0, 0), (0, 4)] + ell(2, 4, 2, 2, 180, 0) + [(4, 0)]],
+    'V': [[(0, 0), (2, 6), (4, 0)]],
+    'W': [[(0, 0), (1, 6), (2, 2.5), (3, 6), (4, 0)]],
+    'X': [[(0, 0), (4, 6)], [(4, 0), (0, 6)]],
+    'Y': [[(0, 0), (2, 3), (4, 0)], [(2, 3), (2, 6)]],
+    'Z': [[(0, 0), (4, 0), (0, 6), (4, 6)]],
+    ' ': [],
+    '-': [[(1, 3), (3, 3)]],
+    '0': [ell(2, 3, 1.7, 3, 0, 360)],
+    '1': [[(1, 1), (2, 0), (2, 6)], [(1, 6), (3, 6)]],
+    '2': [ell(2, 1.6, 2, 1.6, 180, 390) + [(0, 6), (4, 6)]],
+    '3': [ell(2, 1.5, 1.8, 1.5, -150, 90) + ell(2, 4.5, 2, 1.5, -90, 150)],
+    '4': [[(3, 6), (3, 0), (0, 4), (4, 4)]],
+    '5': [[(4, 0), (0.3, 0), (0.1, 2.7)] + ell(2, 4.2, 2, 1.8, -130, 150)],
+    '6': [ell(4, 4.2, 4, 4.2, -100, -180), ell(2, 4.2, 2, 1.8, 0, 360)],
+    '7': [[(0, 0), (4, 0), (1.5, 6)]],
+    '8': [ell(2, 1.5, 1.7, 1.5, 0, 360), ell(2, 4.5, 2, 1.5, 0, 360)],
+    '9': [ell(2, 1.8, 2, 1.8, 0, 360), ell(0, 1.8, 4, 4.2, 0, 80)],
+    '%': [[(4, 0), (0, 6)], ell(0.9, 1.1, 0.9, 1.1, 0, 360, 16), ell(3.1, 4.9, 0.9, 1.1, 0, 360, 16)],
+}
+ORDER = 'ABCDEFGHIJKLMNOPQRSTUVWXYZ -0123456789%'
+
+def seg_dist(p, a, b):
+    abx, aby = b[0] - a[0], b[1] - a[1]
+    apx, apy = p[0] - a[0], p[1] - a[1]
+    len2 = abx * abx + aby * aby
+    t = 0.0 if len2 == 0 else max(0.0, min(1.0, (apx * abx + apy * aby) / len2))
+    dx, dy = apx - t * abx, apy - t * aby
+    return math.sqrt(dx * dx + dy * dy)
+
+def render(glyph):
+    segs = [(to_px(line[i]), to_px(line[i + 1])) for line in glyph for i in range(len(line) - 1)]
+    rows = []
+    for y in range(CELL_H):
+        row = bytearray(CELL_W)
+        for x in range(CELL_W):
+            p = (x + 0.5, y + 0.5)
+            d = min((seg_dist(p, a, b) for a, b in segs), default=1e9) - STROKE
+            row[x] = max(0, min(255, round(255 * (0.5 - d / (2 * SDF_RAD)))))
+        rows.append(row)
+    return rows
+
+def write_png(path, w, h, rows):
+    raw = b''.join(b'\x00' + bytes(r) for r in rows)
+    def chunk(t, b):
+        return struct.pack('>I', len(b)) + t + b + struct.pack('>I', zlib.crc32(t + b) & 0xffffffff)
+    with open(path, 'wb') as f:
+        f.write(b'\x89PNG\r\n\x1a\n')
+        f.write(chunk(b'IHDR', struct.pack('>IIBBBBB', w, h, 8, 0, 0, 0, 0)))
+        f.write(chunk(b'IDAT', zlib.compress(raw, 9)))
+        f.write(chunk(b'IEND', b''))
+
+if __name__ == '__main__':
+    rows = [row for c in ORDER for row in render(GLYPHS[c])]
+    write_png('letters.sdf.png', CELL_W, CELL_H * len(ORDER), rows)
@@ -0,0 +1,362 @@
+use std::path::Path;
+
+use glam::*;
+
+use crate::gputil::AssetSource;
+
+// One point of a lathe profile as (radius, z), with the texture v and normals worked out from its neighbours where not given.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProfilePoint {
+    pub pos: Vec2,
+    pub v: Option<f32>,
+    pub normals: Option<(Vec2, Vec2)>, // facing the previous and next points, the same for a smooth point
+    pub sharp: bool, // only used when the normals are worked out
+}
+
+// A sound atlas to load, as in `SoundAtlas::load_with_starts`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoundSpec {
+    pub file: String,
+    pub volume_db: f64,
+    pub starts: Vec<f64>,
+}
+
+// A kind of target, as designed in targets.cfg.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetArchetype {
+    pub name: String,
+    pub weight: f32,
+    pub scale: f32,
+    pub points: u32,
+    pub hit_points: u32,
+    pub sound: SoundSpec,
+    pub profile: Vec<ProfilePoint>,
+    pub palette: Vec<Vec3>,
+}
+
+pub const MAX_ARCHETYPES: usize = 8;
+pub const MAX_PROFILE_POINTS: usize = 12;
+
+impl TargetArchetype {
+    fn new(name: &str) -> Self {
+        TargetArchetype {
+            name: name.into(),
+            weight: 1.0,
+            scale: 1.0,
+            points: 100,
+            hit_points: 1,
+            sound: SoundSpec {file: "glass_smash.ogg".into(), volume_db: 0.0, starts: vec![0.0]},
+            profile: Vec::new(),
+            palette: Vec::new(),
+        }
+    }
+
+    // profile points with every value filled in, as (pos, v, normal towards the previous point, normal towards the next)
+    pub fn full_profile(&self) -> Vec<(Vec2, f32, Vec2, Vec2)> {
+        let points = &self.profile;
+        let n = points.len();
+        let mut lengths = vec![0.0f32];
+        for pair in points.windows(2) {
+            lengths.push(lengths.last().unwrap() + pair[0].pos.distance(pair[1].pos));
+        }
+        let total = lengths.last().copied().unwrap_or(0.0).max(f32::EPSILON);
+        // outwards is to the right of the way along the profile
+        let outwards = |from: Vec2, to: Vec2| {
+            let t = to - from;
+            vec2(-t.y, t.x).normalize_or(Vec2::X)
+        };
+
+        points.iter().enumerate().map(|(i, p)| {
+            let v = p.v.unwrap_or(lengths[i] / total);
+            let (norm1, norm2) = p.normals.unwrap_or_else(|| {
+                let prev = points[i.saturating_sub(1)].pos;
+                let next = points[(i + 1).min(n - 1)].pos;
+                if p.sharp && i > 0 && i + 1 < n {
+                    (outwards(prev, p.pos), outwards(p.pos, next))
+                } else {
+                    let norm = outwards(prev, next);
+                    (norm, norm)
+                }
+            });
+            (p.pos, v, norm1.normalize_or(Vec2::X), norm2.normalize_or(Vec2::X))
+        }).collect()
+    }
+
+    // vertical offset of the middle of a target from its bottom, for aiming statistics
+    pub fn center_height(&self) -> f32 {
+        let (lo, hi) = self.profile.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| (lo.min(p.pos.y), hi.max(p.pos.y)));
+        0.5 * (lo + hi) * self.scale
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if !(2..=MAX_PROFILE_POINTS).contains(&self.profile.len()) {
+            return Err(format!("{} needs 2 to {} profile points", self.name, MAX_PROFILE_POINTS));
+        }
+        if self.palette.is_empty() {
+            return Err(format!("{} needs at least one color", self.name));
+        }
+        if self.hit_points == 0 {
+            return Err(format!("{} needs at least one hit point", self.name));
+        }
+        Ok(())
+    }
+}
+
+// The archetypes targets are spawned from, loaded from `targets.cfg`.
+// Each one starts with a `[name]` line followed by `<key> = <value>` lines, see the file in the assets folder for details.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchetypeSet {
+    pub archetypes: Vec<TargetArchetype>,
+}
+
+const DEFAULT_ARCHETYPES: &str = include_str!("../assets/targets.cfg");
+
+impl Default for ArchetypeSet {
+    fn default() -> Self {
+        Self::parse(DEFAULT_ARCHETYPES).unwrap()
+    }
+}
+
+impl ArchetypeSet {
+    // falls back to the built in archetypes if the file is missing or invalid
+    pub fn load(assets: &impl AssetSource) -> Self {
+        let bytes = match assets.get_bytes(Path::new("targets.cfg")) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("using default targets, could not read targets.cfg: {}", e);
+                return Self::default();
+            }
+        };
+        match std::str::from_utf8(&bytes).map_err(|e| e.to_string()).and_then(Self::parse) {
+            Ok(archetypes) => archetypes,
+            Err(e) => {
+                log::error!("using default targets, targets.cfg is invalid: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut archetypes: Vec<TargetArchetype> = Vec::new();
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: String| format!("line {}: {}", line_num + 1, msg);
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if archetypes.len() >= MAX_ARCHETYPES {
+                    return Err(err(format!("no more than {} archetypes", MAX_ARCHETYPES)));
+                }
+                archetypes.push(TargetArchetype::new(name.trim()));
+                continue;
+            }
+            let archetype = archetypes.last_mut().ok_or_else(|| err("expected [name] before any values".into()))?;
+
+            let (key, value) = line.split_once('=').ok_or_else(|| err("expected <key> = <value>".into()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let numbers = |count: &[usize]| -> Result<Vec<f32>, String> {
+                let values: Vec<f32> = value.split_whitespace()
+                    .map(|s| s.parse::<f32>().map_err(|_| err(format!("invalid number {} for {}", s, key))))
+                    .collect::<Result<_, _>>()?;
+                if count.contains(&values.len()) {
+                    Ok(values)
+                } else {
+                    Err(err(format!("wrong number of values for {}", key)))
+                }
+            };
+            let invalid = || err(format!("invalid value {} for {}", value, key));
+            match key {
+                "weight" => archetype.weight = value.parse().ok().filter(|w: &f32| *w >= 0.0).ok_or_else(invalid)?,
+                "scale" => archetype.scale = value.parse().ok().filter(|s: &f32| *s > 0.0).ok_or_else(invalid)?,
+                "points" => archetype.points = value.parse().map_err(|_| invalid())?,
+                "hit_points" => archetype.hit_points = value.parse().map_err(|_| invalid())?,
+                "sound" => {
+                    let mut words = value.split_whitespace();
+                    let file = words.next().ok_or_else(invalid)?;
+                    let volume_db = words.next().map_or(Ok(0.0), |v| v.parse().map_err(|_| invalid()))?;
+                    archetype.sound.file = file.into();
+                    archetype.sound.volume_db = volume_db;
+                }
+                "sound_starts" => {
+                    let starts = value.split_whitespace().map(|s| s.parse::<f64>()).collect::<Result<Vec<_>, _>>().map_err(|_| invalid())?;
+                    if starts.is_empty() {
+                        return Err(invalid());
+                    }
+                    archetype.sound.starts = starts;
+                }
+                "point" => {
+                    let sharp = value.ends_with("sharp");
+                    let value = value.trim_end_matches("sharp");
+                    let values: Vec<f32> = value.split_whitespace()
+                        .map(|s| s.parse::<f32>().map_err(|_| invalid()))
+                        .collect::<Result<_, _>>()?;
+                    let pos = match values[..] {
+                        [r, z, ..] => vec2(r, z),
+                        _ => return Err(invalid()),
+                    };
+                    let normals = match values[..] {
+                        [_, _] | [_, _, _] => None,
+                        [_, _, _, nr, nz] if !sharp => Some((vec2(nr, nz), vec2(nr, nz))),
+                        [_, _, _, nr1, nz1, nr2, nz2] if !sharp => Some((vec2(nr1, nz1), vec2(nr2, nz2))),
+                        _ => return Err(err("expected r z [v] [sharp], r z v nr nz or r z v nr1 nz1 nr2 nz2".into())),
+                    };
+                    archetype.profile.push(ProfilePoint {pos, v: values.get(2).copied(), normals, sharp});
+                }
+                "color" => {
+                    let rgb = numbers(&[3])?;
+                    archetype.palette.push(vec3(rgb[0], rgb[1], rgb[2]));
+                }
+                _ => return Err(err(format!("unknown key {}", key))),
+            }
+        }
+
+        if archetypes.is_empty() {
+            return Err("no archetypes".into());
+        }
+        for archetype in archetypes.iter() {
+            archetype.check()?;
+        }
+        if archetypes.iter().all(|a| a.weight == 0.0) {
+            return Err("every weight is zero".into());
+        }
+        Ok(ArchetypeSet {archetypes})
+    }
+
+    // picks an archetype by weight, from `u` in [0, 1)
+    pub fn pick(&self, u: f32) -> usize {
+        let total: f32 = self.archetypes.iter().map(|a| a.weight).sum();
+        let mut left = u * total;
+        for (i, archetype) in self.archetypes.iter().enumerate() {
+            if left < archetype.weight {
+                return i;
+            }
+            left -= archetype.weight;
+        }
+        // rounding at the top end
+        self.archetypes.iter().rposition(|a| a.weight > 0.0).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL: &str = "[a]\npoint = 0 0\npoint = 1 1\ncolor = 1 0 0\n";
+
+    fn archetype(name: &str, weight: f32) -> String {
+        format!("[{}]\nweight = {}\npoint = 0 0\npoint = 1 1\ncolor = 1 0 0\n", name, weight)
+    }
+
+    #[test]
+    fn default_parses() {
+        let set = ArchetypeSet::default();
+        assert!(!set.archetypes.is_empty() && set.archetypes.len() <= MAX_ARCHETYPES);
+        let pot = &set.archetypes[0];
+        assert_eq!(pot.name, "pot");
+        assert_eq!(pot.points, 100);
+        assert_eq!(pot.sound.volume_db, -3.0);
+        assert_eq!(pot.profile[4].normals, Some((vec2(0.0, -1.0), vec2(1.0, 2.0))));
+        for a in set.archetypes.iter() {
+            assert!(a.check().is_ok());
+            assert_eq!(a.full_profile().len(), a.profile.len());
+        }
+    }
+
+    #[test]
+    fn values() {
+        let set = ArchetypeSet::parse("# comment\n[ vase ] # trailing\nweight = 0.5\nscale = 2\npoints = 300\nhit_points = 3\n\
+            sound = bell.ogg -6.5\nsound_starts = 0 1.5\npoint = 0 0 sharp\npoint = 1 0.5 0.25 sharp\npoint = 0 1 1 0 1\ncolor = 0.1 0.2 0.3\n").unwrap();
+        let vase = &set.archetypes[0];
+        assert_eq!(vase.name, "vase");
+        assert_eq!((vase.weight, vase.scale, vase.points, vase.hit_points), (0.5, 2.0, 300, 3));
+        assert_eq!(vase.sound, SoundSpec {file: "bell.ogg".into(), volume_db: -6.5, starts: vec![0.0, 1.5]});
+        assert_eq!(vase.profile[1], ProfilePoint {pos: vec2(1.0, 0.5), v: Some(0.25), normals: None, sharp: true});
+        assert_eq!(vase.profile[2].normals, Some((vec2(0.0, 1.0), vec2(0.0, 1.0))));
+        assert_eq!(vase.palette, vec![vec3(0.1, 0.2, 0.3)]);
+        assert_eq!(vase.center_height(), 1.0);
+    }
+
+    #[test]
+    fn limits() {
+        let most: String = (0..MAX_ARCHETYPES).map(|i| archetype(&format!("a{}", i), 1.0)).collect();
+        assert_eq!(ArchetypeSet::parse(&most).unwrap().archetypes.len(), MAX_ARCHETYPES);
+        let too_many = most.clone() + &archetype("extra", 1.0);
+        let e = ArchetypeSet::parse(&too_many).unwrap_err();
+        assert!(e.starts_with(&format!("line {}:", 5 * MAX_ARCHETYPES + 1)), "{}", e);
+
+        let profile = |n: usize| format!("[a]\ncolor = 1 1 1\n{}", (0..n).map(|i| format!("point = 1 {}\n", i)).collect::<String>());
+        assert!(ArchetypeSet::parse(&profile(MAX_PROFILE_POINTS)).is_ok());
+        assert!(ArchetypeSet::parse(&profile(MAX_PROFILE_POINTS + 1)).is_err());
+        assert!(ArchetypeSet::parse(&profile(1)).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let bad = [
+            "weight = 1\n[a]\npoint = 0 0\npoint = 1 1\ncolor = 1 0 0",
+            "[a]\npoint 0 0",
+            "[a]\nsize = 2",
+            "[a]\nweight = -1",
+            "[a]\nscale = 0",
+            "[a]\npoints = 1.5",
+            "[a]\nhit_points = -1",
+            "[a]\nsound =",
+            "[a]\nsound = a.ogg loud",
+            "[a]\nsound_starts =",
+            "[a]\npoint = 1",
+            "[a]\npoint = 0 0 0 1",
+            "[a]\npoint = 0 0 0 1 1 sharp",
+            "[a]\ncolor = 1 0",
+            "[a]\ncolor = 1 0 x",
+        ];
+        for text in bad {
+            let e = ArchetypeSet::parse(text).unwrap_err();
+            let line = if text.starts_with("weight") {1} else {2};
+            assert!(e.starts_with(&format!("line {}:", line)), "{:?} gave {}", text, e);
+        }
+
+        // whole archetypes missing something
+        assert!(ArchetypeSet::parse("").is_err());
+        assert!(ArchetypeSet::parse("[a]\npoint = 0 0\npoint = 1 1").is_err());
+        assert!(ArchetypeSet::parse(&(SMALL.to_string() + "hit_points = 0")).is_err());
+        assert!(ArchetypeSet::parse(&(archetype("a", 0.0) + &archetype("b", 0.0))).is_err());
+    }
+
+    #[test]
+    fn pick_by_weight() {
+        let set = ArchetypeSet::parse(&(archetype("a", 1.0) + &archetype("none", 0.0) + &archetype("b", 3.0))).unwrap();
+        assert_eq!(set.pick(0.0), 0);
+        assert_eq!(set.pick(0.249), 0);
+        assert_eq!(set.pick(0.251), 2);
+        assert_eq!(set.pick(0.9999999), 2);
+        assert_eq!(set.pick(1.0), 2); // past the end still lands on a weighted archetype
+
+        let mut counts = [0; 3];
+        let n = 4000;
+        for i in 0..n {
+            counts[set.pick((i as f32 + 0.5) / n as f32)] += 1;
+        }
+        assert_eq!(counts, [1000, 0, 3000]);
+    }
+
+    #[test]
+    fn full_profile() {
+        let set = ArchetypeSet::parse("[a]\ncolor = 1 1 1\npoint = 1 2\npoint = 1 1 sharp\npoint = 0 0\npoint = 0 -1 0.9 3 4 0 1\n").unwrap();
+        let full = set.archetypes[0].full_profile();
+        // v follows the length along the profile unless given
+        let total = 2.0 + 2.0f32.sqrt();
+        let v: Vec<f32> = full.iter().map(|p| p.1).collect();
+        assert_eq!(v[0], 0.0);
+        assert!((v[1] - 1.0 / total).abs() < 1e-6 && (v[2] - (1.0 + 2.0f32.sqrt()) / total).abs() < 1e-6);
+        assert_eq!(v[3], 0.9);
+        // ends face out from their only segment, sharp corners face each segment, given normals are normalized
+        assert_eq!((full[0].2, full[0].3), (Vec2::X, Vec2::X));
+        assert_eq!(full[1].2, Vec2::X);
+        assert!((full[1].3 - vec2(1.0, -1.0).normalize()).length() < 1e-6);
+        assert!((full[2].2 - vec2(2.0, -1.0).normalize()).length() < 1e-6);
+        assert_eq!(full[2].2, full[2].3);
+        assert_eq!((full[3].2, full[3].3), (vec2(0.6, 0.8), Vec2::Y));
+    }
+}
@@ -7,12 +7,11 @@ use std::slice::from_ref;
 use std::time::Instant;
 
 use glam::*;
-use kira::manager::AudioManager;
 use kira::sound::Sound;
 use wgpu::util::BufferInitDescriptor;
 use wgpu::util::DeviceExt;
 use wgpu::*;
-use crate::audio_util::SoundAtlas;
+use crate::events::{EventQueue, GameEvent};
 use crate::deferred_renderer::*;
 use crate::gputil::*;
 use crate::camera::*;
@@ -92,10 +91,53 @@ fn arrow_model() -> Box<[ArrowVert]> {
 #[repr(C)]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 struct Arrow {
-    end_pos: Vec3,
+    end_pos: Vec3, // relative to the parent
     state: u32, // 0 = dead, 1 = live
     dir: Vec3, // normalized
     len: f32,
+    parent_rot: Quat, // the target an arrow is stuck in, identity otherwise
+    parent_pos: Vec3,
+    pad: f32,
+}
+
+impl Arrow {
+    fn new(end_pos: Vec3, dir: Vec3, state: u32, len: f32) -> Self {
+        Arrow {end_pos, state, dir, len, parent_rot: Quat::IDENTITY, parent_pos: Vec3::ZERO, pad: 0.0}
+    }
+
+    fn world_end_pos(&self) -> Vec3 {
+        self.parent_pos + self.parent_rot * self.end_pos
+    }
+
+    fn world_dir(&self) -> Vec3 {
+        self.parent_rot * self.dir
+    }
+}
+
+// simulation-side state of an arrow in flight
+#[derive(Clone, Copy, Debug)]
+struct LiveArrow {
+    arrow: Arrow,
+    prev_pos: Vec3, // position at the previous step, for interpolation
+    vel: Vec3,
+    id: u32, // shot number, for scoring
+}
+
+// an arrow stuck in a target, following it around until it breaks
+#[derive(Clone, Copy, Debug)]
+struct EmbeddedArrow {
+    arrow: Arrow, // parent is where the target was at the last step
+    prev_parent: (Vec3, Quat), // at the step before, for interpolation
+    target: usize, // index into the targets passed to `tick`
+    hit_id: u32,
+}
+
+// an arrow fallen out of a broken target, sinking until it settles on the bottom
+#[derive(Clone, Copy, Debug)]
+struct SinkingArrow {
+    arrow: Arrow,
+    prev_pos: Vec3,
+    vel: Vec3,
 }
 
 #[repr(C)]
@@ -103,18 +145,457 @@ struct Arrow {
 struct Splish {
     center: Vec2,
     start_time: f32,
+    skip_dir: Vec2, // direction of travel after a skip, zero for arrows going in
 }
 
 const MAX_DEAD_ARROWS: usize = 64;
 const MAX_LIVE_ARROWS: usize = 4;
-const ARROW_SPEED: f32 = 50.0;
+const MAX_EMBEDDED_ARROWS: usize = 32;
+const MAX_SINKING_ARROWS: usize = 16;
+const ARROW_SPEED: f32 = 50.0; // at release from a full draw
+const MIN_ARROW_SPEED: f32 = 15.0; // at release with no draw at all
+const FULL_DRAW_TIME: f64 = 0.8; // seconds of holding to reach full speed
+const MAX_SWAY_DEG: f32 = 0.6; // aim wander once the bow has been held at full draw for a while
+const SWAY_RAMP_TIME: f64 = 1.5;
+const GRAVITY: f32 = 9.81;
+const AIR_DRAG: f32 = 0.002; // quadratic drag coefficient per meter
+const WATER_ENTRY_SPEED_FACTOR: f32 = 0.35; // speed kept on crossing the surface
+const WATER_DRAG: f32 = 1.0;
+const WATER_GRAVITY: f32 = 1.0; // what is left of gravity after buoyancy, sets how fast spent arrows sink
+const MIN_PIERCE_SPEED: f32 = 2.0; // slower arrows stick in targets they would otherwise go through
+const SKIP_ANGLE: f32 = 0.12; // radians below horizontal, arrows coming in flatter than this skip off the surface
+const MIN_SKIP_SPEED: f32 = 15.0; // slower arrows go in at any angle
+const SKIP_RESTITUTION: f32 = 0.5; // vertical speed kept on a skip
+const SKIP_SPEED_FACTOR: f32 = 0.8; // horizontal speed kept on a skip
+const ENTRY_DEFLECTION: f32 = 0.05; // radians, how far a grazing entry is turned towards the surface
 const ARROW_LEN: f32 = 1.0;
 const MOVING_ARROW_LEN: f32 = 1.5;
-const RAYMARCH_RES: f32 = 0.2;
 const MAX_SPLISHES: usize = 16;
 const SPLISH_DURATION: f32 = 2.0;
 
+// Limits on shooting for modes with a quiver, refilled by the simulation at the start of every lap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuiverRules {
+    pub arrows_per_lap: u32,
+    pub reload_time: f64, // seconds to nock the next arrow after a shot
+    pub refund_on_hit: bool, // each pot smashed puts an arrow back, up to a full quiver
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Quiver {
+    rules: QuiverRules,
+    arrows: u32,
+    ready_at: f64, // rail time the next arrow is nocked
+}
+
+impl Quiver {
+    fn refill(&mut self) {
+        self.arrows = self.rules.arrows_per_lap;
+    }
+}
+
+// What the HUD shows of the quiver.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuiverStatus {
+    pub arrows: u32,
+    pub capacity: u32,
+    pub reload: f32, // 0 just after a shot to 1 once the next arrow is ready
+}
+
+// Simulation state for arrows, usable without a GPU.
 pub struct ArrowController {
+    all_splishes: VecDeque<Splish>,
+
+    dead_arrows: Box<[Arrow]>, // ring buffer
+    num_dead_arrows: usize,
+    next_dead_arrow: usize,
+    live_arrows: Vec<LiveArrow>,
+    embedded_arrows: Vec<EmbeddedArrow>, // oldest first
+    sinking_arrows: Vec<SinkingArrow>, // oldest first
+    pub arrows_shot: u32,
+    quiver: Option<Quiver>,
+    draw_started_at: Option<f64>, // rail time drawing began, None while the bow is at rest
+    updated_at: f64,
+}
+
+impl Default for ArrowController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArrowController {
+    pub fn new() -> Self {
+        let dead_arrows = bytemuck::zeroed_slice_box(MAX_DEAD_ARROWS);
+        ArrowController {
+            all_splishes: VecDeque::new(),
+
+            dead_arrows,
+            num_dead_arrows: 0,
+            next_dead_arrow: 0,
+            live_arrows: Vec::new(),
+            embedded_arrows: Vec::new(),
+            sinking_arrows: Vec::new(),
+            arrows_shot: 0,
+            quiver: None,
+            draw_started_at: None,
+            updated_at: 0.0,
+        }
+    }
+
+    // `quiver` is None for unlimited shooting
+    pub fn reset(&mut self, quiver: Option<QuiverRules>) {
+        self.all_splishes.clear();
+        self.num_dead_arrows = 0;
+        self.next_dead_arrow = 0;
+        self.live_arrows.clear();
+        self.embedded_arrows.clear();
+        self.sinking_arrows.clear();
+        self.arrows_shot = 0;
+        self.quiver = quiver.map(|rules| Quiver {rules, arrows: rules.arrows_per_lap, ready_at: 0.0});
+        self.draw_started_at = None;
+        self.updated_at = 0.0;
+    }
+
+    // tops the quiver back up, for the start of a lap
+    pub fn refill_quiver(&mut self) {
+        if let Some(q) = &mut self.quiver {
+            q.refill();
+        }
+    }
+
+    pub fn quiver_status(&self) -> Option<QuiverStatus> {
+        self.quiver.map(|q| {
+            let reload = if q.rules.reload_time > 0.0 {1.0 - (q.ready_at - self.updated_at) / q.rules.reload_time} else {1.0};
+            QuiverStatus {arrows: q.arrows, capacity: q.rules.arrows_per_lap, reload: reload.clamp(0.0, 1.0) as f32}
+        })
+    }
+
+    // false while the quiver is empty or the next arrow is being nocked
+    pub fn can_shoot(&self) -> bool {
+        self.quiver.is_none_or(|q| q.arrows > 0 && self.updated_at >= q.ready_at)
+    }
+
+    pub fn arrows_in_flight(&self) -> usize {
+        self.live_arrows.len()
+    }
+
+    // shot numbers and tip positions of the arrows in flight
+    pub fn live_positions(&self) -> impl Iterator<Item = (u32, Vec3)> + '_ {
+        self.live_arrows.iter().map(|a| (a.id, a.arrow.end_pos))
+    }
+
+    // Pressing starts drawing the bow and releasing shoots, faster the longer it was held.
+    // The draw only counts from when the next arrow is nocked, and nothing happens with an empty quiver.
+    pub fn start_draw(&mut self) {
+        if self.draw_started_at.is_none() && self.quiver.is_none_or(|q| q.arrows > 0) {
+            self.draw_started_at = Some(self.quiver.map_or(self.updated_at, |q| q.ready_at.max(self.updated_at)));
+        }
+    }
+
+    // released before the arrow was nocked, nothing is shot
+    pub fn release(&mut self, events: &mut EventQueue, camera: &impl CameraController) {
+        let Some(strength) = self.draw_strength() else { return };
+        self.draw_started_at = None;
+        if self.can_shoot() {
+            self.shoot(events, camera, MIN_ARROW_SPEED.lerp(ARROW_SPEED, strength));
+        }
+    }
+
+    // lets go of the string without shooting, for pausing or the run ending mid-draw
+    pub fn cancel_draw(&mut self) {
+        self.draw_started_at = None;
+    }
+
+    // 0 to 1, None while the bow is at rest
+    pub fn draw_strength(&self) -> Option<f32> {
+        self.draw_started_at.map(|start| ((self.updated_at - start) / FULL_DRAW_TIME).clamp(0.0, 1.0) as f32)
+    }
+
+    // yaw and pitch offsets in degrees, the longer the bow is held at full draw the more it wanders
+    pub fn aim_sway(&self) -> Vec2 {
+        let Some(start) = self.draw_started_at else { return Vec2::ZERO };
+        let held = self.updated_at - start - FULL_DRAW_TIME;
+        if held <= 0.0 {
+            return Vec2::ZERO;
+        }
+        let amount = MAX_SWAY_DEG * (held / SWAY_RAMP_TIME).min(1.0) as f32;
+        // a few unrelated frequencies, so the wander never looks periodic
+        let t = held as f32;
+        let wander = vec2(
+            (TAU * 0.31 * t).sin() + 0.5 * (TAU * 0.83 * t + 1.0).sin(),
+            (TAU * 0.23 * t + 2.0).sin() + 0.5 * (TAU * 0.67 * t).sin(),
+        );
+        amount * wander / 1.5
+    }
+
+    fn shoot(&mut self, events: &mut EventQueue, camera: &impl CameraController, speed: f32) {
+        if let Some(q) = &mut self.quiver {
+            q.arrows -= 1;
+            q.ready_at = self.updated_at + q.rules.reload_time;
+        }
+        let id = self.arrows_shot;
+        self.arrows_shot += 1;
+        let eye = camera.eye();
+        let start_pos = eye - vec3(0.0, 0.0, 0.08);
+        let dir = camera.look_dir().normalize();
+        let end_pos = start_pos + dir * MOVING_ARROW_LEN;
+        let arrow = LiveArrow {
+            arrow: Arrow::new(end_pos, dir, 1, MOVING_ARROW_LEN),
+            prev_pos: end_pos,
+            vel: dir * speed,
+            id,
+        };
+
+        events.push(GameEvent::ArrowReleased { arrow: id, eye, pos: start_pos, dir });
+        if self.live_arrows.len() < MAX_LIVE_ARROWS {
+            self.live_arrows.push(arrow);
+        } else {
+            // replace the furthest-away (oldest) live arrow if full
+            let mut dmax = 0.0;
+            let mut imax = 0;
+            for i in 0..self.live_arrows.len() {
+                let d = (self.live_arrows[i].arrow.end_pos - eye).length();
+                if d > dmax {
+                    dmax = d;
+                    imax = i;
+                }
+            }
+            let replaced = &self.live_arrows[imax];
+            events.push(GameEvent::ArrowSpent { arrow: replaced.id, pos: replaced.arrow.end_pos });
+            self.live_arrows[imax] = arrow;
+        }
+    }
+
+    // keep moving arrows still while paused, so that interpolation does not replay the last step
+    pub fn hold(&mut self) {
+        for live in self.live_arrows.iter_mut() {
+            live.prev_pos = live.arrow.end_pos;
+        }
+        for embedded in self.embedded_arrows.iter_mut() {
+            embedded.prev_parent = (embedded.arrow.parent_pos, embedded.arrow.parent_rot);
+        }
+        for sinking in self.sinking_arrows.iter_mut() {
+            sinking.prev_pos = sinking.arrow.end_pos;
+        }
+    }
+
+    pub fn tick(&mut self, time: f64, terrain: &HeightmapTerrain, events: &mut EventQueue, targets: &mut[&mut dyn ArrowTarget]) -> bool {
+        if time <= 0.0 {
+            return false
+        }
+
+        while let Some(s) = self.all_splishes.front() {
+            if s.start_time + SPLISH_DURATION < time as f32 {
+                self.all_splishes.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let mut did_hit = false;
+        let mut refunds = 0;
+        let delta_t = time - self.updated_at;
+
+        // stuck arrows follow their targets, and fall out of any that broke
+        let mut fallen = Vec::new();
+        self.embedded_arrows.retain_mut(|embedded| {
+            let Some(placement) = targets.get(embedded.target).and_then(|t| t.attachment(embedded.hit_id)) else {
+                fallen.push(embedded.arrow);
+                return false;
+            };
+            embedded.prev_parent = (embedded.arrow.parent_pos, embedded.arrow.parent_rot);
+            (embedded.arrow.parent_pos, embedded.arrow.parent_rot) = placement;
+            true
+        });
+        for arrow in fallen {
+            self.drop_arrow(arrow);
+        }
+        let mut settled = Vec::new();
+        self.sinking_arrows.retain_mut(|sinking| {
+            let old_pos = sinking.arrow.end_pos;
+            let dt = delta_t as f32;
+            let (gravity, drag) = if old_pos.z > 0.0 {(GRAVITY, AIR_DRAG)} else {(WATER_GRAVITY, WATER_DRAG)};
+            sinking.vel += dt * (vec3(0.0, 0.0, -gravity) - drag * sinking.vel.length() * sinking.vel);
+            let new_pos = old_pos + sinking.vel * dt;
+            sinking.prev_pos = old_pos;
+            if let Some(hit) = terrain.raycast(old_pos, new_pos) {
+                settled.push(Arrow {end_pos: hit.pos, ..sinking.arrow});
+                return false;
+            }
+            sinking.arrow.end_pos = new_pos;
+            terrain.height_at(new_pos.xy()).is_some()
+        });
+        for arrow in settled {
+            self.bury(arrow);
+        }
+
+        let mut stuck = Vec::new();
+        let mut embedded = Vec::new();
+        self.live_arrows.retain_mut(|LiveArrow {arrow: live_arrow, prev_pos, vel, id}| {
+            let mut stays_live = true;
+            let old_pos = live_arrow.end_pos;
+            let dt = delta_t as f32;
+
+            // semi-implicit Euler, the path within a step is straight so that it can be raycast
+            let (gravity, drag) = if old_pos.z > 0.0 {(GRAVITY, AIR_DRAG)} else {(WATER_GRAVITY, WATER_DRAG)};
+            *vel += dt * (vec3(0.0, 0.0, -gravity) - drag * vel.length() * *vel);
+            let mut step = *vel * dt;
+            let mut surface_hit = None;
+            if old_pos.z > 0.0 && old_pos.z + step.z <= 0.0 {
+                // the path bends where it meets the surface, and the rest of the step is taken at the new velocity
+                let t = old_pos.z / -step.z;
+                let speed = vel.length();
+                let grazing = (-vel.z / speed).asin(); // below horizontal
+                let skips = grazing < SKIP_ANGLE && speed > MIN_SKIP_SPEED;
+                if skips {
+                    // bounces off, losing most of its vertical speed and some of the rest
+                    *vel = vec3(SKIP_SPEED_FACTOR * vel.x, SKIP_SPEED_FACTOR * vel.y, -SKIP_RESTITUTION * vel.z);
+                } else {
+                    // the water turns it a little towards the surface, most at shallow angles, and takes most of its speed
+                    let angle = grazing - ENTRY_DEFLECTION * grazing.cos().powi(2);
+                    let horizontal = vel.xy().normalize_or_zero();
+                    *vel = WATER_ENTRY_SPEED_FACTOR * speed * (angle.cos() * horizontal).extend(-angle.sin());
+                }
+                surface_hit = Some((old_pos + t * step, skips));
+                step = t * step + (1.0 - t) * dt * *vel;
+            }
+            live_arrow.dir = vel.normalize_or(live_arrow.dir);
+            let mut new_pos = old_pos + step;
+
+            // each leg is checked against the terrain and every target, and hits are taken in order along it
+            let bend = surface_hit.map_or(new_pos, |(pos, _)| pos);
+            let legs = [(old_pos, bend), (bend, new_pos)];
+            let num_legs = if surface_hit.is_some() {2} else {1};
+            let first_hit = events.len();
+            let mut stuck_at = None;
+            let mut embedded_in = None;
+            for (n, (start, end)) in legs.into_iter().take(num_legs).enumerate() {
+                let terrain_hit = terrain.raycast(start, end);
+                let mut hits: Vec<(usize, TargetHit)> = Vec::new();
+                for (i, target) in targets.iter().enumerate() {
+                    hits.extend(target.find_hits(start, end).into_iter().map(|hit| (i, hit)));
+                }
+                hits.retain(|(_, hit)| terrain_hit.is_none_or(|t| hit.t <= t.t));
+                hits.sort_by(|a, b| a.1.t.total_cmp(&b.1.t));
+
+                for (i, mut hit) in hits {
+                    if hit.response == HitResponse::Pierce && vel.length() < MIN_PIERCE_SPEED {
+                        hit.response = HitResponse::Embed;
+                    }
+                    // a target the hit breaks still holds the arrow where it struck, until it falls away on the next step
+                    let struck_at = targets[i].attachment(hit.id);
+                    targets[i].apply_hit(events, *id, *vel, &hit);
+                    did_hit = true;
+                    if hit.response != HitResponse::Pierce {
+                        new_pos = hit.pos;
+                        stays_live = false;
+                        if hit.response == HitResponse::Embed {
+                            embedded_in = Some((i, hit, targets[i].attachment(hit.id).or(struck_at)));
+                        }
+                        break;
+                    }
+                }
+                if stays_live && let Some(hit) = terrain_hit {
+                    new_pos = hit.pos;
+                    stays_live = false;
+                    stuck_at = Some(hit.pos);
+                    events.push(GameEvent::ArrowHitTerrain { arrow: *id, pos: hit.pos });
+                }
+                if !stays_live {
+                    break;
+                }
+
+                // only if it got to the surface before anything stopped it
+                if n == 0 && let Some((pos, skipped)) = surface_hit {
+                    if self.all_splishes.len() >= MAX_SPLISHES {
+                        self.all_splishes.pop_front();
+                    }
+                    self.all_splishes.push_back(Splish {
+                        center: pos.xy(),
+                        start_time: time as f32,
+                        skip_dir: if skipped {vel.xy().normalize_or_zero()} else {Vec2::ZERO},
+                    });
+                    if skipped {
+                        events.push(GameEvent::ArrowSkipped { arrow: *id, pos });
+                    } else {
+                        events.push(GameEvent::WaterEntry { arrow: *id, pos });
+                    }
+                }
+            }
+            refunds += events.since(first_hit).iter().filter(|e| matches!(e, GameEvent::TargetSmashed {..} | GameEvent::FishHit {..})).count() as u32;
+
+            if let Some(pos) = stuck_at {
+                stuck.push(Arrow::new(pos, live_arrow.dir, 0, ARROW_LEN));
+            }
+            if let Some((i, hit, placement)) = embedded_in {
+                embedded.push((i, hit.id, placement, Arrow::new(hit.pos, live_arrow.dir, 0, ARROW_LEN)));
+            }
+            if stays_live && terrain.height_at(new_pos.xy()).is_none() {
+                // left the pond
+                stays_live = false;
+            }
+            *prev_pos = old_pos;
+            live_arrow.end_pos = new_pos;
+
+            if !stays_live {
+                events.push(GameEvent::ArrowSpent { arrow: *id, pos: new_pos });
+            }
+            stays_live
+        });
+        for arrow in stuck {
+            self.bury(arrow);
+        }
+        for (target, hit_id, placement, arrow) in embedded {
+            self.embed(placement, target, hit_id, arrow);
+        }
+        if let Some(q) = self.quiver.as_mut().filter(|q| q.rules.refund_on_hit) {
+            q.arrows = (q.arrows + refunds).min(q.rules.arrows_per_lap);
+        }
+        self.updated_at = time;
+        did_hit
+    }
+
+    // puts an arrow which has come to rest into the ring buffer
+    fn bury(&mut self, arrow: Arrow) {
+        self.dead_arrows[self.next_dead_arrow] = arrow;
+        self.next_dead_arrow = (self.next_dead_arrow + 1) % MAX_DEAD_ARROWS;
+        self.num_dead_arrows = MAX_DEAD_ARROWS.min(self.num_dead_arrows + 1);
+    }
+
+    // `arrow` is in world space, `placement` is of the target as it was struck, and it falls straight away without one
+    fn embed(&mut self, placement: Option<(Vec3, Quat)>, target: usize, hit_id: u32, arrow: Arrow) {
+        let Some((pos, rot)) = placement else {
+            self.drop_arrow(arrow);
+            return;
+        };
+        if self.embedded_arrows.len() >= MAX_EMBEDDED_ARROWS {
+            let oldest = self.embedded_arrows.remove(0);
+            self.drop_arrow(oldest.arrow);
+        }
+        let to_local = rot.inverse();
+        self.embedded_arrows.push(EmbeddedArrow {
+            arrow: Arrow {end_pos: to_local * (arrow.end_pos - pos), dir: to_local * arrow.dir, parent_rot: rot, parent_pos: pos, ..arrow},
+            prev_parent: (pos, rot),
+            target, hit_id,
+        });
+    }
+
+    // lets go of an arrow to sink from where it is
+    fn drop_arrow(&mut self, arrow: Arrow) {
+        if self.sinking_arrows.len() >= MAX_SINKING_ARROWS {
+            self.sinking_arrows.remove(0);
+        }
+        let end_pos = arrow.world_end_pos();
+        self.sinking_arrows.push(SinkingArrow {
+            arrow: Arrow::new(end_pos, arrow.world_dir(), 0, arrow.len),
+            prev_pos: end_pos,
+            vel: Vec3::ZERO,
+        });
+    }
+}
+
+pub struct ArrowView {
     arrows_pipeline: RenderPipeline,
     arrows_refr_pipeline: RenderPipeline,
     arrows_refl_pipeline: RenderPipeline,
@@ -128,22 +609,14 @@ pub struct ArrowController {
     splish_pipeline: RenderPipeline,
     splish_buf: Buffer,
     max_splish_inst: u32,
-    all_splishes: VecDeque<Splish>,
 
-    release_sounds: SoundAtlas,
-    splish_sounds: SoundAtlas,
-    thunk_sounds: SoundAtlas,
-
-    dead_arrows: Box<[Arrow]>, // ring buffer
-    num_dead_arrows: usize,
-    next_dead_arrow: usize,
-    live_arrows: Vec<Arrow>,
-    pub arrows_shot: u32,
-    updated_at: f64,
+    // instances copied from the simulation each frame
+    arrow_insts: Vec<Arrow>,
+    splish_insts: Vec<Splish>,
 }
 
-impl ArrowController {
-    pub fn new(gpu: &GPUContext, assets: &impl AssetSource, renderer: &DeferredRenderer) -> Self {
+impl ArrowView {
+    pub fn new(gpu: &GPUContext, renderer: &DeferredRenderer) -> Self {
         let shaders = gpu.process_shader_module("arrows.wgsl", crate::shaders::ARROWS);
 
         let arrows_bg_layout = gpu.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
@@ -235,7 +708,7 @@ impl ArrowController {
 
         let arrows_buf = gpu.device.create_buffer(&BufferDescriptor {
             label: Some("arrows_buf"),
-            size: (size_of::<Arrow>() * (MAX_DEAD_ARROWS + MAX_LIVE_ARROWS)) as u64,
+            size: (size_of::<Arrow>() * (MAX_DEAD_ARROWS + MAX_LIVE_ARROWS + MAX_EMBEDDED_ARROWS + MAX_SINKING_ARROWS)) as u64,
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
             mapped_at_creation: false
         });
@@ -250,7 +723,7 @@ impl ArrowController {
         let splish_vertex_layout = VertexBufferLayout {
             array_stride: size_of::<Splish>() as u64,
             step_mode: VertexStepMode::Instance,
-            attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32],
+            attributes: &vertex_attr_array![0 => Float32x2, 1 => Float32, 2 => Float32x2],
         };
         let splish_pipeline_layout = gpu.device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("splish_pipeline_layout"),
@@ -314,169 +787,55 @@ impl ArrowController {
         });
 
 
-        let release_sounds = SoundAtlas::load_with_stride(assets, "arrow_release.ogg", 5.0, 0.4).unwrap();
-        let thunk_sounds = SoundAtlas::load_with_stride(assets, "arrow_thunk.ogg", -3.0, 0.5).unwrap();
-        let splish_sounds = SoundAtlas::load_with_stride(assets, "water_splish.ogg", -2.0, 1.0).unwrap();
-
-        let dead_arrows = bytemuck::zeroed_slice_box(MAX_DEAD_ARROWS);
-        ArrowController {
+        ArrowView {
             arrows_pipeline, arrows_refr_pipeline, arrows_refl_pipeline, shadow_arrows_pipeline,
             arrows_model, arrows_vertex_buf, arrows_buf, arrows_bg,
-            release_sounds, splish_sounds, thunk_sounds,
             max_arrow_inst: 0,
             splish_pipeline, splish_buf,
-            all_splishes: VecDeque::new(),
             max_splish_inst: 0,
 
-            dead_arrows,
-            num_dead_arrows: 0,
-            next_dead_arrow: 0,
-            live_arrows: Vec::new(),
-            arrows_shot: 0,
-            updated_at: 0.0,
-        }
-    }
-
-    pub fn reset(&mut self) {
-        self.num_dead_arrows = 0;
-        self.next_dead_arrow = 0;
-        self.live_arrows.clear();
-        self.arrows_shot = 0;
-        self.updated_at = 0.0;
-    }
-
-    pub fn shoot(&mut self, audio: Option<&mut AudioManager>, camera: &impl CameraController) {
-        self.arrows_shot += 1;
-        let eye = camera.eye();
-        let start_pos = eye - vec3(0.0, 0.0, 0.08);
-        let dir = camera.look_dir().normalize();
-        let end_pos = start_pos + dir * MOVING_ARROW_LEN;
-        let arrow = Arrow {
-            end_pos, dir, state: 1, len: MOVING_ARROW_LEN,
-        };
-
-        if self.live_arrows.len() < MAX_LIVE_ARROWS {
-            self.live_arrows.push(arrow);
-        } else {
-            // replace the furthest-away (oldest) live arrow if full
-            let mut dmax = 0.0;
-            let mut imax = 0;
-            for i in 0..self.live_arrows.len() {
-                let d = (self.live_arrows[i].end_pos - eye).length();
-                if d > dmax {
-                    dmax = d;
-                    imax = i;
-                }
-            }
-            self.live_arrows[imax] = arrow;
-        }
-        if let Some(audio) = audio {
-            audio.play(self.release_sounds.random_sound()).unwrap();
+            arrow_insts: Vec::new(),
+            splish_insts: Vec::new(),
         }
     }
 
-    pub fn tick(&mut self, time: f64, terrain: &HeightmapTerrain, mut audio: Option<&mut AudioManager>, targets: &mut[&mut dyn ArrowTarget]) -> bool {
-        if time <= 0.0 {
-            return false
-        }
-
-        while let Some(s) = self.all_splishes.front() {
-            if s.start_time + SPLISH_DURATION < time as f32 {
-                self.all_splishes.pop_front();
-            } else {
-                break;
-            }
-        }
-
-        let mut did_hit = false;
-        let delta_t = time - self.updated_at;
-
-        self.live_arrows.retain_mut(|live_arrow: &mut Arrow| {
-            let mut stays_live = true;
-            let old_pos = live_arrow.end_pos;
-            let frame_dist = delta_t as f32 * ARROW_SPEED;
-            let mut new_pos = old_pos + frame_dist * live_arrow.dir;
-
-            let num_tests = (frame_dist / RAYMARCH_RES).ceil() as u32;
-            let delta_p = live_arrow.dir * frame_dist / (num_tests as f32);
-            let mut last_pos = old_pos;
-            let mut last_height = f32::INFINITY;
-            for i in 0..=num_tests {
-                let test_pos = old_pos + delta_p * (i as f32) ;
-                match terrain.height_at(test_pos.xy()) {
-                    None => {
-                        stays_live = false;
-                        break;
-                    }
-                    Some(h) => {
-                        if i > 0 && h > test_pos.z {
-                            let t = (last_pos.z - last_height) / (last_pos.z - last_height + h - test_pos.z);
-                            let stop_pos = last_pos + t * delta_p;
-                            new_pos = stop_pos;
-                            stays_live = false;
-
-                            // put this arrow into the ring buffer
-                            self.dead_arrows[self.next_dead_arrow ] = Arrow {
-                                end_pos: stop_pos, state: 0, dir: live_arrow.dir, len: ARROW_LEN,
-                            };
-                            self.next_dead_arrow = (self.next_dead_arrow + 1) % MAX_DEAD_ARROWS;
-                            self.num_dead_arrows = MAX_DEAD_ARROWS.min(self.num_dead_arrows + 1);
-
-                            if let Some(audio) = &mut audio {
-                                audio.play(self.thunk_sounds.random_sound()).unwrap();
-                            }
-                            break;
-                        }
-                        last_height = h;
-                        last_pos = test_pos;
-                    }
-                }
-            }
-            live_arrow.end_pos = new_pos;
-
-            if old_pos.z > 0.0 && new_pos.z <= 0.0 {
-                if let Some(audio) = &mut audio {
-                    audio.play(self.splish_sounds.random_sound()).unwrap();
-                }
-                if self.all_splishes.len() >= MAX_SPLISHES {
-                    self.all_splishes.pop_front();
-                }
-                self.all_splishes.push_back(Splish {
-                    center: old_pos.xy().lerp(new_pos.xy(), old_pos.z / (old_pos.z - new_pos.z)),
-                    start_time: time as f32,
-                });
-            }
-
-            for target in targets.iter_mut() {
-                let hit_target =  target.process_hits(audio.as_deref_mut(), old_pos, new_pos);
-                did_hit = did_hit || hit_target;
+    // copy instances to draw from the simulation, placing live arrows between the last two steps
+    pub fn update(&mut self, arrows: &ArrowController, alpha: f32) {
+        self.arrow_insts.clear();
+        self.arrow_insts.extend_from_slice(&arrows.dead_arrows[..arrows.num_dead_arrows]);
+        self.arrow_insts.extend(arrows.live_arrows.iter().map(|live| {
+            Arrow {end_pos: live.prev_pos.lerp(live.arrow.end_pos, alpha), ..live.arrow}
+        }));
+        self.arrow_insts.extend(arrows.embedded_arrows.iter().map(|embedded| {
+            let (prev_pos, prev_rot) = embedded.prev_parent;
+            Arrow {
+                parent_pos: prev_pos.lerp(embedded.arrow.parent_pos, alpha),
+                parent_rot: prev_rot.slerp(embedded.arrow.parent_rot, alpha),
+                ..embedded.arrow
             }
-
-            stays_live
-        });
-        self.updated_at = time;
-        did_hit
+        }));
+        self.arrow_insts.extend(arrows.sinking_arrows.iter().map(|sinking| {
+            Arrow {end_pos: sinking.prev_pos.lerp(sinking.arrow.end_pos, alpha), ..sinking.arrow}
+        }));
+        self.splish_insts.clear();
+        self.splish_insts.extend(arrows.all_splishes.iter().copied());
     }
-
 }
 
-impl RenderObject for ArrowController {
+impl RenderObject for ArrowView {
     fn prepass(&mut self, gpu: &GPUContext, renderer: &DeferredRenderer, encoder: &mut CommandEncoder) {
         let planes = renderer.camera.perspective_clipping_planes();
-        let mut visible_arrows: Vec<Arrow> = self.dead_arrows.iter().copied().filter(|arr| {
-            sphere_visible(planes, arr.end_pos, 1.5 * arr.len)
+        let visible_arrows: Vec<Arrow> = self.arrow_insts.iter().copied().filter(|arr| {
+            sphere_visible(planes, arr.world_end_pos(), 1.5 * arr.len)
         }).collect();
-        visible_arrows.append(&mut self.live_arrows.iter().copied().filter(|arr| {
-            sphere_visible(planes, arr.end_pos, 1.5 * arr.len)
-        }).collect());
 
         self.max_arrow_inst = visible_arrows.len() as u32;
         if self.max_arrow_inst != 0 {
             gpu.queue.write_buffer(&self.arrows_buf, 0, bytemuck::cast_slice(&visible_arrows));
         }
 
-        let visible_splishes: Vec<Splish> = self.all_splishes.iter().copied().filter(|s| {
-            sphere_visible(planes, (s.center, 0.0).into(), 1.0)
+        let visible_splishes: Vec<Splish> = self.splish_insts.iter().copied().filter(|s| {
+            sphere_visible(planes, (s.center, 0.0).into(), 2.0) // skip streaks are longer
         }).collect();
         self.max_splish_inst = visible_splishes.len() as u32;
         if self.max_splish_inst != 0 {
@@ -527,19 +886,521 @@ impl RenderObject for ArrowController {
     }
 }
 
+// What an arrow does after striking a target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitResponse {
+    Pierce, // carries on through
+    Stop, // is spent where it struck
+    Embed, // sticks where it struck, and follows the target until it breaks
+}
+
+// Where an arrow's path within a step meets a target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TargetHit {
+    pub id: u32, // meaningful to the target that found it
+    pub t: f32, // fraction of the way along the segment
+    pub pos: Vec3,
+    pub normal: Vec3,
+    pub response: HitResponse,
+}
+
+// Anything arrows can hit. ArrowController gathers the hits from every target and the terrain,
+// and applies them in order along the path until one of them stops the arrow.
 pub trait ArrowTarget {
-    fn process_hits(&mut self, audio: Option<&mut AudioManager>, start: Vec3, end: Vec3) -> bool;
+    // live targets the segment from start to end strikes, in any order
+    fn find_hits(&self, start: Vec3, end: Vec3) -> Vec<TargetHit>;
+
+    // called for each hit the arrow gets to with its velocity as it struck, pushing events for it
+    fn apply_hit(&mut self, events: &mut EventQueue, arrow: u32, vel: Vec3, hit: &TargetHit);
+
+    // where the object behind a hit is now, for arrows stuck in it to follow, None once it has broken
+    fn attachment(&self, id: u32) -> Option<(Vec3, Quat)>;
 }
 
-pub fn collide_ray_sphere(start: Vec3, end: Vec3, center: Vec3, radius: f32) -> bool {
+pub fn segment_point_dist_sq(start: Vec3, end: Vec3, point: Vec3) -> f32 {
     let delta = end - start;
-    let proj = delta.dot(center - start) / delta.length_squared();
+    let proj = delta.dot(point - start) / delta.length_squared();
     if proj <= 0.0 {
-        (center - start).length_squared() < radius * radius
+        (point - start).length_squared()
     } else if proj >= 1.0 {
-        (center - end).length_squared() < radius * radius
+        (point - end).length_squared()
     } else {
-        let perp = center - start - proj * delta;
-        perp.length_squared() < radius * radius
+        let perp = point - start - proj * delta;
+        perp.length_squared()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::{intersect_placed, Sphere};
+    use crate::gputil::PlanarImage;
+    use crate::{boat_rail::RailController, camera::ShadowSettings};
+    use crate::{archetypes::ArchetypeSet, levels::{LayoutGenerator, LevelLayout, TargetPlacement}, scoring::ScoreKeeper, targets::TargetController};
+    use half::f16;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const STEP: f64 = 1.0 / 120.0;
+
+    // a ball drifting sideways and turning, which breaks after `hits_left` arrows
+    struct Drifter {
+        pos: Vec3,
+        rot: Quat,
+        hits_left: u32,
+    }
+
+    impl Drifter {
+        fn drift(&mut self) {
+            self.pos.y += 1.5 * STEP as f32;
+            self.rot = Quat::from_rotation_z(0.8 * STEP as f32) * self.rot;
+        }
+    }
+
+    impl ArrowTarget for Drifter {
+        fn find_hits(&self, start: Vec3, end: Vec3) -> Vec<TargetHit> {
+            if self.hits_left == 0 {
+                return Vec::new();
+            }
+            intersect_placed(&Sphere {center: Vec3::ZERO, radius: 0.5}, self.pos, self.rot, start, end).map(|hit| {
+                TargetHit {id: 0, t: hit.t, pos: start.lerp(end, hit.t), normal: hit.normal, response: HitResponse::Embed}
+            }).into_iter().collect()
+        }
+
+        fn apply_hit(&mut self, _events: &mut EventQueue, _arrow: u32, _vel: Vec3, _hit: &TargetHit) {
+            self.hits_left -= 1;
+        }
+
+        fn attachment(&self, _id: u32) -> Option<(Vec3, Quat)> {
+            (self.hits_left > 0).then_some((self.pos, self.rot))
+        }
+    }
+
+    // thin walls across the x axis, each at an x with its own response, recording the hits applied
+    struct Screens {
+        walls: Vec<(f32, HitResponse)>,
+        applied: Vec<u32>,
+    }
+
+    impl ArrowTarget for Screens {
+        fn find_hits(&self, start: Vec3, end: Vec3) -> Vec<TargetHit> {
+            self.walls.iter().enumerate().filter_map(|(id, &(x, response))| {
+                let t = (x - start.x) / (end.x - start.x);
+                (0.0..=1.0).contains(&t).then(|| TargetHit {id: id as u32, t, pos: start.lerp(end, t), normal: Vec3::NEG_X, response})
+            }).collect()
+        }
+
+        fn apply_hit(&mut self, _events: &mut EventQueue, _arrow: u32, _vel: Vec3, hit: &TargetHit) {
+            self.applied.push(hit.id);
+        }
+
+        fn attachment(&self, _id: u32) -> Option<(Vec3, Quat)> {
+            Some((Vec3::ZERO, Quat::IDENTITY))
+        }
+    }
+
+    fn flat_terrain(z: f32) -> HeightmapTerrain {
+        HeightmapTerrain::new(PlanarImage {width: 33, height: 33, data: vec![f16::from_f32(z); 33 * 33].into_boxed_slice()}, 16.5, 1.0)
+    }
+
+    fn fire(arrows: &mut ArrowController, start: Vec3, vel: Vec3) {
+        let dir = vel.normalize();
+        arrows.live_arrows.push(LiveArrow {arrow: Arrow::new(start, dir, 1, MOVING_ARROW_LEN), prev_pos: start, vel, id: arrows.arrows_shot});
+        arrows.arrows_shot += 1;
+    }
+
+    fn fire_at(arrows: &mut ArrowController, target: Vec3) {
+        let start = vec3(0.0, target.y, target.z);
+        fire(arrows, start, 40.0 * (target - start).normalize());
+    }
+
+    // pots from the default archetypes standing at each of `bottoms`
+    fn pots(terrain: &HeightmapTerrain, bottoms: &[Vec3]) -> TargetController {
+        let targets = bottoms.iter().map(|&bottom| TargetPlacement {bottom, rotation: Quat::IDENTITY, archetype: 0, floating: false, wave: 0}).collect();
+        let layout = LayoutGenerator::Fixed(LevelLayout {targets, waves: vec![0.0]});
+        TargetController::new(terrain, ArchetypeSet::default(), layout, &mut StdRng::seed_from_u64(0))
+    }
+
+    // one step with nothing to hit but the terrain
+    fn step(arrows: &mut ArrowController, terrain: &HeightmapTerrain, events: &mut EventQueue, time: &mut f64) {
+        *time += STEP;
+        arrows.tick(*time, terrain, events, &mut []);
+    }
+
+    // steps until no arrow is in flight
+    fn run(arrows: &mut ArrowController, drifter: &mut Drifter, terrain: &HeightmapTerrain, events: &mut EventQueue, time: &mut f64) {
+        for _ in 0..120 {
+            if arrows.live_arrows.is_empty() {
+                return;
+            }
+            *time += STEP;
+            arrows.tick(*time, terrain, events, &mut [drifter]);
+            drifter.drift();
+        }
+        panic!("arrow still flying");
+    }
+
+    #[test]
+    fn embeds_follows_and_falls_away() {
+        let terrain = flat_terrain(-3.0);
+        let mut arrows = ArrowController::new();
+        let mut events = EventQueue::default();
+        let mut drifter = Drifter {pos: vec3(6.0, 0.0, 0.5), rot: Quat::IDENTITY, hits_left: 2};
+        let mut time = 1.0;
+        arrows.tick(time, &terrain, &mut events, &mut [&mut drifter]);
+
+        fire_at(&mut arrows, drifter.pos);
+        run(&mut arrows, &mut drifter, &terrain, &mut events, &mut time);
+        assert_eq!(drifter.hits_left, 1);
+        assert_eq!(arrows.embedded_arrows.len(), 1);
+        let stuck = arrows.embedded_arrows[0].arrow;
+        assert!((stuck.world_end_pos().distance(stuck.parent_pos) - 0.5).abs() < 0.05, "not at the surface: {:?}", stuck);
+        assert!(stuck.world_end_pos().x < drifter.pos.x, "not on the near side: {:?}", stuck);
+
+        // rides along with the target, keeping its place on it
+        for _ in 0..60 {
+            time += STEP;
+            arrows.tick(time, &terrain, &mut events, &mut [&mut drifter]);
+            drifter.drift();
+        }
+        let riding = arrows.embedded_arrows[0].arrow;
+        assert!(riding.parent_pos.y > 0.7, "target didn't move: {:?}", riding.parent_pos);
+        assert_eq!(riding.end_pos, stuck.end_pos);
+        assert!((riding.world_end_pos() - drifter.pos).length() < 0.55);
+        assert!(riding.world_dir().abs_diff_eq(riding.parent_rot * stuck.dir, 1e-6));
+
+        // the breaking arrow is held where it struck for a step, then both fall away and sink
+        fire_at(&mut arrows, drifter.pos);
+        run(&mut arrows, &mut drifter, &terrain, &mut events, &mut time);
+        assert_eq!(drifter.hits_left, 0);
+        assert_eq!(arrows.embedded_arrows.len(), 2);
+        assert!((arrows.embedded_arrows[1].arrow.world_end_pos() - arrows.embedded_arrows[1].arrow.parent_pos).length() < 0.55);
+        time += STEP;
+        arrows.tick(time, &terrain, &mut events, &mut [&mut drifter]);
+        assert!(arrows.embedded_arrows.is_empty());
+        assert_eq!(arrows.sinking_arrows.len(), 2);
+        for _ in 0..1200 {
+            time += STEP;
+            arrows.tick(time, &terrain, &mut events, &mut [&mut drifter]);
+        }
+        assert!(arrows.sinking_arrows.is_empty());
+        assert_eq!(arrows.num_dead_arrows, 2);
+        for i in 0..2 {
+            assert!((arrows.dead_arrows[i].end_pos.z + 3.0).abs() < 1e-3, "{:?}", arrows.dead_arrows[i]);
+        }
+    }
+
+    #[test]
+    fn pierces_fast_and_embeds_slow() {
+        let terrain = flat_terrain(-3.0);
+        let mut targets = pots(&terrain, &[vec3(4.0, 0.0, 0.0), vec3(7.0, 0.0, 0.0)]);
+        let mut arrows = ArrowController::new();
+        let mut events = EventQueue::default();
+        let mut time = 1.0;
+        targets.tick(time, &terrain, &[], None);
+        arrows.tick(time, &terrain, &mut events, &mut [&mut targets]);
+
+        // a fast arrow smashes through both pots, and the second one counts double
+        fire(&mut arrows, vec3(0.0, 0.0, 0.5), vec3(40.0, 0.0, 0.0));
+        while !arrows.live_arrows.is_empty() {
+            time += STEP;
+            arrows.tick(time, &terrain, &mut events, &mut [&mut targets]);
+        }
+        let smashed: Vec<u32> = events.iter().filter_map(|e| match *e {GameEvent::TargetSmashed {id, ..} => Some(id), _ => None}).collect();
+        assert_eq!(smashed, [0, 1]);
+        assert!(arrows.embedded_arrows.is_empty() && arrows.sinking_arrows.is_empty());
+        let mut scoring = ScoreKeeper::default();
+        scoring.process(&[GameEvent::ArrowReleased {arrow: 0, eye: Vec3::ZERO, pos: vec3(0.0, 0.0, 0.5), dir: Vec3::X}]);
+        scoring.process(events.since(0));
+        assert_eq!(scoring.score.points, 100 + 2 * 100);
+
+        // one too slow to go through sticks in the pot it breaks, then falls away with it
+        let mut targets = pots(&terrain, &[vec3(4.0, 0.0, 0.0)]);
+        targets.tick(time, &terrain, &[], None);
+        let mut events = EventQueue::default();
+        fire(&mut arrows, vec3(3.4, 0.0, 0.5), vec3(1.0, 0.0, 0.0));
+        let mut ticks = 0;
+        while !events.iter().any(|e| matches!(e, GameEvent::TargetSmashed {..})) {
+            time += STEP;
+            arrows.tick(time, &terrain, &mut events, &mut [&mut targets]);
+            ticks += 1;
+            assert!(ticks < 60, "never reached the pot");
+        }
+        assert_eq!(arrows.embedded_arrows.len(), 1);
+        assert!(arrows.live_arrows.is_empty());
+        time += STEP;
+        arrows.tick(time, &terrain, &mut events, &mut [&mut targets]);
+        assert!(arrows.embedded_arrows.is_empty());
+        assert_eq!(arrows.sinking_arrows.len(), 1);
+    }
+
+    #[test]
+    fn flies_drops_and_sinks() {
+        let terrain = flat_terrain(-3.0);
+        let mut arrows = ArrowController::new();
+        let mut events = EventQueue::default();
+        let mut time = 1.0;
+        step(&mut arrows, &terrain, &mut events, &mut time);
+
+        // gravity pulls it down and drag slows it, but only a little in air
+        fire(&mut arrows, vec3(-10.0, 0.0, 5.0), vec3(20.0, 0.0, 0.0));
+        for _ in 0..60 {
+            step(&mut arrows, &terrain, &mut events, &mut time);
+        }
+        let live = &arrows.live_arrows[0];
+        assert!((live.arrow.end_pos.z - (5.0 - 0.5 * GRAVITY * 0.25)).abs() < 0.05, "{:?}", live.arrow);
+        assert!((live.vel.z + 0.5 * GRAVITY).abs() < 0.05, "{:?}", live.vel);
+        assert!(live.vel.x < 20.0 && live.vel.x > 19.0, "{:?}", live.vel);
+        assert!(live.arrow.dir.abs_diff_eq(live.vel.normalize(), 1e-6));
+        arrows.live_arrows.clear();
+
+        // going in steeply it loses most of its speed at the surface
+        fire(&mut arrows, vec3(-10.0, 0.0, 0.5), vec3(10.0, 0.0, -20.0));
+        let mut speed = 0.0;
+        while arrows.live_arrows[0].arrow.end_pos.z > 0.0 {
+            speed = arrows.live_arrows[0].vel.length();
+            step(&mut arrows, &terrain, &mut events, &mut time);
+        }
+        let entry_speed = arrows.live_arrows[0].vel.length();
+        assert!((entry_speed / speed - WATER_ENTRY_SPEED_FACTOR).abs() < 0.02, "{} after {}", entry_speed, speed);
+        assert!(events.iter().any(|e| matches!(e, GameEvent::WaterEntry {arrow: 1, ..})));
+
+        // then the water stops it, and it sinks slowly to the bottom
+        for _ in 0..30 {
+            step(&mut arrows, &terrain, &mut events, &mut time);
+        }
+        assert!(arrows.live_arrows[0].vel.length() < 0.4 * entry_speed, "{:?}", arrows.live_arrows[0].vel);
+        let entered_at = arrows.live_arrows[0].arrow.end_pos;
+        let mut ticks = 0;
+        while !arrows.live_arrows.is_empty() {
+            step(&mut arrows, &terrain, &mut events, &mut time);
+            ticks += 1;
+            assert!(ticks < 1200, "never reached the bottom");
+        }
+        assert_eq!(arrows.num_dead_arrows, 1);
+        let dead = arrows.dead_arrows[0];
+        assert!((dead.end_pos.z + 3.0).abs() < 1e-3, "{:?}", dead);
+        assert!(dead.end_pos.xy().distance(entered_at.xy()) < 1.0, "drifted from {:?} to {:?}", entered_at, dead);
+        assert!(events.iter().any(|e| matches!(e, GameEvent::ArrowHitTerrain {arrow: 1, ..})));
+    }
+
+    // the surface event, velocity just before and velocity just after the step where an arrow reaches the surface
+    fn meet_surface(vel: Vec3) -> (GameEvent, Vec3, Vec3) {
+        let terrain = flat_terrain(-3.0);
+        let mut arrows = ArrowController::new();
+        let mut events = EventQueue::default();
+        let mut time = 1.0;
+        step(&mut arrows, &terrain, &mut events, &mut time);
+        fire(&mut arrows, vec3(-10.0, 0.0, 0.2), vel);
+        loop {
+            let before = arrows.live_arrows[0].vel;
+            step(&mut arrows, &terrain, &mut events, &mut time);
+            if let Some(e) = events.iter().find(|e| matches!(e, GameEvent::WaterEntry {..} | GameEvent::ArrowSkipped {..})) {
+                return (*e, before, arrows.live_arrows[0].vel);
+            }
+            assert!(time < 3.0, "never reached the surface");
+        }
+    }
+
+    #[test]
+    fn skips_or_goes_in() {
+        // grazing and fast, it bounces off and stays above the water
+        let (event, before, after) = meet_surface(vec3(30.0, 0.0, -2.0));
+        assert!(matches!(event, GameEvent::ArrowSkipped {arrow: 0, ..}), "{:?}", event);
+        assert!((-before.z / before.length()).asin() < SKIP_ANGLE);
+        assert!((after.x - SKIP_SPEED_FACTOR * before.x).abs() < 0.1, "{:?} after {:?}", after, before);
+        assert!((after.z + SKIP_RESTITUTION * before.z).abs() < 0.1, "{:?} after {:?}", after, before);
+
+        // steeper, or too slow to skip, it goes in and is turned a little towards the surface
+        for vel in [vec3(30.0, 0.0, -6.0), vec3(10.0, 0.0, -0.5)] {
+            let (event, before, after) = meet_surface(vel);
+            assert!(matches!(event, GameEvent::WaterEntry {arrow: 0, ..}), "{:?}", event);
+            let grazing = (-before.z / before.length()).asin();
+            let angle = (-after.z / after.length()).asin();
+            let expected = grazing - ENTRY_DEFLECTION * grazing.cos().powi(2);
+            assert!((angle - expected).abs() < 0.01, "{} from {}, expected {}", angle, grazing, expected);
+            assert!(angle < grazing);
+            assert!((after.length() / before.length() - WATER_ENTRY_SPEED_FACTOR).abs() < 0.02);
+        }
+    }
+
+    fn rail() -> RailController {
+        RailController::new(ShadowSettings {sun_dir: Vec3::Z, range_xy: 60.0, range_z: 10.0})
+    }
+
+    // holds the bow for `secs`, returning the speed of the arrow let go
+    fn draw_and_release(arrows: &mut ArrowController, camera: &RailController, events: &mut EventQueue, time: &mut f64, secs: f64) -> Option<f32> {
+        let terrain = flat_terrain(-3.0);
+        let shot = arrows.arrows_shot;
+        arrows.start_draw();
+        let end = *time + secs;
+        while *time < end - 1e-9 {
+            step(arrows, &terrain, events, time);
+        }
+        arrows.release(events, camera);
+        (arrows.arrows_shot > shot).then(|| arrows.live_arrows.last().unwrap().vel.length())
+    }
+
+    #[test]
+    fn draw_sets_speed() {
+        let terrain = flat_terrain(-3.0);
+        let camera = rail();
+        let mut arrows = ArrowController::new();
+        let mut events = EventQueue::default();
+        let mut time = 1.0;
+        step(&mut arrows, &terrain, &mut events, &mut time);
+        assert_eq!(arrows.draw_strength(), None);
+
+        let quick = draw_and_release(&mut arrows, &camera, &mut events, &mut time, 0.0).unwrap();
+        assert!((quick - MIN_ARROW_SPEED).abs() < 1e-3, "{}", quick);
+        let half = draw_and_release(&mut arrows, &camera, &mut events, &mut time, FULL_DRAW_TIME / 2.0).unwrap();
+        assert!((half - MIN_ARROW_SPEED.lerp(ARROW_SPEED, 0.5)).abs() < 0.1, "{}", half);
+        let full = draw_and_release(&mut arrows, &camera, &mut events, &mut time, FULL_DRAW_TIME).unwrap();
+        let held = draw_and_release(&mut arrows, &camera, &mut events, &mut time, 3.0 * FULL_DRAW_TIME).unwrap();
+        assert!((full - ARROW_SPEED).abs() < 1e-3 && (held - ARROW_SPEED).abs() < 1e-3, "{} {}", full, held);
+        assert_eq!(arrows.draw_strength(), None);
+        let released = events.iter().filter(|e| matches!(e, GameEvent::ArrowReleased {..})).count();
+        assert_eq!(released, 4);
+
+        // letting go of the string fires nothing, and releasing at rest does nothing either
+        arrows.start_draw();
+        step(&mut arrows, &terrain, &mut events, &mut time);
+        assert!(arrows.draw_strength().unwrap() > 0.0);
+        arrows.cancel_draw();
+        assert_eq!(arrows.draw_strength(), None);
+        arrows.release(&mut events, &camera);
+        assert_eq!(arrows.arrows_shot, 4);
+        assert_eq!(events.iter().filter(|e| matches!(e, GameEvent::ArrowReleased {..})).count(), released);
+    }
+
+    #[test]
+    fn sways_after_full_draw() {
+        let terrain = flat_terrain(-3.0);
+        let mut arrows = ArrowController::new();
+        let mut events = EventQueue::default();
+        let mut time = 1.0;
+        step(&mut arrows, &terrain, &mut events, &mut time);
+        assert_eq!(arrows.aim_sway(), Vec2::ZERO);
+
+        arrows.start_draw();
+        let drawn_at = time + FULL_DRAW_TIME;
+        while time < drawn_at - 1e-9 {
+            assert_eq!(arrows.aim_sway(), Vec2::ZERO);
+            step(&mut arrows, &terrain, &mut events, &mut time);
+        }
+        assert_eq!(arrows.draw_strength(), Some(1.0));
+        let mut max_sway: f32 = 0.0;
+        for _ in 0..(4.0 * SWAY_RAMP_TIME / STEP) as usize {
+            step(&mut arrows, &terrain, &mut events, &mut time);
+            let sway = arrows.aim_sway();
+            assert!(sway.x.abs() <= MAX_SWAY_DEG && sway.y.abs() <= MAX_SWAY_DEG, "{:?}", sway);
+            max_sway = max_sway.max(sway.length());
+        }
+        assert!(max_sway > 0.5 * MAX_SWAY_DEG, "{}", max_sway);
+        arrows.cancel_draw();
+        assert_eq!(arrows.aim_sway(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn earliest_hit_wins() {
+        let terrain = flat_terrain(1.0);
+        let mut arrows = ArrowController::new();
+        let mut events = EventQueue::default();
+        let mut near = Screens {walls: vec![(5.0, HitResponse::Stop), (2.0, HitResponse::Pierce)], applied: vec![]};
+        let mut far = Screens {walls: vec![(6.0, HitResponse::Stop), (3.5, HitResponse::Pierce)], applied: vec![]};
+        arrows.tick(1.0, &terrain, &mut events, &mut [&mut near, &mut far]);
+
+        // one long step crosses every wall, it goes through those that let it and stops at the first that doesn't
+        fire(&mut arrows, vec3(0.0, 0.0, 5.0), vec3(40.0, 0.0, 0.0));
+        arrows.tick(1.25, &terrain, &mut events, &mut [&mut near, &mut far]);
+        assert_eq!(near.applied, [1, 0]);
+        assert_eq!(far.applied, [1]);
+        assert!(arrows.live_arrows.is_empty() && arrows.embedded_arrows.is_empty());
+        assert_eq!(arrows.num_dead_arrows, 0);
+        let spent = events.iter().find_map(|e| match *e {GameEvent::ArrowSpent {arrow: 0, pos} => Some(pos), _ => None}).unwrap();
+        assert!((spent.x - 5.0).abs() < 1e-3, "{:?}", spent);
+
+        // the terrain in the way hides anything behind it
+        near.applied.clear();
+        far.applied.clear();
+        near.walls = vec![(1.0, HitResponse::Pierce), (4.0, HitResponse::Stop)];
+        far.walls = vec![(4.5, HitResponse::Embed)];
+        fire(&mut arrows, vec3(0.0, 0.0, 3.0), vec3(20.0, 0.0, -10.0));
+        arrows.tick(1.5, &terrain, &mut events, &mut [&mut near, &mut far]);
+        assert_eq!(near.applied, [0]);
+        assert!(far.applied.is_empty());
+        assert!(events.iter().any(|e| matches!(e, GameEvent::ArrowHitTerrain {arrow: 1, ..})));
+        assert_eq!(arrows.num_dead_arrows, 1);
+        let buried = arrows.dead_arrows[0].end_pos;
+        assert!((buried.z - 1.0).abs() < 1e-3 && buried.x > 1.0 && buried.x < 4.0, "{:?}", buried);
+    }
+
+    #[test]
+    fn quiver_reloads_refunds_and_refills() {
+        let terrain = flat_terrain(-3.0);
+        let camera = rail();
+        let mut targets = pots(&terrain, &[vec3(4.0, 0.0, 0.0), vec3(7.0, 0.0, 0.0), vec3(10.0, 0.0, 0.0)]);
+        let mut arrows = ArrowController::new();
+        let mut events = EventQueue::default();
+        let rules = QuiverRules {arrows_per_lap: 3, reload_time: 0.5, refund_on_hit: true};
+        arrows.reset(Some(rules));
+        let mut time = 1.0;
+        targets.tick(time, &terrain, &[], None);
+        arrows.tick(time, &terrain, &mut events, &mut [&mut targets]);
+        assert_eq!(arrows.quiver_status(), Some(QuiverStatus {arrows: 3, capacity: 3, reload: 1.0}));
+
+        // the next arrow can't be drawn until it is nocked
+        assert!(draw_and_release(&mut arrows, &camera, &mut events, &mut time, 0.0).is_some());
+        arrows.live_arrows.clear();
+        assert!(!arrows.can_shoot());
+        assert_eq!(arrows.quiver_status().unwrap().reload, 0.0);
+        assert!(draw_and_release(&mut arrows, &camera, &mut events, &mut time, 0.25).is_none());
+        assert!((arrows.quiver_status().unwrap().reload - 0.5).abs() < 0.02);
+        arrows.start_draw();
+        assert_eq!(arrows.draw_strength(), Some(0.0));
+        arrows.cancel_draw();
+        let speed = draw_and_release(&mut arrows, &camera, &mut events, &mut time, 0.3).unwrap();
+        assert!(speed < MIN_ARROW_SPEED.lerp(ARROW_SPEED, 0.1), "draw counted before nocking: {}", speed);
+        arrows.live_arrows.clear();
+        assert_eq!(arrows.quiver_status().unwrap().arrows, 1);
+
+        // smashing three pots with one arrow puts back no more than a full quiver
+        fire(&mut arrows, vec3(0.0, 0.0, 0.5), vec3(40.0, 0.0, 0.0));
+        while !arrows.live_arrows.is_empty() {
+            time += STEP;
+            arrows.tick(time, &terrain, &mut events, &mut [&mut targets]);
+        }
+        assert_eq!(events.iter().filter(|e| matches!(e, GameEvent::TargetSmashed {..})).count(), 3);
+        assert_eq!(arrows.quiver_status().unwrap().arrows, 3);
+
+        // an empty quiver can't be drawn until it is refilled for the next lap
+        for _ in 0..3 {
+            assert!(draw_and_release(&mut arrows, &camera, &mut events, &mut time, rules.reload_time + STEP).is_some());
+            arrows.live_arrows.clear();
+        }
+        assert_eq!(arrows.quiver_status().unwrap().arrows, 0);
+        assert!(draw_and_release(&mut arrows, &camera, &mut events, &mut time, rules.reload_time + STEP).is_none());
+        assert_eq!(arrows.draw_strength(), None);
+        arrows.refill_quiver();
+        assert_eq!(arrows.quiver_status().unwrap().arrows, 3);
+        assert!(arrows.can_shoot());
+
+        // without refunds hits don't put arrows back
+        arrows.reset(Some(QuiverRules {refund_on_hit: false, ..rules}));
+        let mut targets = pots(&terrain, &[vec3(4.0, 0.0, 0.0)]);
+        targets.tick(time, &terrain, &[], None);
+        arrows.tick(time, &terrain, &mut events, &mut [&mut targets]);
+        assert!(draw_and_release(&mut arrows, &camera, &mut events, &mut time, 0.0).is_some());
+        arrows.live_arrows.clear();
+        fire(&mut arrows, vec3(0.0, 0.0, 0.5), vec3(40.0, 0.0, 0.0));
+        while !arrows.live_arrows.is_empty() {
+            time += STEP;
+            arrows.tick(time, &terrain, &mut events, &mut [&mut targets]);
+        }
+        assert_eq!(events.iter().filter(|e| matches!(e, GameEvent::TargetSmashed {..})).count(), 4);
+        assert_eq!(arrows.quiver_status().unwrap().arrows, 2);
+
+        arrows.reset(None);
+        assert_eq!(arrows.quiver_status(), None);
+        assert!(arrows.can_shoot());
     }
 }
@@ -5,9 +5,10 @@ use std::{io::Cursor, path::Path};
 #[cfg(not(target_arch = "wasm32"))]
 use kira::sound::streaming::{StreamingSoundHandle, StreamingSoundData, StreamingSoundSettings};
 use kira::{Volume, sound::{EndPosition, FromFileError, PlaybackPosition, Region, SoundData, static_sound::{StaticSoundData, StaticSoundSettings}}};
-use rand::Rng;
+use kira::manager::AudioManager;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-use crate::gputil::AssetSource;
+use crate::{archetypes::ArchetypeSet, events::GameEvent, gputil::AssetSource, ui::GameState};
 
 pub fn load_static_sound(source: &impl AssetSource, path: impl AsRef<Path>, volume_db: f64) -> Result<StaticSoundData, FromFileError> {
     let bytes = source.get_bytes(path.as_ref()).map_err(FromFileError::IoError)?;
@@ -83,8 +84,13 @@ impl SoundAtlas {
         self.sound.with_settings(self.sound.settings.playback_region(Region {start, end}))
     }
 
-    pub fn random_sound(&self) -> StaticSoundData {
-        let n = rand::thread_rng().gen_range(0..self.start_positions.len());
+    pub fn with_playback_rate(self, rate: f64) -> Self {
+        let settings = self.sound.settings.playback_rate(rate);
+        SoundAtlas {sound: self.sound.with_settings(settings), ..self}
+    }
+
+    pub fn random_sound(&self, rng: &mut impl Rng) -> StaticSoundData {
+        let n = rng.random_range(0..self.start_positions.len());
         self.get_sound(n)
     }
 
@@ -110,3 +116,64 @@ impl SoundAtlas {
     }
 }
 
+
+// Plays the sound effects for gameplay events.
+// Variations are picked with their own generator seeded from the run, so the same seed gives the same sounds
+// and sound never affects the simulation.
+pub struct GameSounds {
+    release_sounds: SoundAtlas,
+    splish_sounds: SoundAtlas,
+    skip_sounds: SoundAtlas,
+    thunk_sounds: SoundAtlas,
+    smash_sounds: Vec<Option<SoundAtlas>>, // for each target archetype, None if its sound failed to load
+    rng: StdRng,
+}
+
+// keeps the sound generator apart from the simulation's, which is seeded with the plain run seed
+const SOUND_SEED_SALT: u64 = 0x5eed_50d5 << 32;
+
+impl GameSounds {
+    pub fn new(assets: &impl AssetSource, archetypes: &ArchetypeSet) -> Self {
+        let release_sounds = SoundAtlas::load_with_stride(assets, "arrow_release.ogg", 5.0, 0.4).unwrap();
+        let thunk_sounds = SoundAtlas::load_with_stride(assets, "arrow_thunk.ogg", -3.0, 0.5).unwrap();
+        let splish_sounds = SoundAtlas::load_with_stride(assets, "water_splish.ogg", -2.0, 1.0).unwrap();
+        // a quicker, quieter slap than going in
+        let skip_sounds = SoundAtlas::load_with_stride(assets, "water_splish.ogg", -8.0, 1.0).unwrap().with_playback_rate(1.8);
+        // designers may name any file, so a missing one is quiet rather than fatal
+        let smash_sounds = archetypes.archetypes.iter().map(|a| {
+            SoundAtlas::load_with_starts(assets, &a.sound.file, a.sound.volume_db, &a.sound.starts)
+                .inspect_err(|e| log::error!("could not load {} for {}: {}", a.sound.file, a.name, e))
+                .ok()
+        }).collect();
+
+        GameSounds { release_sounds, splish_sounds, skip_sounds, thunk_sounds, smash_sounds, rng: StdRng::seed_from_u64(SOUND_SEED_SALT) }
+    }
+
+    // called with the run seed whenever the simulation restarts or resets its world
+    pub fn reseed(&mut self, seed: u32) {
+        self.rng = StdRng::seed_from_u64(SOUND_SEED_SALT ^ seed as u64);
+    }
+
+    // `seed` is that of the current run, picked up again when a run starts
+    pub fn play_events(&mut self, audio: &mut AudioManager, events: &[GameEvent], seed: u32) {
+        for event in events {
+            let atlas = match event {
+                GameEvent::ArrowReleased {..} => &self.release_sounds,
+                GameEvent::ArrowHitTerrain {..} | GameEvent::FishHit {..} | GameEvent::TargetCracked {..} => &self.thunk_sounds,
+                GameEvent::WaterEntry {..} => &self.splish_sounds,
+                GameEvent::ArrowSkipped {..} => &self.skip_sounds,
+                GameEvent::TargetSmashed { archetype, .. } => match self.smash_sounds.get(*archetype as usize) {
+                    Some(Some(atlas)) => atlas,
+                    _ => continue,
+                },
+                // the world is reset as the countdown starts
+                GameEvent::StateChanged { new: GameState::Countdown {..}, .. } => {
+                    self.reseed(seed);
+                    continue;
+                }
+                GameEvent::ArrowSpent {..} | GameEvent::StateChanged {..} => continue,
+            };
+            let _ = audio.play(atlas.random_sound(&mut self.rng));
+        }
+    }
+}
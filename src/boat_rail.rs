@@ -1,8 +1,7 @@
 use glam::*;
 use std::{f32::consts::TAU, ops::{Add, Mul, Sub}};
-use web_time::Instant;
 
-use crate::{camera::{Camera, CameraController, ShadowSettings}, ui::GameState};
+use crate::{camera::{Camera, CameraController, ShadowSettings}, game_mode::RAIL_PERIOD, settings::Settings};
 
 // use the compiler to parse static csv
 const RAIL_CSV: &[f32] = &include!("rail-path.csv");
@@ -51,117 +50,224 @@ fn rail_points() -> LoopedRail<Vec2> {
     LoopedRail {points}
 }
 
+#[derive(Clone, Copy, Debug)]
+struct RailPose {
+    time: f64,
+    pitch: f32,
+    yaw: f32,
+    zoom: f32, // 0 for the normal view, 1 fully zoomed
+    sway: Vec2, // yaw and pitch in degrees added by the bow at full draw
+}
+
+impl RailPose {
+    // field of view as a fraction of the unzoomed one
+    fn fov_scale(self) -> f32 {
+        let z = self.zoom * self.zoom * (3.0 - 2.0 * self.zoom); // smoothstep
+        1.0.lerp(ZOOM_FOV_SCALE, z)
+    }
+
+    fn lerp(self, next: RailPose, alpha: f32) -> RailPose {
+        // take the short way around when yaw wraps
+        let yaw_delta = (next.yaw - self.yaw + 180.0).rem_euclid(360.0) - 180.0;
+        RailPose {
+            time: self.time + (next.time - self.time) * alpha as f64,
+            pitch: self.pitch.lerp(next.pitch, alpha),
+            yaw: self.yaw + yaw_delta * alpha,
+            zoom: self.zoom.lerp(next.zoom, alpha),
+            sway: self.sway.lerp(next.sway, alpha),
+        }
+    }
+}
+
 pub struct RailController {
     shadow_settings: ShadowSettings,
     rail: LoopedRail<Vec2>,
     period: f64,
     pub current_time: f64,
+    finished_at: Option<f64>, // the view turns back to the rail direction after the finish
     pitch: f32,
     yaw: f32,
-    updated_at: Instant,
+    zoom: f32,
+    zoom_held: bool,
+    sway: Vec2,
+    fov_y_deg: f32,
+    prev_pose: RailPose,
     mouse_accum: DVec2,
 }
 
 const EYE_HEIGHT: f32 = 2.0;
-const FOV_Y_DEG: f32 = 60.0;
+const ZOOM_FOV_SCALE: f32 = 0.5;
+const ZOOM_TIME: f64 = 0.2; // seconds to zoom fully in or out
 const CLIP_NEAR: f32 = 0.1;
 const MAX_PITCH: f32 = 88.0;
 const ROT_SPEED: f32 = 0.05;
 
 impl CameraController for RailController {
-    fn camera(&self, fb_size: Vec2, water_fb_size: Vec2) -> crate::camera::Camera {
-        let eye = self.eye();
-        let aspect_ratio = fb_size.x / fb_size.y;
-        let mat = Mat4::perspective_infinite_reverse_rh(FOV_Y_DEG.to_radians(), aspect_ratio, CLIP_NEAR)
-            * Mat4::look_to_rh(eye, self.look_dir(), Vec3::new(0.0, 0.0, 1.0));
-        if mat.determinant() == 0.0 {
-            panic!("Singular camera matrix: {:?}", mat);
-        }
-
-        let shadow_skew = self.shadow_settings.sun_dir.xy() / self.shadow_settings.sun_dir.z;
-        let norm_sun = self.shadow_settings.sun_dir.normalize();
-        let sin_above = norm_sun.xy().length();
-        let cos_below = (1.0 - sin_above * sin_above / 1.7689).sqrt();
-        let refr_sun_dir = vec3(norm_sun.x/1.33, norm_sun.y/1.33, cos_below);
-        let shadow_depth_corr = (norm_sun.z * refr_sun_dir.xy().length()) / (refr_sun_dir.z * norm_sun.xy().length());
-        
-        Camera {
-            matrix: mat,
-            inv_matrix: mat.inverse(),
-            eye: eye,
-            clip_near: CLIP_NEAR,
-            fb_size, water_fb_size,
-            shadow_skew,
-            shadow_range_xy: self.shadow_settings.range_xy,
-            shadow_range_z: self.shadow_settings.range_z,
-            shadow_depth_corr,
-            time_s: self.current_time as f32,
-            pad: Vec2::ZERO,
-        }
+    fn camera(&self, fb_size: Vec2, water_fb_size: Vec2) -> Camera {
+        self.camera_at(self.pose(), fb_size, water_fb_size)
     }
 
     fn eye(&self) -> Vec3 {
-        let xy = self.rail.sample(self.current_time / self.period);
-        vec3(xy.x, xy.y, EYE_HEIGHT)
+        self.eye_at(self.pose())
     }
 
     fn look_dir(&self) -> Vec3 {
-        let yaw_rad = ((self.yaw + 180.0).rem_euclid(360.0) - 180.0).to_radians();
-        let pitch_rad = self.pitch.to_radians();
-        let rail_xy = self.rail.sample_dir(self.current_time / self.period, 2.0).normalize();
+        self.look_dir_at(self.pose())
+    }
+}
 
-        let dir_fac = if self.current_time > self.period {
-            let dt = (self.current_time - self.period).min(10.0) / 10.0;
-            (1.0 - dt * dt * (3.0 - 2.0 * dt)) as f32 // smoothstep
-        } else {
-            1.0
-        };
-        let xy = Vec2::from_angle(yaw_rad * dir_fac).rotate(rail_xy);
-        let rz = Vec2::from_angle(pitch_rad * dir_fac);
-        Vec3::new(xy.x * rz.x, xy.y * rz.x, rz.y)
+// Rail camera blended between the previous and current simulation steps for rendering.
+pub struct InterpolatedRail<'a> {
+    rail: &'a RailController,
+    pose: RailPose,
+}
+
+impl CameraController for InterpolatedRail<'_> {
+    fn camera(&self, fb_size: Vec2, water_fb_size: Vec2) -> Camera {
+        self.rail.camera_at(self.pose, fb_size, water_fb_size)
+    }
+
+    fn eye(&self) -> Vec3 {
+        self.rail.eye_at(self.pose)
+    }
+
+    fn look_dir(&self) -> Vec3 {
+        self.rail.look_dir_at(self.pose)
     }
 }
 
 impl RailController {
-    pub fn new(shadow_settings: ShadowSettings, now: Instant) -> Self {
+    pub fn new(shadow_settings: ShadowSettings) -> Self {
         let rail = rail_points();
         log::info!("path has {} points", rail.points.len());
 
         RailController {
             shadow_settings,
             rail,
-            period: GameState::GAME_PERIOD,
+            period: RAIL_PERIOD,
+            finished_at: None,
             pitch: 0.0,
             yaw: 00.0,
+            zoom: 0.0,
+            zoom_held: false,
+            sway: Vec2::ZERO,
+            fov_y_deg: Settings::default().fov_y_deg,
             current_time: 0.0,
-            updated_at: now,
+            prev_pose: RailPose { time: 0.0, pitch: 0.0, yaw: 0.0, zoom: 0.0, sway: Vec2::ZERO },
             mouse_accum: DVec2::ZERO,
         }
     }
 
-    pub fn reset(&mut self, now: Instant, start_time: f64) {
-        self.updated_at = now;
+    pub fn reset(&mut self, start_time: f64) {
         self.current_time = start_time;
+        self.finished_at = None;
         self.yaw = 0.0;
         self.pitch = 0.0;
+        self.zoom = 0.0;
+        self.zoom_held = false;
+        self.sway = Vec2::ZERO;
         self.mouse_accum = DVec2::ZERO;
-    }
-
-    pub fn unpause(&mut self, now: Instant) {
-        self.updated_at = now
+        self.prev_pose = self.pose();
     }
 
     pub fn mouse(&mut self, dx: f64, dy: f64) {
         self.mouse_accum += dvec2(dx, dy);
     }
 
-    pub fn tick(&mut self, now: Instant) -> f64 {
-        let delta_t = (now - self.updated_at).as_secs_f64();
+    // only changes the view, so that runs play out the same at any field of view
+    pub fn set_fov(&mut self, fov_y_deg: f32) {
+        self.fov_y_deg = fov_y_deg;
+    }
+
+    pub fn finish(&mut self) {
+        self.finished_at = Some(self.current_time);
+    }
+
+    pub fn set_zoom(&mut self, held: bool) {
+        self.zoom_held = held;
+    }
+
+    // offsets the view from where the player is aiming, in degrees of yaw and pitch
+    pub fn set_sway(&mut self, sway: Vec2) {
+        self.sway = sway;
+    }
+
+    // advance by one fixed simulation step
+    pub fn step(&mut self, delta_t: f64) -> f64 {
+        self.prev_pose = self.pose();
         self.current_time += delta_t;
-        self.updated_at = now;
-        self.yaw = (self.yaw - ROT_SPEED * self.mouse_accum.x as f32) % 360.0;
-        self.pitch = (self.pitch - ROT_SPEED * self.mouse_accum.y as f32).clamp(-MAX_PITCH, MAX_PITCH);
+        let zoom_delta = (delta_t / ZOOM_TIME) as f32;
+        self.zoom = if self.zoom_held {(self.zoom + zoom_delta).min(1.0)} else {(self.zoom - zoom_delta).max(0.0)};
+        // keep the same on-screen speed when zoomed
+        let rot_speed = ROT_SPEED * self.pose().fov_scale();
+        self.yaw = (self.yaw - rot_speed * self.mouse_accum.x as f32) % 360.0;
+        self.pitch = (self.pitch - rot_speed * self.mouse_accum.y as f32).clamp(-MAX_PITCH, MAX_PITCH);
         self.mouse_accum = DVec2::ZERO;
         self.current_time
     }
+
+    // hold the camera still, so that interpolation does not replay the last step while paused
+    pub fn hold(&mut self) {
+        self.prev_pose = self.pose();
+    }
+
+    pub fn interpolated(&self, alpha: f32) -> InterpolatedRail<'_> {
+        InterpolatedRail { rail: self, pose: self.prev_pose.lerp(self.pose(), alpha) }
+    }
+
+    fn pose(&self) -> RailPose {
+        RailPose { time: self.current_time, pitch: self.pitch, yaw: self.yaw, zoom: self.zoom, sway: self.sway }
+    }
+
+    fn camera_at(&self, pose: RailPose, fb_size: Vec2, water_fb_size: Vec2) -> Camera {
+        let eye = self.eye_at(pose);
+        let aspect_ratio = fb_size.x / fb_size.y;
+        let mat = Mat4::perspective_infinite_reverse_rh((self.fov_y_deg * pose.fov_scale()).to_radians(), aspect_ratio, CLIP_NEAR)
+            * Mat4::look_to_rh(eye, self.look_dir_at(pose), Vec3::new(0.0, 0.0, 1.0));
+        if mat.determinant() == 0.0 {
+            panic!("Singular camera matrix: {:?}", mat);
+        }
+
+        let shadow_skew = self.shadow_settings.sun_dir.xy() / self.shadow_settings.sun_dir.z;
+        let norm_sun = self.shadow_settings.sun_dir.normalize();
+        let sin_above = norm_sun.xy().length();
+        let cos_below = (1.0 - sin_above * sin_above / 1.7689).sqrt();
+        let refr_sun_dir = vec3(norm_sun.x/1.33, norm_sun.y/1.33, cos_below);
+        let shadow_depth_corr = (norm_sun.z * refr_sun_dir.xy().length()) / (refr_sun_dir.z * norm_sun.xy().length());
+        
+        Camera {
+            matrix: mat,
+            inv_matrix: mat.inverse(),
+            eye: eye,
+            clip_near: CLIP_NEAR,
+            fb_size, water_fb_size,
+            shadow_skew,
+            shadow_range_xy: self.shadow_settings.range_xy,
+            shadow_range_z: self.shadow_settings.range_z,
+            shadow_depth_corr,
+            time_s: pose.time as f32,
+            pad: Vec2::ZERO,
+        }
+    }
+
+    fn eye_at(&self, pose: RailPose) -> Vec3 {
+        let xy = self.rail.sample(pose.time / self.period);
+        vec3(xy.x, xy.y, EYE_HEIGHT)
+    }
+
+    fn look_dir_at(&self, pose: RailPose) -> Vec3 {
+        let yaw_rad = ((pose.yaw + pose.sway.x + 180.0).rem_euclid(360.0) - 180.0).to_radians();
+        let pitch_rad = (pose.pitch + pose.sway.y).to_radians();
+        let rail_xy = self.rail.sample_dir(pose.time / self.period, 2.0).normalize();
+
+        let dir_fac = if let Some(finished_at) = self.finished_at.filter(|t| pose.time > *t) {
+            let dt = (pose.time - finished_at).min(10.0) / 10.0;
+            (1.0 - dt * dt * (3.0 - 2.0 * dt)) as f32 // smoothstep
+        } else {
+            1.0
+        };
+        let xy = Vec2::from_angle(yaw_rad * dir_fac).rotate(rail_xy);
+        let rz = Vec2::from_angle(pitch_rad * dir_fac);
+        Vec3::new(xy.x * rz.x, xy.y * rz.x, rz.y)
+    }
 }
@@ -0,0 +1,156 @@
+use std::cell::Cell;
+use web_time::{Duration, Instant};
+
+// Source of wall-clock time for the main loop.
+// Only the stepper reads this, game logic sees time in whole simulation steps.
+pub trait GameClock {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl GameClock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// Clock which only moves when told to, for driving the game loop from tests and tools.
+pub struct ManualClock {
+    now: Cell<Instant>,
+}
+
+impl ManualClock {
+    pub fn new(start: Instant) -> Self {
+        ManualClock { now: Cell::new(start) }
+    }
+
+    pub fn advance(&self, delta: Duration) {
+        self.now.set(self.now.get() + delta);
+    }
+}
+
+impl GameClock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+// Converts variable frame times into a whole number of fixed-length simulation steps.
+// The leftover fraction of a step is used to interpolate between the last two simulated states when rendering.
+pub struct FixedStepper {
+    last_frame: Instant,
+    accum: Duration,
+}
+
+impl FixedStepper {
+    pub const STEP: Duration = Duration::from_nanos(1_000_000_000 / 120);
+    pub const STEP_SECS: f64 = Self::STEP.as_secs_f64();
+    // drop any time beyond this after a hitch instead of trying to catch up
+    pub const MAX_STEPS_PER_FRAME: u32 = 12;
+
+    pub fn new(now: Instant) -> Self {
+        FixedStepper { last_frame: now, accum: Duration::ZERO }
+    }
+
+    // returns the number of steps to simulate for this frame
+    pub fn advance(&mut self, now: Instant) -> u32 {
+        self.accum += now.saturating_duration_since(self.last_frame);
+        self.last_frame = now;
+
+        let num_steps = (self.accum.as_nanos() / Self::STEP.as_nanos()) as u32;
+        if num_steps > Self::MAX_STEPS_PER_FRAME {
+            self.accum = Duration::ZERO;
+            Self::MAX_STEPS_PER_FRAME
+        } else {
+            self.accum -= Self::STEP * num_steps;
+            num_steps
+        }
+    }
+
+    // fraction of a step elapsed since the last simulated state
+    pub fn alpha(&self) -> f32 {
+        (self.accum.as_secs_f64() / Self::STEP_SECS) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: Duration = FixedStepper::STEP;
+
+    fn setup() -> (ManualClock, FixedStepper) {
+        let clock = ManualClock::new(Instant::now());
+        let stepper = FixedStepper::new(clock.now());
+        (clock, stepper)
+    }
+
+    #[test]
+    fn counts_whole_steps() {
+        let (clock, mut stepper) = setup();
+        assert_eq!(stepper.advance(clock.now()), 0);
+        clock.advance(STEP);
+        assert_eq!(stepper.advance(clock.now()), 1);
+        clock.advance(STEP * 5);
+        assert_eq!(stepper.advance(clock.now()), 5);
+        assert_eq!(stepper.alpha(), 0.0);
+        clock.advance(STEP - Duration::from_nanos(1));
+        assert_eq!(stepper.advance(clock.now()), 0);
+    }
+
+    #[test]
+    fn carries_over_the_remainder() {
+        let (clock, mut stepper) = setup();
+        // 60 Hz frames run two steps each, 144 Hz frames step on five out of six
+        let mut total = 0;
+        for _ in 0..60 {
+            clock.advance(STEP * 2);
+            total += stepper.advance(clock.now());
+        }
+        assert_eq!(total, 120);
+
+        let frame = STEP * 5 / 6 + Duration::from_nanos(1); // rounded up so 12 frames reach 10 steps
+        let mut steps = Vec::new();
+        for _ in 0..12 {
+            clock.advance(frame);
+            steps.push(stepper.advance(clock.now()));
+            let alpha = stepper.alpha();
+            assert!((0.0..1.0).contains(&alpha), "alpha {}", alpha);
+        }
+        assert_eq!(steps.iter().sum::<u32>(), 10);
+        assert!(steps.iter().all(|&n| n <= 1));
+
+        // half a step left over shows up in alpha
+        let (clock, mut stepper) = setup();
+        clock.advance(STEP * 3 + STEP / 2);
+        assert_eq!(stepper.advance(clock.now()), 3);
+        assert!((stepper.alpha() - 0.5).abs() < 1e-4);
+        clock.advance(STEP - STEP / 2);
+        assert_eq!(stepper.advance(clock.now()), 1);
+        assert!(stepper.alpha() < 1e-4);
+    }
+
+    #[test]
+    fn caps_catch_up() {
+        let (clock, mut stepper) = setup();
+        clock.advance(STEP * FixedStepper::MAX_STEPS_PER_FRAME);
+        assert_eq!(stepper.advance(clock.now()), FixedStepper::MAX_STEPS_PER_FRAME);
+
+        // a hitch is dropped rather than simulated over the following frames
+        clock.advance(Duration::from_secs(3) + STEP / 2);
+        assert_eq!(stepper.advance(clock.now()), FixedStepper::MAX_STEPS_PER_FRAME);
+        assert_eq!(stepper.alpha(), 0.0);
+        clock.advance(STEP);
+        assert_eq!(stepper.advance(clock.now()), 1);
+    }
+
+    #[test]
+    fn ignores_time_going_backwards() {
+        let clock = ManualClock::new(Instant::now());
+        let mut stepper = FixedStepper::new(clock.now() + STEP * 4);
+        assert_eq!(stepper.advance(clock.now()), 0);
+        clock.advance(STEP);
+        assert_eq!(stepper.advance(clock.now()), 1);
+    }
+}
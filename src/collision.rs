@@ -0,0 +1,381 @@
+use glam::*;
+
+use crate::arrows::segment_point_dist_sq;
+
+// Where a segment first meets a shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapeHit {
+    pub t: f32, // fraction of the way along the segment, 0 if it starts inside a solid
+    pub normal: Vec3, // facing back along the segment
+}
+
+// Solid shapes for hit testing arrows against targets.
+pub trait Shape {
+    fn intersect_segment(&self, start: Vec3, end: Vec3) -> Option<ShapeHit>;
+}
+
+// tests a shape modelled around the origin, placed at `origin` with `rotation`
+pub fn intersect_placed(shape: &impl Shape, origin: Vec3, rotation: Quat, start: Vec3, end: Vec3) -> Option<ShapeHit> {
+    let to_local = rotation.inverse();
+    let hit = shape.intersect_segment(to_local * (start - origin), to_local * (end - origin))?;
+    Some(ShapeHit {t: hit.t, normal: rotation * hit.normal})
+}
+
+// the normal at a hit, turned to face back along the segment
+fn facing(normal: Vec3, delta: Vec3) -> Vec3 {
+    let normal = normal.normalize_or(-delta.normalize_or_zero());
+    if normal.dot(delta) > 0.0 {-normal} else {normal}
+}
+
+// smallest root of a t^2 + b t + c in [0, 1]
+fn first_root(a: f32, b: f32, c: f32) -> Option<f32> {
+    let roots = if a.abs() < 1e-9 {
+        if b == 0.0 {return None}
+        [-c / b, f32::INFINITY]
+    } else {
+        let disc = b * b - 4.0 * a * c;
+        if disc < 0.0 {return None}
+        let (r0, r1) = ((-b - disc.sqrt()) / (2.0 * a), (-b + disc.sqrt()) / (2.0 * a));
+        [r0.min(r1), r0.max(r1)]
+    };
+    roots.into_iter().find(|t| (0.0..=1.0).contains(t))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Shape for Sphere {
+    fn intersect_segment(&self, start: Vec3, end: Vec3) -> Option<ShapeHit> {
+        let delta = end - start;
+        let rel = start - self.center;
+        let c = rel.length_squared() - self.radius * self.radius;
+        if c <= 0.0 {
+            return Some(ShapeHit {t: 0.0, normal: facing(rel, delta)});
+        }
+        let a = delta.length_squared();
+        let b = rel.dot(delta);
+        let disc = b * b - a * c;
+        // outside and heading away, or missing
+        if a == 0.0 || b >= 0.0 || disc < 0.0 {
+            return None;
+        }
+        let t = (-b - disc.sqrt()) / a;
+        (t <= 1.0).then(|| ShapeHit {t, normal: facing(rel + t * delta, delta)})
+    }
+}
+
+// Cylinder between two points with hemispherical ends.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capsule {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f32,
+}
+
+impl Shape for Capsule {
+    fn intersect_segment(&self, start: Vec3, end: Vec3) -> Option<ShapeHit> {
+        let delta = end - start;
+        let closest_on_axis = |p: Vec3| {
+            let axis = self.b - self.a;
+            let s = if axis == Vec3::ZERO {0.0} else {((p - self.a).dot(axis) / axis.length_squared()).clamp(0.0, 1.0)};
+            self.a + s * axis
+        };
+        if segment_point_dist_sq(self.a, self.b, start) <= self.radius * self.radius {
+            return Some(ShapeHit {t: 0.0, normal: facing(start - closest_on_axis(start), delta)});
+        }
+
+        // the side, only where it lies between the ends
+        let axis = (self.b - self.a).normalize_or_zero();
+        let length = (self.b - self.a).length();
+        let rel = start - self.a;
+        let rel_perp = rel - axis * rel.dot(axis);
+        let delta_perp = delta - axis * delta.dot(axis);
+        let side_t = first_root(
+            delta_perp.length_squared(),
+            2.0 * rel_perp.dot(delta_perp),
+            rel_perp.length_squared() - self.radius * self.radius,
+        ).filter(|t| (0.0..=length).contains(&(rel + t * delta).dot(axis)));
+
+        // the ends
+        let end_t = [self.a, self.b].into_iter()
+            .filter_map(|center| Sphere {center, radius: self.radius}.intersect_segment(start, end))
+            .map(|hit| hit.t)
+            .reduce(f32::min);
+
+        let t = match (side_t, end_t) {
+            (Some(s), Some(e)) => s.min(e),
+            (s, e) => s.or(e)?,
+        };
+        let pos = start + t * delta;
+        Some(ShapeHit {t, normal: facing(pos - closest_on_axis(pos), delta)})
+    }
+}
+
+// Oriented box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Obb {
+    pub center: Vec3,
+    pub half_extents: Vec3,
+    pub rotation: Quat,
+}
+
+impl Shape for Obb {
+    fn intersect_segment(&self, start: Vec3, end: Vec3) -> Option<ShapeHit> {
+        let to_local = self.rotation.inverse();
+        let origin = to_local * (start - self.center);
+        let delta = to_local * (end - start);
+
+        let (mut t_near, mut t_far) = (0.0f32, 1.0f32);
+        let mut near_axis = None;
+        for axis in 0..3 {
+            let (o, d, h) = (origin[axis], delta[axis], self.half_extents[axis]);
+            if d.abs() < 1e-9 {
+                if o.abs() > h {
+                    return None;
+                }
+                continue;
+            }
+            let (ta, tb) = ((-h - o) / d, (h - o) / d);
+            let (t0, t1) = (ta.min(tb), ta.max(tb));
+            if t0 > t_near {
+                t_near = t0;
+                near_axis = Some(axis);
+            }
+            t_far = t_far.min(t1);
+            if t_near > t_far {
+                return None;
+            }
+        }
+        // no entry face means it starts inside
+        let local_normal = match near_axis {
+            Some(axis) => -delta[axis].signum() * Vec3::AXES[axis],
+            None => -delta,
+        };
+        Some(ShapeHit {t: t_near, normal: facing(self.rotation * local_normal, end - start)})
+    }
+}
+
+// Surface of revolution about the z axis, from a profile of (radius, z) points.
+// Each pair of neighbouring points sweeps out a cone, cylinder or flat ring.
+// Profiles need not be closed, so this is a shell: a segment starting inside hits where it leaves.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lathe {
+    profile: Box<[Vec2]>,
+    bound_center: Vec3,
+    bound_radius: f32,
+}
+
+impl Lathe {
+    pub fn new(profile: impl Into<Box<[Vec2]>>) -> Self {
+        let profile = profile.into();
+        let (z_min, z_max) = profile.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| (lo.min(p.y), hi.max(p.y)));
+        let bound_center = vec3(0.0, 0.0, 0.5 * (z_min + z_max));
+        let bound_radius = profile.iter().map(|p| vec2(p.x, p.y - bound_center.z).length()).fold(0.0, f32::max);
+        Lathe {profile, bound_center, bound_radius}
+    }
+}
+
+impl Shape for Lathe {
+    fn intersect_segment(&self, start: Vec3, end: Vec3) -> Option<ShapeHit> {
+        if segment_point_dist_sq(start, end, self.bound_center) > self.bound_radius * self.bound_radius {
+            return None;
+        }
+        let (o, d) = (start, end - start);
+        let mut best: Option<ShapeHit> = None;
+        for edge in self.profile.windows(2) {
+            let (p0, p1) = (edge[0], edge[1]);
+            let (z_lo, z_hi) = (p0.y.min(p1.y), p0.y.max(p1.y));
+            let hit = if z_hi - z_lo < 1e-6 {
+                // flat ring
+                if d.z == 0.0 {
+                    continue;
+                }
+                let t = (p0.y - o.z) / d.z;
+                let r = (o + t * d).xy().length();
+                ((0.0..=1.0).contains(&t) && r >= p0.x.min(p1.x) && r <= p0.x.max(p1.x))
+                    .then(|| ShapeHit {t, normal: facing(Vec3::Z, d)})
+            } else {
+                // radius grows by k per unit of z, x^2 + y^2 = (c + k z)^2
+                let k = (p1.x - p0.x) / (p1.y - p0.y);
+                let c = p0.x - k * p0.y;
+                let r_o = c + k * o.z;
+                let a = d.x * d.x + d.y * d.y - k * k * d.z * d.z;
+                let b = 2.0 * (o.x * d.x + o.y * d.y - k * d.z * r_o);
+                let c2 = o.x * o.x + o.y * o.y - r_o * r_o;
+                // both roots may be on the cone, only the part between the points counts
+                let roots = if a.abs() < 1e-9 {
+                    [if b == 0.0 {f32::NAN} else {-c2 / b}, f32::NAN]
+                } else {
+                    let disc = b * b - 4.0 * a * c2;
+                    if disc < 0.0 {
+                        continue;
+                    }
+                    let (r0, r1) = ((-b - disc.sqrt()) / (2.0 * a), (-b + disc.sqrt()) / (2.0 * a));
+                    [r0.min(r1), r0.max(r1)]
+                };
+                roots.into_iter()
+                    .filter(|t| (0.0..=1.0).contains(t))
+                    .map(|t| (t, o + t * d))
+                    .find(|(_, p)| (z_lo..=z_hi).contains(&p.z))
+                    .map(|(t, p)| ShapeHit {t, normal: facing(vec3(p.x, p.y, -k * (c + k * p.z)), d)})
+            };
+            if let Some(hit) = hit && best.is_none_or(|b| hit.t < b.t) {
+                best = Some(hit);
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::f32::consts::FRAC_PI_2;
+
+    fn assert_hit(hit: Option<ShapeHit>, t: f32, normal: Vec3) {
+        let hit = hit.expect("expected a hit");
+        assert!((hit.t - t).abs() < 1e-4, "t {} instead of {}", hit.t, t);
+        assert!(hit.normal.abs_diff_eq(normal, 1e-4), "normal {} instead of {}", hit.normal, normal);
+    }
+
+    const UNIT_SPHERE: Sphere = Sphere {center: Vec3::ZERO, radius: 1.0};
+    const CAPSULE: Capsule = Capsule {a: vec3(0.0, 0.0, -1.0), b: vec3(0.0, 0.0, 1.0), radius: 0.5};
+
+    #[test]
+    fn sphere() {
+        let sphere = Sphere {center: vec3(1.0, 2.0, 3.0), radius: 1.0};
+        assert_hit(sphere.intersect_segment(vec3(-2.0, 2.0, 3.0), vec3(4.0, 2.0, 3.0)), 1.0 / 3.0, -Vec3::X);
+        assert_hit(sphere.intersect_segment(vec3(1.0, 2.0, 10.0), vec3(1.0, 2.0, 0.0)), 0.6, Vec3::Z);
+        // too short, heading away, and passing by
+        assert!(sphere.intersect_segment(vec3(-2.0, 2.0, 3.0), vec3(-0.1, 2.0, 3.0)).is_none());
+        assert!(sphere.intersect_segment(vec3(4.0, 2.0, 3.0), vec3(8.0, 2.0, 3.0)).is_none());
+        assert!(sphere.intersect_segment(vec3(-2.0, 3.5, 3.0), vec3(4.0, 3.5, 3.0)).is_none());
+    }
+
+    #[test]
+    fn capsule() {
+        // side, end cap, and the side only counting between the ends
+        assert_hit(CAPSULE.intersect_segment(vec3(-2.0, 0.0, 0.0), vec3(2.0, 0.0, 0.0)), 0.375, -Vec3::X);
+        assert_hit(CAPSULE.intersect_segment(vec3(0.0, 0.0, 3.0), vec3(0.0, 0.0, -3.0)), 0.25, Vec3::Z);
+        let z = 1.0 + 0.5 * 0.6f32;
+        let hit = CAPSULE.intersect_segment(vec3(-2.0, 0.0, z), vec3(2.0, 0.0, z)).unwrap();
+        assert!((hit.t - (2.0 - 0.4) / 4.0).abs() < 1e-4, "{:?}", hit);
+        assert!(hit.normal.abs_diff_eq(vec3(-0.8, 0.0, 0.6), 1e-4), "{:?}", hit);
+        assert!(CAPSULE.intersect_segment(vec3(-2.0, 0.0, 1.6), vec3(2.0, 0.0, 1.6)).is_none());
+        assert!(CAPSULE.intersect_segment(vec3(-2.0, 0.6, 0.0), vec3(2.0, 0.6, 0.0)).is_none());
+    }
+
+    #[test]
+    fn obb() {
+        let obb = Obb {center: Vec3::ZERO, half_extents: vec3(1.0, 2.0, 3.0), rotation: Quat::IDENTITY};
+        assert_hit(obb.intersect_segment(vec3(0.0, -5.0, 0.0), vec3(0.0, 5.0, 0.0)), 0.3, -Vec3::Y);
+        assert_hit(obb.intersect_segment(vec3(0.5, 0.5, 10.0), vec3(0.5, 0.5, 0.0)), 0.7, Vec3::Z);
+        assert!(obb.intersect_segment(vec3(1.5, -5.0, 0.0), vec3(1.5, 5.0, 0.0)).is_none());
+        // turned a quarter about z, the long side lies along x
+        let turned = Obb {rotation: Quat::from_rotation_z(FRAC_PI_2), ..obb};
+        assert_hit(turned.intersect_segment(vec3(-5.0, 0.0, 0.0), vec3(5.0, 0.0, 0.0)), 0.3, -Vec3::X);
+        assert!(turned.intersect_segment(vec3(0.0, -1.5, -5.0), vec3(0.0, -1.5, 5.0)).is_none());
+        assert!(turned.intersect_segment(vec3(1.5, 0.0, -5.0), vec3(1.5, 0.0, 5.0)).is_some());
+    }
+
+    #[test]
+    fn start_inside() {
+        let delta = vec3(3.0, 4.0, 0.0);
+        let back = -delta.normalize();
+        for hit in [
+            UNIT_SPHERE.intersect_segment(Vec3::ZERO, delta),
+            CAPSULE.intersect_segment(Vec3::ZERO, delta),
+            Obb {center: Vec3::ZERO, half_extents: Vec3::ONE, rotation: Quat::IDENTITY}.intersect_segment(Vec3::ZERO, delta),
+        ] {
+            let hit = hit.expect("expected a hit at the start");
+            assert_eq!(hit.t, 0.0);
+            assert!(hit.normal.dot(delta) < 0.0, "{:?}", hit);
+        }
+        // off center, the normal points out from the middle
+        let start = vec3(0.5, 0.0, 0.0);
+        assert_hit(UNIT_SPHERE.intersect_segment(start, start - Vec3::X), 0.0, Vec3::X);
+        assert_hit(UNIT_SPHERE.intersect_segment(Vec3::ZERO, delta), 0.0, back);
+        // a lathe is a shell, hit where the segment leaves it
+        let cylinder = Lathe::new(vec![vec2(1.0, 0.0), vec2(1.0, 2.0)]);
+        assert_hit(cylinder.intersect_segment(vec3(0.0, 0.0, 1.0), vec3(4.0, 0.0, 1.0)), 0.25, -Vec3::X);
+    }
+
+    #[test]
+    fn grazing() {
+        for (y, hits) in [(0.999, true), (1.001, false)] {
+            let hit = UNIT_SPHERE.intersect_segment(vec3(-2.0, y, 0.0), vec3(2.0, y, 0.0));
+            assert_eq!(hit.is_some(), hits, "sphere at {}", y);
+            if let Some(hit) = hit {
+                assert!((hit.t - 0.5).abs() < 0.02 && hit.normal.y > 0.99, "{:?}", hit);
+            }
+            assert_eq!(CAPSULE.intersect_segment(vec3(-2.0, 0.5 * y, 0.0), vec3(2.0, 0.5 * y, 0.0)).is_some(), hits, "capsule at {}", y);
+            let obb = Obb {center: Vec3::ZERO, half_extents: Vec3::ONE, rotation: Quat::IDENTITY};
+            assert_eq!(obb.intersect_segment(vec3(-2.0, y, 0.0), vec3(2.0, y, 0.0)).is_some(), hits, "box at {}", y);
+            let cylinder = Lathe::new(vec![vec2(1.0, -1.0), vec2(1.0, 1.0)]);
+            assert_eq!(cylinder.intersect_segment(vec3(-2.0, y, 0.0), vec3(2.0, y, 0.0)).is_some(), hits, "lathe at {}", y);
+        }
+    }
+
+    #[test]
+    fn placed() {
+        // lying along x once turned a quarter about y, and moved to x = 5
+        let (origin, rotation) = (vec3(5.0, 0.0, 0.0), Quat::from_rotation_y(FRAC_PI_2));
+        assert_hit(intersect_placed(&CAPSULE, origin, rotation, vec3(5.9, -3.0, 0.0), vec3(5.9, 3.0, 0.0)), (3.0 - 0.5) / 6.0, -Vec3::Y);
+        assert!(intersect_placed(&CAPSULE, origin, Quat::IDENTITY, vec3(5.9, -3.0, 0.0), vec3(5.9, 3.0, 0.0)).is_none());
+        assert_hit(intersect_placed(&CAPSULE, origin, rotation, vec3(5.0, 0.0, 4.0), vec3(5.0, 0.0, 0.0)), (4.0 - 0.5) / 4.0, Vec3::Z);
+
+        // a bowl tipped on its side opens towards +x
+        let bowl = Lathe::new(vec![vec2(1.0, 1.0), vec2(1.0, 0.0), vec2(0.0, 0.0)]);
+        let tipped = Quat::from_rotation_y(FRAC_PI_2);
+        assert_hit(intersect_placed(&bowl, Vec3::ZERO, tipped, vec3(3.0, 0.0, 0.0), vec3(-3.0, 0.0, 0.0)), 0.5, Vec3::X);
+        assert_hit(intersect_placed(&bowl, Vec3::ZERO, tipped, vec3(-3.0, 0.0, 0.0), vec3(3.0, 0.0, 0.0)), 0.5, -Vec3::X);
+    }
+
+    // radius of a profile at height z, for profiles going up monotonically
+    fn profile_radius(profile: &[Vec2], z: f32) -> Option<f32> {
+        profile.windows(2).find(|e| (e[0].y..=e[1].y).contains(&z)).map(|e| {
+            e[0].x + (e[1].x - e[0].x) * (z - e[0].y) / (e[1].y - e[0].y)
+        })
+    }
+
+    #[test]
+    fn lathe_matches_sampled_profile() {
+        const STEPS: usize = 4000;
+        // a vase with a closed bottom and an open top
+        let profile = [vec2(0.0, 0.0), vec2(0.4, 0.1), vec2(0.5, 0.5), vec2(0.3, 0.9), vec2(0.35, 1.0)];
+        let lathe = Lathe::new(profile.to_vec());
+        // how far outside the surface a point is, None above and below it
+        let outside = |p: Vec3| profile_radius(&profile, p.z).map(|r| p.xy().length() - r);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut hits = 0;
+        for _ in 0..1000 {
+            let mut point = || vec3(rng.random_range(-1.0..1.0), rng.random_range(-1.0..1.0), rng.random_range(-0.5..1.5));
+            let (start, end) = (point(), point());
+            let marched = (1..=STEPS).find_map(|i| {
+                let (t0, t1) = ((i - 1) as f32 / STEPS as f32, i as f32 / STEPS as f32);
+                let (a, b) = (outside(start.lerp(end, t0))?, outside(start.lerp(end, t1))?);
+                (a.signum() != b.signum()).then_some(t1)
+            });
+            match (lathe.intersect_segment(start, end), marched) {
+                (Some(hit), marched) => {
+                    hits += 1;
+                    let pos = start.lerp(end, hit.t);
+                    let off = outside(pos).expect("hit above or below the profile");
+                    assert!(off.abs() < 1e-3, "hit {:?} is {} off the surface", hit, off);
+                    assert!(hit.normal.dot(end - start) <= 0.0);
+                    if let Some(t) = marched {
+                        assert!((hit.t - t).abs() < 2.0 / STEPS as f32, "hit at {} instead of {}", hit.t, t);
+                    }
+                }
+                (None, Some(t)) => panic!("missed a crossing at {} from {} to {}", t, start, end),
+                (None, None) => {}
+            }
+        }
+        assert!(hits > 200, "only {} hits", hits);
+    }
+}
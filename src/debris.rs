@@ -0,0 +1,333 @@
+use glam::*;
+use half::f16;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::f32::consts::TAU;
+
+use crate::archetypes::TargetArchetype;
+use crate::targets::POT_U_DIVS;
+use crate::terrain_view::HeightmapTerrain;
+
+// A shard is a patch of quads cut out of a target's lathe mesh, drawn by the same shader as whole targets.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShardInst {
+    pos: Vec3, // of its center
+    pad: f32,
+    rotation: Quat,
+    center: Vec3, // in the model space of the target it came from, after scaling
+    scale: f32,
+    color_a: [f16; 3],
+    color_b: [f16; 3],
+    archetype: u32,
+    first_ring: u32,
+    num_rings: u32,
+    first_quad: u32,
+    num_quads: u32,
+}
+
+impl ShardInst {
+    pub fn pos(&self) -> Vec3 {
+        self.pos
+    }
+}
+
+pub const MAX_SHARDS: usize = 1024;
+const MAX_SHARD_RINGS: u32 = 3;
+const MAX_SHARD_QUADS: u32 = 2;
+pub const NUM_SHARD_VERTS: u32 = MAX_SHARD_RINGS * MAX_SHARD_QUADS * 6;
+
+const EXPLODE_SPEED: f32 = 2.5; // m/s away from the axis of the target
+const ARROW_MOMENTUM_SHARE: f32 = 0.3; // of the arrow's velocity, given to shards right where it struck
+const ARROW_FALLOFF: f32 = 0.25; // distance from the strike at which shards get half as much
+const MAX_SPIN: f32 = 12.0; // radians per second
+const GRAVITY: f32 = 9.81;
+const AIR_DRAG: f32 = 0.05; // quadratic drag coefficient per meter
+const WATER_GRAVITY: f32 = 3.0; // what is left of gravity after buoyancy, ceramic sinks faster than arrows
+const WATER_DRAG: f32 = 3.0; // thin and flat, so shards flutter down slowly
+const WATER_SPIN_DAMPING: f32 = 1.5; // per second
+const BOUNCE_RESTITUTION: f32 = 0.25; // of the speed into the ground
+const BOUNCE_FRICTION: f32 = 0.5; // of the speed along the ground and spin kept on a bounce
+const SETTLE_SPEED: f32 = 0.4; // shards slower than this after a bounce stay put
+const REST_HEIGHT: f32 = 0.02; // of a shard's center above the ground
+const LIE_FLAT_RATE: f32 = 4.0; // per second, how fast settled shards topple flat
+const SETTLED_LIFETIME: f64 = 30.0; // seconds on the bottom before sinking into it
+const BURY_TIME: f32 = 3.0;
+const BURY_DEPTH: f32 = 0.3;
+
+// A target breaking, as seen by the debris.
+#[derive(Clone, Copy, Debug)]
+pub struct Breakage {
+    pub bottom: Vec3,
+    pub orientation: Quat,
+    pub scale: f32,
+    pub archetype: u32,
+    pub colors: ([f16; 3], [f16; 3]),
+    pub hit_pos: Vec3,
+    pub arrow_vel: Vec3,
+    pub seed: u64, // the same breakage always fractures the same way
+}
+
+// One shard as a rigid body.
+#[derive(Clone, Copy, Debug)]
+struct Shard {
+    inst: ShardInst,
+    prev: (Vec3, Quat), // placement at the step before, for interpolation
+    vel: Vec3,
+    spin: Vec3, // angular velocity
+    normal: Vec3, // mean normal of its surface, in model space
+    settled: Option<(f64, Vec3)>, // when it came to rest and the ground normal there
+}
+
+// Simulation state for the pieces of smashed targets, usable without a GPU.
+// Shards keep going until they come to rest on the bottom, and sink out of sight a while later.
+pub struct DebrisController {
+    shards: VecDeque<Shard>, // oldest first
+    updated_at: f64,
+}
+
+impl Default for DebrisController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebrisController {
+    pub fn new() -> Self {
+        DebrisController {shards: VecDeque::new(), updated_at: 0.0}
+    }
+
+    pub fn reset(&mut self) {
+        self.shards.clear();
+        self.updated_at = 0.0;
+    }
+
+    // fractures a target into bands of rings, and each band into runs of quads
+    pub fn shatter(&mut self, breakage: &Breakage, archetype: &TargetArchetype) {
+        let profile: Vec<Vec2> = archetype.profile.iter().map(|p| p.pos).collect();
+        let num_segments = profile.len() as u32 - 1;
+        let mut rng = StdRng::seed_from_u64(breakage.seed);
+
+        let mut first_ring = 0;
+        while first_ring < num_segments {
+            let num_rings = rng.random_range(1..=MAX_SHARD_RINGS).min(num_segments - first_ring);
+            let quad_offset = rng.random_range(0..POT_U_DIVS);
+            let mut quad = 0;
+            while quad < POT_U_DIVS {
+                let num_quads = rng.random_range(1..=MAX_SHARD_QUADS).min(POT_U_DIVS - quad);
+                let first_quad = (quad_offset + quad) % POT_U_DIVS;
+                self.spawn(breakage, &profile, (first_ring, num_rings), (first_quad, num_quads), &mut rng);
+                quad += num_quads;
+            }
+            first_ring += num_rings;
+        }
+    }
+
+    fn spawn(&mut self, breakage: &Breakage, profile: &[Vec2], (first_ring, num_rings): (u32, u32), (first_quad, num_quads): (u32, u32), rng: &mut impl Rng) {
+        // the corners of each quad as laid out by the shader, odd rings are turned half a quad
+        let corner = |ring: u32, quad: u32, du: f32, dv: u32| {
+            let u = (quad as f32 + du + 0.5 * (ring % 2) as f32) / POT_U_DIVS as f32;
+            let point = profile[(ring + dv) as usize];
+            (Vec2::from_angle(TAU * u) * point.x).extend(point.y)
+        };
+        let mut sum = Vec3::ZERO;
+        let mut normal = Vec3::ZERO;
+        for ring in first_ring..first_ring + num_rings {
+            for quad in first_quad..first_quad + num_quads {
+                let [a, b, c, d] = [(0.0, 0), (1.0, 0), (0.5, 1), (1.5, 1)].map(|(du, dv)| corner(ring, quad, du, dv));
+                sum += a + b + c + d;
+                normal += (c - a).cross(b - a) + (d - b).cross(c - b);
+            }
+        }
+        // nothing there to break off
+        if normal.length_squared() < 1e-8 {
+            return;
+        }
+        let center = breakage.scale * sum / (4 * num_rings * num_quads) as f32;
+        let pos = breakage.bottom + breakage.orientation * center;
+
+        // thrown out from the middle, and along with the arrow near where it struck
+        let outwards = breakage.orientation * (center.xy().normalize_or_zero().extend(0.4)).normalize();
+        let from_arrow = ARROW_MOMENTUM_SHARE * breakage.arrow_vel / (1.0 + (pos.distance(breakage.hit_pos) / ARROW_FALLOFF).powi(2));
+        let vel = EXPLODE_SPEED * rng.random_range(0.6..1.0) * outwards + from_arrow;
+        let spin = MAX_SPIN * (vec3(rng.random(), rng.random(), rng.random()) * 2.0 - 1.0).clamp_length_max(1.0);
+
+        if self.shards.len() >= MAX_SHARDS {
+            self.shards.pop_front();
+        }
+        self.shards.push_back(Shard {
+            inst: ShardInst {
+                pos,
+                pad: 0.0,
+                rotation: breakage.orientation,
+                center,
+                scale: breakage.scale,
+                color_a: breakage.colors.0,
+                color_b: breakage.colors.1,
+                archetype: breakage.archetype,
+                first_ring, num_rings, first_quad, num_quads,
+            },
+            prev: (pos, breakage.orientation),
+            vel, spin,
+            normal: normal.normalize(),
+            settled: None,
+        });
+    }
+
+    pub fn tick(&mut self, time: f64, terrain: &HeightmapTerrain) {
+        let dt = (time - self.updated_at).max(0.0) as f32;
+        self.updated_at = time;
+
+        self.shards.retain_mut(|shard| {
+            let inst = &mut shard.inst;
+            shard.prev = (inst.pos, inst.rotation);
+
+            if let Some((settled_at, ground)) = shard.settled {
+                // topples over to lie flat on whichever side is nearer, then sinks into the silt
+                let normal = inst.rotation * shard.normal;
+                let flat = Quat::from_rotation_arc(normal, if normal.dot(ground) >= 0.0 {ground} else {-ground}) * inst.rotation;
+                inst.rotation = inst.rotation.slerp(flat, 1.0 - (-LIE_FLAT_RATE * dt).exp());
+                let age = time - settled_at;
+                if age > SETTLED_LIFETIME {
+                    inst.pos.z -= dt * BURY_DEPTH / BURY_TIME;
+                }
+                return age < SETTLED_LIFETIME + BURY_TIME as f64;
+            }
+
+            // semi-implicit Euler, as for arrows
+            let (gravity, drag) = if inst.pos.z > 0.0 {(GRAVITY, AIR_DRAG)} else {(WATER_GRAVITY, WATER_DRAG)};
+            shard.vel += dt * (vec3(0.0, 0.0, -gravity) - drag * shard.vel.length() * shard.vel);
+            if inst.pos.z <= 0.0 {
+                shard.spin *= (-WATER_SPIN_DAMPING * dt).exp();
+            }
+            inst.rotation = (Quat::from_scaled_axis(dt * shard.spin) * inst.rotation).normalize();
+            let new_pos = inst.pos + dt * shard.vel;
+
+            // shards starting at the bottom of a target may already be under the ground
+            let ground_hit = terrain.raycast(inst.pos, new_pos).map(|hit| (hit.pos, hit.normal)).or_else(|| {
+                let height = terrain.height_at(new_pos.xy())?;
+                (new_pos.z < height).then(|| (new_pos.xy().extend(height), terrain.normal_at(new_pos.xy()).unwrap()))
+            });
+            match ground_hit {
+                Some((pos, ground)) => {
+                    let into = shard.vel.dot(ground);
+                    shard.vel = BOUNCE_FRICTION * (shard.vel - into * ground) - BOUNCE_RESTITUTION * into.min(0.0) * ground;
+                    shard.spin *= BOUNCE_FRICTION;
+                    inst.pos = pos + REST_HEIGHT * ground;
+                    if shard.vel.length() < SETTLE_SPEED {
+                        shard.settled = Some((time, ground));
+                    }
+                }
+                None => inst.pos = new_pos,
+            }
+            // out of the pond
+            terrain.height_at(inst.pos.xy()).is_some()
+        });
+    }
+
+    pub fn hold(&mut self) {
+        for shard in self.shards.iter_mut() {
+            shard.prev = (shard.inst.pos, shard.inst.rotation);
+        }
+    }
+
+    // instances to draw, placed between the last two steps
+    pub fn instances(&self, alpha: f32) -> impl Iterator<Item = ShardInst> + '_ {
+        self.shards.iter().map(move |shard| {
+            let (prev_pos, prev_rot) = shard.prev;
+            ShardInst {
+                pos: prev_pos.lerp(shard.inst.pos, alpha),
+                rotation: prev_rot.slerp(shard.inst.rotation, alpha),
+                ..shard.inst
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archetypes::ArchetypeSet;
+
+    fn breakage(seed: u64) -> Breakage {
+        Breakage {
+            bottom: vec3(2.0, -1.0, -1.5),
+            orientation: Quat::from_rotation_z(0.7) * Quat::from_rotation_x(0.2),
+            scale: 1.3,
+            archetype: 0,
+            colors: ([f16::ONE; 3], [f16::ZERO; 3]),
+            hit_pos: vec3(2.0, -1.5, -1.0),
+            arrow_vel: vec3(0.0, 30.0, -10.0),
+            seed,
+        }
+    }
+
+    #[test]
+    fn shatter_covers_the_whole_target() {
+        for archetype in ArchetypeSet::default().archetypes.iter() {
+            let num_segments = archetype.profile.len() as u32 - 1;
+            let max_r = archetype.profile.iter().map(|p| p.pos.x).fold(0.0, f32::max);
+            let (lo, hi) = archetype.profile.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), p| (lo.min(p.pos.y), hi.max(p.pos.y)));
+            for seed in 0..20 {
+                let b = breakage(seed);
+                let mut debris = DebrisController::default();
+                debris.shatter(&b, archetype);
+
+                // every quad of every ring ends up in exactly one shard
+                let mut covered = vec![0; (num_segments * POT_U_DIVS) as usize];
+                for shard in debris.shards.iter() {
+                    let inst = &shard.inst;
+                    assert!((1..=MAX_SHARD_RINGS).contains(&inst.num_rings) && (1..=MAX_SHARD_QUADS).contains(&inst.num_quads));
+                    assert!(inst.first_ring + inst.num_rings <= num_segments && inst.first_quad < POT_U_DIVS);
+                    for ring in inst.first_ring..inst.first_ring + inst.num_rings {
+                        for quad in inst.first_quad..inst.first_quad + inst.num_quads {
+                            covered[(ring * POT_U_DIVS + quad % POT_U_DIVS) as usize] += 1;
+                        }
+                    }
+
+                    // starting out on the target, where it was
+                    let local = b.orientation.inverse() * (inst.pos - b.bottom);
+                    assert!((local - inst.center).length() < 1e-5);
+                    assert!(inst.center.xy().length() <= b.scale * max_r + 1e-5, "{:?}", inst.center);
+                    assert!((b.scale * lo - 1e-5..=b.scale * hi + 1e-5).contains(&inst.center.z), "{:?}", inst.center);
+                    assert!(shard.vel.is_finite() && shard.vel.length() <= EXPLODE_SPEED + ARROW_MOMENTUM_SHARE * b.arrow_vel.length());
+                    assert!(shard.spin.length() <= MAX_SPIN + 1e-4);
+                    assert!((shard.normal.length() - 1.0).abs() < 1e-5);
+                }
+                assert!(covered.iter().all(|&n| n == 1), "{} seed {}: {:?}", archetype.name, seed, covered);
+                assert!(debris.shards.len() <= MAX_SHARDS);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_shards() {
+        let archetype = &ArchetypeSet::default().archetypes[0];
+        let shards = |seed| {
+            let mut debris = DebrisController::default();
+            debris.shatter(&breakage(seed), archetype);
+            debris.instances(1.0).map(|inst| (inst.pos, inst.first_ring, inst.num_rings, inst.first_quad, inst.num_quads)).collect::<Vec<_>>()
+        };
+        assert_eq!(shards(4), shards(4));
+        assert_ne!(shards(4), shards(5));
+    }
+
+    #[test]
+    fn oldest_shards_make_room() {
+        let archetype = &ArchetypeSet::default().archetypes[0];
+        let mut debris = DebrisController::default();
+        let mut seed = 0;
+        while debris.shards.len() < MAX_SHARDS {
+            debris.shatter(&breakage(seed), archetype);
+            seed += 1;
+        }
+        let mut last = DebrisController::default();
+        last.shatter(&breakage(seed), archetype);
+        let first_left = debris.shards[last.shards.len()].inst.pos;
+        debris.shatter(&breakage(seed), archetype);
+        assert_eq!(debris.shards.len(), MAX_SHARDS);
+        assert_eq!(debris.shards.front().unwrap().inst.pos, first_left);
+        let newest: Vec<Vec3> = debris.shards.iter().rev().take(last.shards.len()).map(|s| s.inst.pos).collect();
+        assert_eq!(newest, last.shards.iter().rev().map(|s| s.inst.pos).collect::<Vec<_>>());
+    }
+}
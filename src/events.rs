@@ -0,0 +1,55 @@
+use glam::Vec3;
+
+use crate::ui::GameState;
+
+// Things that happened during a simulation step, for audio, UI and stats to react to.
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent {
+    // arrows are numbered by shot within a run
+    ArrowReleased {arrow: u32, eye: Vec3, pos: Vec3, dir: Vec3},
+    ArrowHitTerrain {arrow: u32, pos: Vec3},
+    WaterEntry {arrow: u32, pos: Vec3},
+    ArrowSkipped {arrow: u32, pos: Vec3}, // bounced off the water surface
+    TargetSmashed {arrow: u32, id: u32, pos: Vec3, depth: f32, archetype: u32, points: u32}, // pos is where the arrow struck, depth is of the bottom of the pot below the water surface, points are its base value
+    TargetCracked {arrow: u32, id: u32, pos: Vec3}, // struck by an arrow, but not yet smashed
+    FishHit {arrow: u32, id: u32, pos: Vec3, depth: f32}, // depth is of the fish below the water surface
+    ArrowSpent {arrow: u32, pos: Vec3}, // stuck, out of bounds or replaced, it can't hit anything more
+    StateChanged {old: GameState, new: GameState},
+}
+
+// Events collected over the steps of one frame, taken by GameSystem and handed to each consumer.
+#[derive(Debug, Default)]
+pub struct EventQueue {
+    events: Vec<GameEvent>,
+}
+
+impl EventQueue {
+    pub fn push(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    // events pushed after the queue had `start` events
+    pub fn since(&self, start: usize) -> &[GameEvent] {
+        &self.events[start.min(self.events.len())..]
+    }
+
+    pub fn take(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &GameEvent> {
+        self.events.iter()
+    }
+}
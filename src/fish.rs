@@ -0,0 +1,563 @@
+use glam::*;
+use wgpu::util::BufferInitDescriptor;
+use wgpu::util::DeviceExt;
+use wgpu::*;
+use rand::Rng;
+use std::f32::consts::{PI, TAU};
+use std::mem::size_of;
+use std::slice::from_ref;
+
+use crate::arrows::{ArrowTarget, HitResponse, TargetHit};
+use crate::camera::sphere_visible;
+use crate::collision::{intersect_placed, Capsule};
+use crate::events::{EventQueue, GameEvent};
+use crate::{deferred_renderer::{DeferredRenderer, RenderObject}, gputil::*, terrain_view::HeightmapTerrain};
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct FishVert {
+    pos: Vec3, // x forwards, z up
+    norm: Vec3,
+    fin: f32, // 1 on fins, 0 on the body
+}
+
+fn fish_model() -> Box<[FishVert]> {
+    // body cross sections as (x, half height, half width), modelled on graph paper
+    let sections = [
+        vec3(-0.30, 0.02, 0.01),
+        vec3(-0.20, 0.06, 0.03),
+        vec3(-0.05, 0.11, 0.05),
+        vec3(0.10, 0.12, 0.06),
+        vec3(0.22, 0.09, 0.05),
+        vec3(0.28, 0.05, 0.035),
+        vec3(0.31, 0.0, 0.0),
+    ];
+    let ring = |s: Vec3, i: u32| {
+        let a = TAU * i as f32 / FISH_SIDES as f32;
+        let (sin, cos) = a.sin_cos();
+        let pos = vec3(s.x, s.z * cos, s.y * sin);
+        // normal of the ellipse, tilted forwards where the body narrows towards the ends
+        let norm = vec3(0.0, cos / s.z.max(0.01), sin / s.y.max(0.01)).normalize() + vec3(pos.x.signum() * 0.3, 0.0, 0.0);
+        FishVert {pos, norm: norm.normalize(), fin: 0.0}
+    };
+
+    let mut out = Vec::new();
+    for pair in sections.windows(2) {
+        for i in 0..FISH_SIDES {
+            let (a0, a1) = (ring(pair[0], i), ring(pair[0], i + 1));
+            let (b0, b1) = (ring(pair[1], i), ring(pair[1], i + 1));
+            out.extend_from_slice(&[a0, b0, a1, a1, b0, b1]);
+        }
+    }
+
+    let fin = |x: f32, z: f32| FishVert {pos: vec3(x, 0.0, z), norm: Vec3::Y, fin: 1.0};
+    out.extend_from_slice(&[
+        // tail
+        fin(-0.27, 0.0), fin(-0.42, 0.11), fin(-0.36, 0.0),
+        fin(-0.27, 0.0), fin(-0.36, 0.0), fin(-0.42, -0.11),
+        // dorsal
+        fin(-0.05, 0.10), fin(0.08, 0.11), fin(-0.12, 0.17),
+    ]);
+    out.into_boxed_slice()
+}
+
+const FISH_SIDES: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct FishInst {
+    pos: Vec3,
+    phase: f32, // of the tail beat
+    rotation: Quat,
+    color: Vec3,
+    wiggle: f32, // 0 to 1, how hard it is swimming
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Fish {
+    pos: Vec3,
+    prev_pos: Vec3,
+    vel: Vec3,
+    heading: Vec3, // normalized, kept when it stops
+    rotation: Quat,
+    prev_rotation: Quat,
+    phase: f32,
+    school: usize,
+    color: Vec3,
+    depth: f32, // preferred, below the surface
+    home: Vec3, // where it comes back after being caught
+    caught_at: Option<f64>,
+    flee: Option<(Vec2, f64)>, // what it is fleeing from and until when
+}
+
+const NUM_SCHOOLS: usize = 4;
+const FISH_PER_SCHOOL: usize = 6;
+const SPAWN_RADIUS: f32 = 35.0;
+const MIN_SPAWN_DEPTH: f32 = 2.0; // of the bottom where a school starts
+const MAX_FISH_DEPTH: f32 = 2.5;
+
+const CRUISE_SPEED: f32 = 1.5;
+const MIN_SPEED: f32 = 0.5;
+const FLEE_SPEED: f32 = 4.0;
+const MAX_ACCEL: f32 = 4.0;
+const MAX_CLIMB: f32 = 0.4; // of the heading, so they never point straight up or down
+const NEIGHBOUR_RADIUS: f32 = 3.0;
+const SEPARATION_RADIUS: f32 = 0.8;
+const SEPARATION_WEIGHT: f32 = 3.0;
+const ALIGNMENT_WEIGHT: f32 = 1.0;
+const COHESION_WEIGHT: f32 = 0.5;
+const DEPTH_WEIGHT: f32 = 1.5;
+const MIN_WATER_DEPTH: f32 = 1.0; // fish turn back before the bottom gets shallower than this
+const FLOOR_CLEARANCE: f32 = 0.3;
+const SURFACE_CLEARANCE: f32 = 0.3;
+const LOOK_AHEAD: f32 = 2.5; // meters, for spotting shallows
+const AVOID_WEIGHT: f32 = 6.0;
+const FLEE_RADIUS: f32 = 6.0; // from an arrow hitting the water
+const FLEE_TIME: f64 = 1.5;
+const FLEE_WEIGHT: f32 = 8.0;
+const TAIL_BEATS_PER_M: f32 = 1.5;
+
+// caught fish float up belly first
+const FLOAT_SPEED: f32 = 0.3;
+const FLOAT_DEPTH: f32 = 0.1; // of the top of a floating fish
+const ROLL_TIME: f64 = 1.5;
+
+const SCHOOL_COLORS: [Vec3; 4] = [
+    vec3(0.85, 0.32, 0.06), // koi
+    vec3(0.55, 0.57, 0.6), // silver
+    vec3(0.7, 0.55, 0.15), // gold
+    vec3(0.2, 0.25, 0.2), // carp
+];
+
+// orientation of a fish pointing along `heading`, rolled over by `roll` radians
+fn heading_rotation(heading: Vec3, roll: f32) -> Quat {
+    let forward = heading.normalize();
+    let left = Vec3::Z.cross(forward).normalize_or(Vec3::Y);
+    let up = forward.cross(left);
+    Quat::from_mat3(&Mat3::from_cols(forward, left, up)) * Quat::from_rotation_x(roll)
+}
+
+// Schools of fish swimming around the pond, usable without a GPU.
+// They steer as boids, keeping apart, lining up and staying together with their own school,
+// while holding their depth, turning back from shallows and darting away from arrows hitting the water.
+pub struct FishController {
+    fish: Vec<Fish>,
+    pub fish_caught: u32,
+    updated_at: Option<f64>,
+    shape: Capsule, // of the body, for hit testing
+}
+
+impl FishController {
+    fn gen_fish(terrain: &HeightmapTerrain, rng: &mut impl Rng) -> Vec<Fish> {
+        let mut fish = Vec::with_capacity(NUM_SCHOOLS * FISH_PER_SCHOOL);
+        for school in 0..NUM_SCHOOLS {
+            // somewhere deep enough, or the middle of the pond after enough tries
+            let mut center = Vec2::ZERO;
+            for _ in 0..100 {
+                let xy = (vec2(rng.random(), rng.random()) - 0.5) * 2.0 * SPAWN_RADIUS;
+                if terrain.height_at(xy).is_some_and(|z| z < -MIN_SPAWN_DEPTH) {
+                    center = xy;
+                    break;
+                }
+            }
+            let floor = terrain.height_at(center).unwrap_or(-MIN_SPAWN_DEPTH);
+            let heading = Vec3::X.rotate_z(TAU * rng.random::<f32>());
+            let color = SCHOOL_COLORS[school % SCHOOL_COLORS.len()];
+            for _ in 0..FISH_PER_SCHOOL {
+                let offset = (vec2(rng.random(), rng.random()) - 0.5) * 3.0;
+                let max_depth = (-floor - FLOOR_CLEARANCE).max(SURFACE_CLEARANCE + 0.1);
+                let depth = rng.random_range(SURFACE_CLEARANCE..max_depth).min(MAX_FISH_DEPTH);
+                let pos = (center + offset).extend(-depth);
+                let rotation = heading_rotation(heading, 0.0);
+                fish.push(Fish {
+                    pos, prev_pos: pos,
+                    vel: heading * CRUISE_SPEED,
+                    heading, rotation,
+                    prev_rotation: rotation,
+                    phase: rng.random(),
+                    school, color, depth,
+                    home: pos,
+                    caught_at: None,
+                    flee: None,
+                });
+            }
+        }
+        fish
+    }
+
+    pub fn new(terrain: &HeightmapTerrain, rng: &mut impl Rng) -> Self {
+        FishController {
+            fish: Self::gen_fish(terrain, rng),
+            fish_caught: 0,
+            updated_at: None,
+            shape: Capsule {a: vec3(-0.25, 0.0, 0.0), b: vec3(0.22, 0.0, 0.0), radius: 0.1},
+        }
+    }
+
+    pub fn reset(&mut self, terrain: &HeightmapTerrain, rng: &mut impl Rng) {
+        self.fish = Self::gen_fish(terrain, rng);
+        self.fish_caught = 0;
+        self.updated_at = None;
+    }
+
+    // keep the fish still while paused
+    pub fn hold(&mut self) {
+        for fish in self.fish.iter_mut() {
+            fish.prev_pos = fish.pos;
+            fish.prev_rotation = fish.rotation;
+        }
+    }
+
+    // `events` are those pushed this step, caught fish come back after `respawn_delay` seconds if set
+    pub fn tick(&mut self, time: f64, terrain: &HeightmapTerrain, events: &[GameEvent], respawn_delay: Option<f64>) {
+        let dt = self.updated_at.map_or(0.0, |t| (time - t).max(0.0)) as f32;
+        self.updated_at = Some(time);
+
+        for event in events {
+            if let GameEvent::WaterEntry { pos, .. } | GameEvent::ArrowSkipped { pos, .. } = *event {
+                for fish in self.fish.iter_mut().filter(|f| f.caught_at.is_none()) {
+                    if fish.pos.xy().distance(pos.xy()) < FLEE_RADIUS {
+                        fish.flee = Some((pos.xy(), time + FLEE_TIME));
+                    }
+                }
+            }
+        }
+
+        let accels: Vec<Vec3> = (0..self.fish.len()).map(|i| self.steer(i, time, terrain)).collect();
+        for (fish, accel) in self.fish.iter_mut().zip(accels) {
+            fish.prev_pos = fish.pos;
+            fish.prev_rotation = fish.rotation;
+
+            if let Some(caught_at) = fish.caught_at {
+                if respawn_delay.is_some_and(|delay| caught_at + delay <= time) {
+                    fish.caught_at = None;
+                    fish.pos = fish.home;
+                    fish.prev_pos = fish.home;
+                    fish.vel = fish.heading * CRUISE_SPEED;
+                    fish.rotation = heading_rotation(fish.heading, 0.0);
+                    fish.prev_rotation = fish.rotation;
+                    continue;
+                }
+                fish.pos.z = (fish.pos.z + FLOAT_SPEED * dt).min(-FLOAT_DEPTH);
+                fish.heading = fish.heading.with_z(0.0).normalize_or(Vec3::X);
+                let roll = PI * smoothstep(((time - caught_at) / ROLL_TIME) as f32);
+                fish.rotation = heading_rotation(fish.heading, roll);
+                continue;
+            }
+
+            if fish.flee.is_some_and(|(_, until)| until <= time) {
+                fish.flee = None;
+            }
+            let max_speed = if fish.flee.is_some() {FLEE_SPEED} else {CRUISE_SPEED};
+            fish.vel += dt * accel.clamp_length_max(MAX_ACCEL);
+            fish.vel.z = fish.vel.z.clamp(-MAX_CLIMB * fish.vel.length(), MAX_CLIMB * fish.vel.length());
+            // a fish brought to a stop carries on the way it was facing
+            fish.vel = fish.vel.normalize_or(fish.heading) * fish.vel.length().clamp(MIN_SPEED, max_speed);
+            fish.pos += dt * fish.vel;
+
+            // never out of the water or into the bottom
+            let floor = terrain.height_at(fish.pos.xy()).unwrap_or(0.0);
+            fish.pos.z = fish.pos.z.max(floor + FLOOR_CLEARANCE).min(-SURFACE_CLEARANCE);
+
+            fish.heading = fish.vel.normalize_or(fish.heading);
+            fish.rotation = heading_rotation(fish.heading, 0.0);
+            fish.phase = (fish.phase + dt * fish.vel.length() * TAIL_BEATS_PER_M).fract();
+        }
+    }
+
+    // boids steering for one fish
+    fn steer(&self, i: usize, time: f64, terrain: &HeightmapTerrain) -> Vec3 {
+        let fish = &self.fish[i];
+        if fish.caught_at.is_some() {
+            return Vec3::ZERO;
+        }
+        let mut separation = Vec3::ZERO;
+        let mut heading_sum = Vec3::ZERO;
+        let mut center_sum = Vec3::ZERO;
+        let mut neighbours = 0;
+        for (j, other) in self.fish.iter().enumerate() {
+            if j == i || other.caught_at.is_some() {
+                continue;
+            }
+            let offset = fish.pos - other.pos;
+            let dist = offset.length();
+            if dist < SEPARATION_RADIUS && dist > 0.0 {
+                separation += offset / dist * (1.0 - dist / SEPARATION_RADIUS);
+            }
+            if other.school == fish.school && dist < NEIGHBOUR_RADIUS {
+                heading_sum += other.heading;
+                center_sum += other.pos;
+                neighbours += 1;
+            }
+        }
+
+        let mut accel = SEPARATION_WEIGHT * separation;
+        if neighbours > 0 {
+            let n = neighbours as f32;
+            accel += ALIGNMENT_WEIGHT * (heading_sum / n * CRUISE_SPEED - fish.vel);
+            accel += COHESION_WEIGHT * (center_sum / n - fish.pos);
+        }
+
+        // hold its depth, or as near as the bottom allows
+        let floor = terrain.height_at(fish.pos.xy()).unwrap_or(0.0);
+        let target_z = (-fish.depth).max(floor + FLOOR_CLEARANCE + 0.2).min(-SURFACE_CLEARANCE);
+        accel.z += DEPTH_WEIGHT * (target_z - fish.pos.z);
+
+        // turn downhill from shallows ahead, or back towards the middle off the edge of the map
+        let ahead = fish.pos.xy() + LOOK_AHEAD * fish.heading.xy().normalize_or_zero();
+        let shallow = terrain.height_at(ahead).is_none_or(|z| z > -MIN_WATER_DEPTH)
+            || floor > -MIN_WATER_DEPTH;
+        if shallow {
+            let away = terrain.normal_at(fish.pos.xy()).map_or(-fish.pos.xy(), |n| n.xy());
+            accel += AVOID_WEIGHT * away.normalize_or(-fish.pos.xy().normalize_or_zero()).extend(0.0);
+        }
+
+        if let Some((from, until)) = fish.flee && until > time {
+            let away = (fish.pos.xy() - from).normalize_or(fish.heading.xy());
+            accel += FLEE_WEIGHT * away.extend(-0.3);
+        }
+        accel
+    }
+}
+
+impl ArrowTarget for FishController {
+    // arrows stick in the fish they hit
+    fn find_hits(&self, start: Vec3, end: Vec3) -> Vec<TargetHit> {
+        self.fish.iter().enumerate()
+            .filter(|(_, f)| f.caught_at.is_none())
+            .filter_map(|(id, f)| {
+                let hit = intersect_placed(&self.shape, f.pos, f.rotation, start, end)?;
+                Some(TargetHit {id: id as u32, t: hit.t, pos: start.lerp(end, hit.t), normal: hit.normal, response: HitResponse::Embed})
+            })
+            .collect()
+    }
+
+    fn apply_hit(&mut self, events: &mut EventQueue, arrow: u32, _vel: Vec3, hit: &TargetHit) {
+        let Some(time) = self.updated_at else { return };
+        let fish = &mut self.fish[hit.id as usize];
+        if fish.caught_at.is_none() {
+            fish.caught_at = Some(time);
+            fish.flee = None;
+            self.fish_caught += 1;
+            events.push(GameEvent::FishHit { arrow, id: hit.id, pos: hit.pos, depth: (-fish.pos.z).max(0.0) });
+        }
+    }
+
+    // only caught fish carry arrows, and they let go when it swims off again
+    fn attachment(&self, id: u32) -> Option<(Vec3, Quat)> {
+        let fish = self.fish.get(id as usize)?;
+        fish.caught_at.map(|_| (fish.pos, fish.rotation))
+    }
+}
+
+pub struct FishView {
+    fish_pipeline: RenderPipeline,
+    fish_refr_pipeline: RenderPipeline,
+    fish_refl_pipeline: RenderPipeline,
+    shadow_fish_pipeline: RenderPipeline,
+    fish_model_len: u32,
+    fish_vertex_buf: Buffer,
+    fish_buf: Buffer,
+    fish_bg: BindGroup,
+    max_fish_inst: u32,
+
+    // instances copied from the simulation each frame
+    fish_insts: Vec<FishInst>,
+}
+
+impl FishView {
+    pub fn new(gpu: &GPUContext, renderer: &DeferredRenderer) -> Self {
+        let shaders = gpu.process_shader_module("fish.wgsl", crate::shaders::FISH);
+
+        let fish_bg_layout = gpu.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("fish_bg_layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer { ty: BufferBindingType::Storage { read_only: true }, has_dynamic_offset: false, min_binding_size: None },
+                    count: None,
+                }
+            ]
+        });
+
+        let fish_model = fish_model();
+        let fish_vertex_layout = VertexBufferLayout {
+            array_stride: size_of::<FishVert>() as u64,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32],
+        };
+        let fish_vertex_buf = gpu.device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("fish_vertex_buf"),
+            contents: bytemuck::cast_slice(&fish_model),
+            usage: BufferUsages::VERTEX,
+        });
+
+        let fish_pipeline_layout = gpu.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("fish_pipeline_layout"),
+            bind_group_layouts: &[
+                &renderer.global_bind_layout,
+                &fish_bg_layout,
+            ],
+            immediate_size: 0,
+        });
+
+        let fish_pipeline_desc = RenderPipelineDescriptor {
+            label: Some("fish"),
+            layout: Some(&fish_pipeline_layout),
+            vertex: VertexState {
+                module: &shaders,
+                entry_point: Some("fish_vert"),
+                compilation_options: Default::default(),
+                buffers: from_ref(&fish_vertex_layout),
+            },
+            fragment: Some(FragmentState {
+                module: &shaders,
+                entry_point: Some("fish_frag"),
+                compilation_options: Default::default(),
+                targets: DeferredRenderer::GBUFFER_TARGETS,
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..PrimitiveState::default()
+            },
+            depth_stencil: reverse_z(),
+            multisample: MultisampleState::default(),
+            multiview_mask: None,
+            cache: None,
+        };
+        let fish_pipeline = gpu.device.create_render_pipeline(&fish_pipeline_desc);
+        let fish_refr_pipeline = DeferredRenderer::create_refracted_pipeline(&gpu.device, &fish_pipeline_desc);
+        let fish_refl_pipeline = DeferredRenderer::create_reflected_pipeline(&gpu.device, &fish_pipeline_desc);
+
+        let shadow_fish_pipeline = gpu.device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("fish_shadow"),
+            layout: Some(&fish_pipeline_layout),
+            vertex: VertexState {
+                module: &shaders,
+                entry_point: Some("fish_vert_shadow"),
+                compilation_options: Default::default(),
+                buffers: from_ref(&fish_vertex_layout),
+            },
+            fragment: None,
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..PrimitiveState::default()
+            },
+            depth_stencil: reverse_z(),
+            multisample: MultisampleState::default(),
+            multiview_mask: None,
+            cache: None,
+        });
+
+        let fish_buf = gpu.device.create_buffer(&BufferDescriptor {
+            label: Some("fish_buf"),
+            size: (size_of::<FishInst>() * NUM_SCHOOLS * FISH_PER_SCHOOL) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+        let fish_bg = gpu.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("fish_bg"),
+            layout: &fish_bg_layout,
+            entries: &[
+                BindGroupEntry {binding: 0, resource: fish_buf.as_entire_binding()}
+            ]
+        });
+
+        FishView {
+            fish_pipeline, fish_refr_pipeline, fish_refl_pipeline, shadow_fish_pipeline,
+            fish_model_len: fish_model.len() as u32,
+            fish_vertex_buf, fish_buf, fish_bg,
+            max_fish_inst: 0,
+            fish_insts: Vec::new(),
+        }
+    }
+
+    // copy instances to draw from the simulation, placing fish between the last two steps
+    pub fn update(&mut self, fish: &FishController, alpha: f32) {
+        self.fish_insts.clear();
+        self.fish_insts.extend(fish.fish.iter().map(|f| FishInst {
+            pos: f.prev_pos.lerp(f.pos, alpha),
+            phase: f.phase,
+            rotation: f.prev_rotation.slerp(f.rotation, alpha),
+            color: f.color,
+            wiggle: if f.caught_at.is_some() {0.0} else {f.vel.length() / FLEE_SPEED},
+        }));
+    }
+
+    fn draw<'a>(&'a self, pipeline: &'a RenderPipeline, pass: &mut RenderPass<'a>) {
+        if self.max_fish_inst != 0 {
+            pass.set_pipeline(pipeline);
+            pass.set_vertex_buffer(0, self.fish_vertex_buf.slice(..));
+            pass.set_bind_group(1, &self.fish_bg, &[]);
+            pass.draw(0..self.fish_model_len, 0..self.max_fish_inst);
+        }
+    }
+}
+
+impl RenderObject for FishView {
+    fn prepass(&mut self, gpu: &GPUContext, renderer: &DeferredRenderer, encoder: &mut CommandEncoder) {
+        let planes = renderer.camera.perspective_clipping_planes();
+        let visible_fish: Vec<FishInst> = self.fish_insts.iter().copied().filter(|f| {
+            sphere_visible(planes, f.pos, 3.0)
+        }).collect();
+
+        self.max_fish_inst = visible_fish.len() as u32;
+        if self.max_fish_inst != 0 {
+            gpu.queue.write_buffer(&self.fish_buf, 0, bytemuck::cast_slice(&visible_fish));
+        }
+    }
+
+    fn draw_shadow_casters<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut RenderPass<'a>) {
+        self.draw(&self.shadow_fish_pipeline, pass);
+    }
+
+    fn draw_underwater<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut RenderPass<'a>) {
+        self.draw(&self.fish_refr_pipeline, pass);
+    }
+
+    fn draw_reflected<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut RenderPass<'a>) {
+        self.draw(&self.fish_refl_pipeline, pass);
+    }
+
+    fn draw_opaque<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut RenderPass<'a>) {
+        self.draw(&self.fish_pipeline, pass);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use half::f16;
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::gputil::PlanarImage;
+
+    fn flat_terrain(z: f32) -> HeightmapTerrain {
+        HeightmapTerrain::new(PlanarImage {width: 33, height: 33, data: vec![f16::from_f32(z); 33 * 33].into_boxed_slice()}, 16.5, 1.0)
+    }
+
+    #[test]
+    fn stopped_fish_keep_their_heading() {
+        let terrain = flat_terrain(-4.0);
+        let mut fish = FishController::new(&terrain, &mut StdRng::seed_from_u64(3));
+        for f in fish.fish.iter_mut() {
+            f.vel = Vec3::ZERO;
+        }
+        let headings: Vec<Vec3> = fish.fish.iter().map(|f| f.heading).collect();
+        // the first tick has no time step, so nothing speeds them up again
+        fish.tick(0.0, &terrain, &[], None);
+        for (f, heading) in fish.fish.iter().zip(headings) {
+            assert!((f.vel - heading * MIN_SPEED).length() < 1e-5, "{:?} for heading {:?}", f.vel, heading);
+            assert!(f.pos.is_finite() && f.heading.is_finite() && f.rotation.is_finite());
+        }
+        for step in 1..240 {
+            fish.tick(step as f64 / 120.0, &terrain, &[], None);
+        }
+        for f in fish.fish.iter() {
+            assert!(f.pos.is_finite() && f.vel.is_finite() && f.rotation.is_finite());
+            assert!(f.vel.length() >= MIN_SPEED - 1e-4);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::path::Path;
+
+use crate::{arrows::QuiverRules, gputil::AssetSource};
+
+// Rules for a run: when it finishes, what happens to smashed pots, and how it is scored.
+// Modes are picked on the title screen and stay selected across runs.
+pub trait GameMode {
+    fn kind(&self) -> ModeKind;
+
+    // rail circuits before the finish, None to keep going until the player ends the run from the pause screen
+    fn laps(&self) -> Option<u32> {
+        None
+    }
+
+    // seconds of rail travel before the finish
+    fn time_limit(&self) -> Option<f64> {
+        self.laps().map(|laps| laps as f64 * RAIL_PERIOD)
+    }
+
+    fn arrow_limit(&self) -> Option<u32> {
+        None
+    }
+
+    // None to shoot as fast as the player can click
+    fn quiver(&self) -> Option<QuiverRules> {
+        None
+    }
+
+    // seconds after being smashed before a pot comes back, None to stay smashed
+    fn respawn_delay(&self) -> Option<f64> {
+        None
+    }
+
+    fn is_finished(&self, run: &RunStatus) -> bool {
+        let out_of_time = self.time_limit().is_some_and(|limit| run.time >= limit);
+        // let the last arrow land before ending the run
+        let out_of_arrows = self.arrow_limit().is_some_and(|limit| run.arrows_shot >= limit && run.arrows_in_flight == 0);
+        out_of_time || out_of_arrows
+    }
+
+    // None for modes which are not scored
+    fn score(&self, run: &RunStatus) -> Option<u32> {
+        Some(run.points)
+    }
+}
+
+// seconds for the boat to make one circuit of the rail
+pub const RAIL_PERIOD: f64 = 180.0;
+
+// What a mode needs to know about the run in progress.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunStatus {
+    pub time: f64, // seconds of rail travel
+    pub arrows_shot: u32,
+    pub arrows_in_flight: usize,
+    pub targets_hit: u32,
+    pub points: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ModeKind {
+    #[default]
+    TimedLaps,
+    Endless,
+    Zen,
+    ScoreAttack,
+    Hunter,
+}
+
+impl ModeKind {
+    pub const ALL: [ModeKind; 5] = [ModeKind::TimedLaps, ModeKind::Endless, ModeKind::Zen, ModeKind::ScoreAttack, ModeKind::Hunter];
+
+    pub fn create(self, config: &ModeConfig) -> Box<dyn GameMode> {
+        match self {
+            ModeKind::TimedLaps => Box::new(TimedLaps),
+            ModeKind::Endless => Box::new(Endless),
+            ModeKind::Zen => Box::new(Zen),
+            ModeKind::ScoreAttack => Box::new(ScoreAttack),
+            ModeKind::Hunter => Box::new(Hunter {quiver: config.hunter_quiver}),
+        }
+    }
+
+    // shown on the title and finish screens, only letters and spaces
+    pub fn name(self) -> &'static str {
+        match self {
+            ModeKind::TimedLaps => "TIMED LAPS",
+            ModeKind::Endless => "ENDLESS",
+            ModeKind::Zen => "ZEN",
+            ModeKind::ScoreAttack => "SCORE ATTACK",
+            ModeKind::Hunter => "HUNTER",
+        }
+    }
+
+    // stable id for replays and saved scores
+    pub fn id(self) -> u8 {
+        match self {
+            ModeKind::TimedLaps => 0,
+            ModeKind::Endless => 1,
+            ModeKind::Zen => 2,
+            ModeKind::ScoreAttack => 3,
+            ModeKind::Hunter => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.id() == id)
+    }
+
+    pub fn cycle(self, forward: bool) -> Self {
+        let n = Self::ALL.len();
+        let i = Self::ALL.iter().position(|m| *m == self).unwrap();
+        Self::ALL[if forward {(i + 1) % n} else {(i + n - 1) % n}]
+    }
+}
+
+// The original game: smash as many pots as possible before the boat completes the rail.
+pub struct TimedLaps;
+
+impl GameMode for TimedLaps {
+    fn kind(&self) -> ModeKind {
+        ModeKind::TimedLaps
+    }
+
+    fn laps(&self) -> Option<u32> {
+        Some(1)
+    }
+}
+
+// Keeps circling with pots coming back, until the player ends the run.
+pub struct Endless;
+
+impl GameMode for Endless {
+    fn kind(&self) -> ModeKind {
+        ModeKind::Endless
+    }
+
+    fn respawn_delay(&self) -> Option<f64> {
+        Some(30.0)
+    }
+}
+
+// Untimed and unscored practice.
+pub struct Zen;
+
+impl GameMode for Zen {
+    fn kind(&self) -> ModeKind {
+        ModeKind::Zen
+    }
+
+    fn respawn_delay(&self) -> Option<f64> {
+        Some(10.0)
+    }
+
+    fn score(&self, _run: &RunStatus) -> Option<u32> {
+        None
+    }
+}
+
+// A fixed number of arrows to smash as much as possible with, within one circuit.
+pub struct ScoreAttack;
+
+impl GameMode for ScoreAttack {
+    fn kind(&self) -> ModeKind {
+        ModeKind::ScoreAttack
+    }
+
+    fn laps(&self) -> Option<u32> {
+        Some(1)
+    }
+
+    fn arrow_limit(&self) -> Option<u32> {
+        Some(30)
+    }
+}
+
+// Two circuits with a quiver that refills each lap, where every pot smashed earns its arrow back.
+pub struct Hunter {
+    quiver: QuiverRules,
+}
+
+impl GameMode for Hunter {
+    fn kind(&self) -> ModeKind {
+        ModeKind::Hunter
+    }
+
+    fn laps(&self) -> Option<u32> {
+        Some(2)
+    }
+
+    fn quiver(&self) -> Option<QuiverRules> {
+        Some(self.quiver)
+    }
+}
+
+// Tuning for the modes which have any, loaded from `modes.cfg` as `<key> = <value>` lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModeConfig {
+    pub hunter_quiver: QuiverRules,
+}
+
+const DEFAULT_MODE_CONFIG: &str = include_str!("../assets/modes.cfg");
+
+impl Default for ModeConfig {
+    fn default() -> Self {
+        Self::parse(DEFAULT_MODE_CONFIG).unwrap()
+    }
+}
+
+impl ModeConfig {
+    // falls back to the built in tuning if the file is missing or invalid
+    pub fn load(assets: &impl AssetSource) -> Self {
+        let bytes = match assets.get_bytes(Path::new("modes.cfg")) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("using default mode tuning, could not read modes.cfg: {}", e);
+                return Self::default();
+            }
+        };
+        match std::str::from_utf8(&bytes).map_err(|e| e.to_string()).and_then(Self::parse) {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!("using default mode tuning, modes.cfg is invalid: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    // every key has to be given, so that the file in the assets folder is the one place the defaults live
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (mut arrows_per_lap, mut reload_time, mut refund_on_hit) = (None, None, None);
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: String| format!("line {}: {}", line_num + 1, msg);
+
+            let (key, value) = line.split_once('=').ok_or_else(|| err("expected <key> = <value>".into()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || err(format!("invalid value {} for {}", value, key));
+            match key {
+                "hunter_arrows_per_lap" => arrows_per_lap = Some(value.parse().ok().filter(|n: &u32| *n > 0).ok_or_else(invalid)?),
+                "hunter_reload_time" => reload_time = Some(value.parse().ok().filter(|t: &f64| *t >= 0.0).ok_or_else(invalid)?),
+                "hunter_refund_on_hit" => refund_on_hit = Some(value.parse().map_err(|_| invalid())?),
+                _ => return Err(err(format!("unknown key {}", key))),
+            }
+        }
+
+        let missing = |key: &str| format!("missing {}", key);
+        Ok(ModeConfig {
+            hunter_quiver: QuiverRules {
+                arrows_per_lap: arrows_per_lap.ok_or_else(|| missing("hunter_arrows_per_lap"))?,
+                reload_time: reload_time.ok_or_else(|| missing("hunter_reload_time"))?,
+                refund_on_hit: refund_on_hit.ok_or_else(|| missing("hunter_refund_on_hit"))?,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config() {
+        let config = ModeConfig::default();
+        assert_eq!(config.hunter_quiver, QuiverRules {arrows_per_lap: 15, reload_time: 0.75, refund_on_hit: true});
+        assert_eq!(ModeKind::Hunter.create(&config).quiver(), Some(config.hunter_quiver));
+        for mode in ModeKind::ALL.into_iter().filter(|m| *m != ModeKind::Hunter) {
+            assert_eq!(mode.create(&config).quiver(), None);
+        }
+    }
+
+    #[test]
+    fn parse_config() {
+        let config = ModeConfig::parse("hunter_refund_on_hit = false # stingy\nhunter_reload_time = 0\n\nhunter_arrows_per_lap=3").unwrap();
+        assert_eq!(config.hunter_quiver, QuiverRules {arrows_per_lap: 3, reload_time: 0.0, refund_on_hit: false});
+
+        let full = "hunter_arrows_per_lap = 15\nhunter_reload_time = 0.75\nhunter_refund_on_hit = true\n";
+        for (bad, line) in [
+            ("hunter_arrows_per_lap = 0", 4),
+            ("hunter_arrows_per_lap = 1.5", 4),
+            ("hunter_reload_time = -1", 4),
+            ("hunter_refund_on_hit = yes", 4),
+            ("hunter_quiver = 2", 4),
+            ("hunter_reload_time", 4),
+        ] {
+            let e = ModeConfig::parse(&(full.to_string() + bad)).unwrap_err();
+            assert!(e.starts_with(&format!("line {}:", line)), "{:?} gave {}", bad, e);
+        }
+        assert!(ModeConfig::parse("hunter_arrows_per_lap = 15\nhunter_reload_time = 0.75").is_err());
+    }
+}
@@ -9,7 +9,9 @@ use std::sync::OnceLock;
 use half::f16;
 
 // structure used for collision with heightmaps,
-// each level stores the range of imput values within its texel
+// each level stores the range of imput values within its texel.
+// Bins are over the cells between texel centers, where bilinear sampling interpolates,
+// so a level 1 bin covers 2x2 cells and takes the range of the 3x3 texels at their corners.
 pub struct BakedRangeMips {
     pub orig_size: u32,
     pub num_mip_levels: usize,
@@ -18,16 +20,64 @@ pub struct BakedRangeMips {
 }
 
 impl BakedRangeMips {
+    pub fn from_heightmap(heightmap: &PlanarImage<f16>) -> Self {
+        assert_eq!(heightmap.width, heightmap.height, "range mips need a square heightmap");
+        let orig_size = heightmap.width as u32;
+        let mut level_offsets = vec![(0, 0)]; // level 0 is the heightmap itself
+        let mut raw_data: Vec<[f16; 2]> = Vec::new();
+
+        let mut size = orig_size / 2;
+        level_offsets.push((0, size as usize));
+        for y in 0..size {
+            for x in 0..size {
+                let mut range = [f16::INFINITY, f16::NEG_INFINITY];
+                for ty in 2*y..=(2*y + 2).min(orig_size - 1) {
+                    for tx in 2*x..=(2*x + 2).min(orig_size - 1) {
+                        let h = heightmap.pixel_at(uvec2(tx, ty));
+                        range = [range[0].min(h), range[1].max(h)];
+                    }
+                }
+                raw_data.push(range);
+            }
+        }
+
+        while size > 1 {
+            let (prev_offset, prev_size) = *level_offsets.last().unwrap();
+            let offset = raw_data.len();
+            size = size.div_ceil(2);
+            level_offsets.push((offset, size as usize));
+            for y in 0..size {
+                for x in 0..size {
+                    let mut range = [f16::INFINITY, f16::NEG_INFINITY];
+                    for sub in Self::sub_bins(uvec2(x, y)) {
+                        if (sub.x as usize) < prev_size && (sub.y as usize) < prev_size {
+                            let sub_range = raw_data[prev_offset + prev_size * sub.y as usize + sub.x as usize];
+                            range = [range[0].min(sub_range[0]), range[1].max(sub_range[1])];
+                        }
+                    }
+                    raw_data.push(range);
+                }
+            }
+        }
+
+        BakedRangeMips {
+            orig_size,
+            num_mip_levels: level_offsets.len() - 1,
+            level_offsets: level_offsets.into_boxed_slice(),
+            raw_data: raw_data.into_boxed_slice(),
+        }
+    }
+
+    // number of bins across a level
+    pub fn level_size(&self, level: usize) -> u32 {
+        self.level_offsets[level].1 as u32
+    }
+
     pub fn sub_bins(ij: UVec2) -> [UVec2; 4] {
         [uvec2(2*ij.x,     2*ij.y), uvec2(2*ij.x,     2*ij.y + 1),
          uvec2(2*ij.x + 1, 2*ij.y), uvec2(2*ij.x + 1, 2*ij.y + 1)]
     } 
 
-    pub fn get_range_uv(&self, uv: Vec2, level: usize) -> (f32, f32) {
-        let bin = (uv * self.orig_size as f32).floor().as_uvec2() / (1u32 << level);
-        self.get_bin(bin, level)
-    }
-
     pub fn get_bin(&self, bin: UVec2, level: usize) -> (f32, f32) {
         if level == 0 || level > self.num_mip_levels {
             panic!{"mip level out of bounds"}
@@ -0,0 +1,287 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::game_mode::ModeKind;
+
+pub const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HighScore {
+    pub initials: [u8; 3], // A-Z
+    pub score: u32,
+}
+
+impl HighScore {
+    pub fn initials_str(&self) -> &str {
+        std::str::from_utf8(&self.initials).unwrap_or("???")
+    }
+}
+
+// Best scores for each mode and seed, as `<mode id> <seed> <initials> <score>` lines.
+// Natively this lives in high_scores.cfg in the working directory, on the web in localStorage.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HighScoreTable {
+    tables: HashMap<(ModeKind, u32), Vec<HighScore>>, // sorted best first
+    #[cfg(not(target_arch = "wasm32"))]
+    path: Option<std::path::PathBuf>, // where `save` writes to
+}
+
+impl HighScoreTable {
+    // invalid lines are skipped with a warning
+    pub fn parse(text: &str) -> Self {
+        let mut table = HighScoreTable::default();
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match words[..] {
+                [mode, seed, initials, score] => (|| {
+                    let mode = ModeKind::from_id(mode.parse().ok()?)?;
+                    let seed = seed.parse().ok()?;
+                    let initials: [u8; 3] = initials.as_bytes().try_into().ok()?;
+                    let score = score.parse().ok()?;
+                    initials.iter().all(u8::is_ascii_uppercase).then_some((mode, seed, HighScore {initials, score}))
+                })(),
+                _ => None,
+            };
+            match parsed {
+                Some((mode, seed, entry)) => {
+                    table.insert(mode, seed, entry);
+                }
+                None => log::warn!("high scores line {}: expected <mode id> <seed> <initials> <score>", line_num + 1),
+            }
+        }
+        table
+    }
+
+    pub fn to_config_string(&self) -> String {
+        let mut keys: Vec<_> = self.tables.keys().copied().collect();
+        keys.sort_by_key(|(mode, seed)| (mode.id(), *seed));
+
+        let mut out = String::from("# <mode id> <seed> <initials> <score>\n");
+        for (mode, seed) in keys {
+            for entry in self.tables[&(mode, seed)].iter() {
+                let _ = writeln!(out, "{} {} {} {}", mode.id(), seed, entry.initials_str(), entry.score);
+            }
+        }
+        out
+    }
+
+    pub fn top(&self, mode: ModeKind, seed: u32) -> &[HighScore] {
+        self.tables.get(&(mode, seed)).map_or(&[], |t| t.as_slice())
+    }
+
+    pub fn qualifies(&self, mode: ModeKind, seed: u32, score: u32) -> bool {
+        let top = self.top(mode, seed);
+        score > 0 && (top.len() < MAX_HIGH_SCORES || top.last().is_some_and(|last| score > last.score))
+    }
+
+    // returns the rank of the new entry, or None if it didn't make the table
+    pub fn insert(&mut self, mode: ModeKind, seed: u32, entry: HighScore) -> Option<usize> {
+        let top = self.tables.entry((mode, seed)).or_default();
+        // ties go to whoever got there first
+        let rank = top.iter().position(|e| entry.score > e.score).unwrap_or(top.len());
+        top.insert(rank, entry);
+        top.truncate(MAX_HIGH_SCORES);
+        (rank < MAX_HIGH_SCORES).then_some(rank)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HighScoreTable {
+    // a missing file gives an empty table, which is saved back to the same path
+    pub fn load_file(path: &std::path::Path) -> std::io::Result<Self> {
+        let mut table = match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e),
+        };
+        table.path = Some(path.to_owned());
+        Ok(table)
+    }
+
+    pub fn save(&self) {
+        let Some(path) = &self.path else { return };
+        if let Err(e) = std::fs::write(path, self.to_config_string()) {
+            log::error!("could not save high scores to {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl HighScoreTable {
+    const STORAGE_KEY: &'static str = "bowfishing_blitz.high_scores";
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    pub fn load_local_storage() -> Self {
+        let text = Self::local_storage().and_then(|s| s.get_item(Self::STORAGE_KEY).ok().flatten());
+        text.map(|t| Self::parse(&t)).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let saved = Self::local_storage().map(|s| s.set_item(Self::STORAGE_KEY, &self.to_config_string()));
+        if !matches!(saved, Some(Ok(()))) {
+            log::warn!("could not save high scores to localStorage");
+        }
+    }
+}
+
+// Arcade style initials entry after a run that made the table.
+// Letters are picked with up and down and confirmed one at a time, or typed directly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InitialsEntry {
+    pub mode: ModeKind,
+    pub seed: u32,
+    pub score: u32,
+    pub initials: [u8; 3],
+    pub cursor: usize,
+}
+
+impl InitialsEntry {
+    pub fn new(mode: ModeKind, seed: u32, score: u32) -> Self {
+        InitialsEntry {mode, seed, score, initials: *b"AAA", cursor: 0}
+    }
+
+    pub fn cycle_letter(&mut self, forward: bool) {
+        let c = self.initials[self.cursor] - b'A';
+        self.initials[self.cursor] = b'A' + if forward {(c + 1) % 26} else {(c + 25) % 26};
+    }
+
+    pub fn move_cursor(&mut self, forward: bool) {
+        self.cursor = if forward {(self.cursor + 1).min(2)} else {self.cursor.saturating_sub(1)};
+    }
+
+    // returns true once the last letter is confirmed
+    pub fn confirm_letter(&mut self) -> bool {
+        if self.cursor == 2 {
+            true
+        } else {
+            self.cursor += 1;
+            false
+        }
+    }
+
+    // returns true once the last letter is typed, other characters are ignored
+    pub fn type_letter(&mut self, c: char) -> bool {
+        if !c.is_ascii_alphabetic() {
+            return false;
+        }
+        self.initials[self.cursor] = c.to_ascii_uppercase() as u8;
+        self.confirm_letter()
+    }
+
+    pub fn entry(&self) -> HighScore {
+        HighScore {initials: self.initials, score: self.score}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(initials: &[u8; 3], score: u32) -> HighScore {
+        HighScore {initials: *initials, score}
+    }
+
+    fn scores(table: &HighScoreTable, mode: ModeKind, seed: u32) -> Vec<u32> {
+        table.top(mode, seed).iter().map(|e| e.score).collect()
+    }
+
+    #[test]
+    fn insert_keeps_order_and_cap() {
+        let mut table = HighScoreTable::default();
+        let mode = ModeKind::TimedLaps;
+        assert_eq!(table.insert(mode, 1, score(b"AAA", 50)), Some(0));
+        assert_eq!(table.insert(mode, 1, score(b"BBB", 70)), Some(0));
+        assert_eq!(table.insert(mode, 1, score(b"CCC", 60)), Some(1));
+        assert_eq!(table.insert(mode, 1, score(b"DDD", 10)), Some(3));
+        assert_eq!(scores(&table, mode, 1), [70, 60, 50, 10]);
+
+        for i in 0..10 {
+            table.insert(mode, 1, score(b"EEE", 20 + i));
+        }
+        assert_eq!(table.top(mode, 1).len(), MAX_HIGH_SCORES);
+        assert_eq!(scores(&table, mode, 1), [70, 60, 50, 29, 28, 27, 26, 25, 24, 23]);
+        // the table is full, so anything not beating the last entry is dropped
+        assert_eq!(table.insert(mode, 1, score(b"FFF", 5)), None);
+        assert_eq!(table.insert(mode, 1, score(b"FFF", 23)), None);
+        assert_eq!(table.insert(mode, 1, score(b"FFF", 24)), Some(9));
+        assert_eq!(*table.top(mode, 1).last().unwrap(), score(b"FFF", 24));
+        assert_eq!(table.top(mode, 1).len(), MAX_HIGH_SCORES);
+    }
+
+    #[test]
+    fn ties_go_to_the_first() {
+        let mut table = HighScoreTable::default();
+        let mode = ModeKind::Endless;
+        table.insert(mode, 0, score(b"AAA", 100));
+        assert_eq!(table.insert(mode, 0, score(b"BBB", 100)), Some(1));
+        assert_eq!(table.insert(mode, 0, score(b"CCC", 100)), Some(2));
+        let initials: Vec<&str> = table.top(mode, 0).iter().map(|e| e.initials_str()).collect();
+        assert_eq!(initials, ["AAA", "BBB", "CCC"]);
+    }
+
+    #[test]
+    fn qualifies_matches_insert() {
+        let mut table = HighScoreTable::default();
+        let mode = ModeKind::ScoreAttack;
+        assert!(!table.qualifies(mode, 3, 0));
+        assert!(table.qualifies(mode, 3, 1));
+        for i in 0..MAX_HIGH_SCORES as u32 {
+            assert!(table.qualifies(mode, 3, 10 + i));
+            table.insert(mode, 3, score(b"AAA", 10 + i));
+        }
+        assert!(!table.qualifies(mode, 3, 9));
+        assert!(!table.qualifies(mode, 3, 10)); // a tie with the last entry does not make it
+        assert!(table.qualifies(mode, 3, 11));
+        for s in [9, 10, 11, 30] {
+            let qualifies = table.qualifies(mode, 3, s);
+            assert_eq!(table.clone().insert(mode, 3, score(b"ZZZ", s)).is_some(), qualifies, "score {}", s);
+        }
+    }
+
+    #[test]
+    fn keyed_by_mode_and_seed() {
+        let mut table = HighScoreTable::default();
+        for _ in 0..MAX_HIGH_SCORES {
+            table.insert(ModeKind::Zen, 5, score(b"AAA", 1000));
+        }
+        assert!(!table.qualifies(ModeKind::Zen, 5, 500));
+        assert!(table.qualifies(ModeKind::Zen, 6, 500));
+        assert!(table.qualifies(ModeKind::Hunter, 5, 500));
+        assert_eq!(table.insert(ModeKind::Hunter, 5, score(b"BBB", 500)), Some(0));
+        assert_eq!(scores(&table, ModeKind::Hunter, 5), [500]);
+        assert!(table.top(ModeKind::Zen, 6).is_empty());
+    }
+
+    #[test]
+    fn parse_and_write() {
+        let text = "# comment\n\
+            0 7 ABC 300\n\
+            0 7 XYZ 900 # best\n\
+            4 7 HNT 50\n\
+            0 8 ABC 10\n\
+            0 7 abc 5\n\
+            9 7 ABC 5\n\
+            0 x ABC 5\n\
+            0 7 ABCD 5\n\
+            0 7 ABC -5\n\
+            0 7 ABC\n";
+        let table = HighScoreTable::parse(text);
+        assert_eq!(table.top(ModeKind::TimedLaps, 7), [score(b"XYZ", 900), score(b"ABC", 300)]);
+        assert_eq!(scores(&table, ModeKind::Hunter, 7), [50]);
+        assert_eq!(scores(&table, ModeKind::TimedLaps, 8), [10]);
+        assert_eq!(table.tables.len(), 3);
+
+        let written = table.to_config_string();
+        assert_eq!(HighScoreTable::parse(&written), table);
+
+        // more than fit in a table are cut to the best
+        let many: String = (0..15).map(|i| format!("2 1 AAA {}\n", i)).collect();
+        assert_eq!(scores(&HighScoreTable::parse(&many), ModeKind::Zen, 1), [14, 13, 12, 11, 10, 9, 8, 7, 6, 5]);
+    }
+}
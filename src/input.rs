@@ -0,0 +1,240 @@
+use std::path::Path;
+
+use crate::gputil::AssetSource;
+
+// Things the player can do, independent of which key or button does them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputAction {
+    Shoot,
+    Pause,
+    ToggleMusic,
+    Zoom,
+    NextMode,
+    PrevMode,
+    LetterUp,
+    LetterDown,
+    LookX,
+    LookY,
+}
+
+impl InputAction {
+    const NAMES: [(&'static str, InputAction); 10] = [
+        ("shoot", InputAction::Shoot),
+        ("pause", InputAction::Pause),
+        ("toggle_music", InputAction::ToggleMusic),
+        ("zoom", InputAction::Zoom),
+        ("next_mode", InputAction::NextMode),
+        ("prev_mode", InputAction::PrevMode),
+        ("letter_up", InputAction::LetterUp),
+        ("letter_down", InputAction::LetterDown),
+        ("look_x", InputAction::LookX),
+        ("look_y", InputAction::LookY),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES.iter().find(|(n, _)| *n == name).map(|(_, action)| *action)
+    }
+
+    pub fn is_axis(self) -> bool {
+        matches!(self, InputAction::LookX | InputAction::LookY)
+    }
+}
+
+// Raw input from a front end, before bindings are applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RawInput<'a> {
+    // key names follow the browser's KeyboardEvent.key, mouse buttons are mouse_left, mouse_right, mouse_middle
+    Button {name: &'a str, pressed: bool},
+    MouseMotion {dx: f64, dy: f64},
+}
+
+// Bound input as seen by the game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ActionEvent {
+    Pressed(InputAction),
+    Released(InputAction),
+    Look {dx: f32, dy: f32},
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Binding {
+    source: String,
+    action: InputAction,
+    scale: f32,
+}
+
+// Table mapping raw input to actions, loaded from `bindings.cfg`.
+// Each line reads `<source> = <action> [scale]`, see the file in the assets folder for details.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputBindings {
+    bindings: Vec<Binding>,
+}
+
+const DEFAULT_BINDINGS: &str = include_str!("../assets/bindings.cfg");
+const AXIS_SOURCES: [&str; 2] = ["mouse_x", "mouse_y"];
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self::parse(DEFAULT_BINDINGS).unwrap()
+    }
+}
+
+impl InputBindings {
+    // falls back to the built in bindings if the file is missing or invalid
+    pub fn load(assets: &impl AssetSource) -> Self {
+        let bytes = match assets.get_bytes(Path::new("bindings.cfg")) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("using default bindings, could not read bindings.cfg: {}", e);
+                return Self::default();
+            }
+        };
+        match std::str::from_utf8(&bytes).map_err(|e| e.to_string()).and_then(Self::parse) {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                log::error!("using default bindings, bindings.cfg is invalid: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut bindings = Vec::new();
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let err = |msg: String| format!("line {}: {}", line_num + 1, msg);
+
+            let (source, rhs) = line.split_once('=').ok_or_else(|| err("expected <source> = <action>".into()))?;
+            let source = normalize_button_name(source.trim());
+            let mut words = rhs.split_whitespace();
+            let action_name = words.next().ok_or_else(|| err("missing action".into()))?;
+            let action = InputAction::from_name(action_name).ok_or_else(|| err(format!("unknown action {}", action_name)))?;
+            let scale = match words.next() {
+                Some(s) => s.parse::<f32>().map_err(|_| err(format!("invalid scale {}", s)))?,
+                None => 1.0,
+            };
+            if words.next().is_some() {
+                return Err(err("too many values".into()));
+            }
+
+            let is_axis_source = AXIS_SOURCES.contains(&source.as_str());
+            if action.is_axis() != is_axis_source {
+                return Err(err(format!("{} can only be bound to {}", action_name, if action.is_axis() {"mouse_x or mouse_y"} else {"keys and buttons"})));
+            }
+            bindings.push(Binding {source, action, scale});
+        }
+        Ok(InputBindings {bindings})
+    }
+
+    // a button may be bound to several actions
+    pub fn map(&self, input: RawInput) -> Vec<ActionEvent> {
+        match input {
+            RawInput::Button { name, pressed } => {
+                let name = normalize_button_name(name);
+                self.bindings.iter().filter(|b| b.source == name).map(|b| {
+                    if pressed {
+                        ActionEvent::Pressed(b.action)
+                    } else {
+                        ActionEvent::Released(b.action)
+                    }
+                }).collect()
+            }
+            RawInput::MouseMotion { dx, dy } => {
+                let (mut look_x, mut look_y) = (0.0, 0.0);
+                for b in self.bindings.iter() {
+                    let value = match b.source.as_str() {
+                        "mouse_x" => dx,
+                        "mouse_y" => dy,
+                        _ => continue,
+                    };
+                    match b.action {
+                        InputAction::LookX => look_x += value * b.scale as f64,
+                        InputAction::LookY => look_y += value * b.scale as f64,
+                        _ => {}
+                    }
+                }
+                if look_x == 0.0 && look_y == 0.0 {
+                    vec![]
+                } else {
+                    vec![ActionEvent::Look {dx: look_x as f32, dy: look_y as f32}]
+                }
+            }
+        }
+    }
+}
+
+// key names are matched case-insensitively, and the browser reports the space bar as " "
+fn normalize_button_name(name: &str) -> String {
+    if name == " " {
+        "space".into()
+    } else {
+        name.to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(name: &str) -> RawInput<'_> {
+        RawInput::Button {name, pressed: true}
+    }
+
+    #[test]
+    fn default_parses() {
+        let bindings = InputBindings::default();
+        assert_eq!(bindings.map(press("mouse_left")), vec![ActionEvent::Pressed(InputAction::Shoot)]);
+        assert_eq!(bindings.map(press("Escape")), vec![ActionEvent::Pressed(InputAction::Pause)]);
+        assert_eq!(bindings.map(press("ArrowUp")), vec![ActionEvent::Pressed(InputAction::LetterUp)]);
+        assert_eq!(bindings.map(RawInput::MouseMotion {dx: 2.0, dy: -1.0}), vec![ActionEvent::Look {dx: 2.0, dy: -1.0}]);
+        assert!(bindings.map(press("q")).is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let line_err = |text: &str| InputBindings::parse(text).unwrap_err();
+        assert_eq!(line_err("# comment\n\nspace shoot"), "line 3: expected <source> = <action>");
+        assert_eq!(line_err("space ="), "line 1: missing action");
+        assert_eq!(line_err("space = jump"), "line 1: unknown action jump");
+        assert_eq!(line_err("mouse_x = look_x fast"), "line 1: invalid scale fast");
+        assert_eq!(line_err("mouse_x = look_x 1 2"), "line 1: too many values");
+        assert_eq!(line_err("space = look_x"), "line 1: look_x can only be bound to mouse_x or mouse_y");
+        assert_eq!(line_err("mouse_y = shoot"), "line 1: shoot can only be bound to keys and buttons");
+        assert_eq!(InputBindings::parse("# nothing bound\n"), Ok(InputBindings {bindings: vec![]}));
+    }
+
+    #[test]
+    fn key_names() {
+        let bindings = InputBindings::parse("Space = shoot\nA = prev_mode # trailing comment").unwrap();
+        for name in [" ", "space", "SPACE"] {
+            assert_eq!(bindings.map(press(name)), vec![ActionEvent::Pressed(InputAction::Shoot)], "{:?}", name);
+        }
+        assert_eq!(bindings.map(press("a")), vec![ActionEvent::Pressed(InputAction::PrevMode)]);
+        assert_eq!(bindings.map(press("A")), vec![ActionEvent::Pressed(InputAction::PrevMode)]);
+    }
+
+    #[test]
+    fn axis_scale_and_invert() {
+        let bindings = InputBindings::parse("mouse_x = look_x 0.5\nmouse_y = look_y -2").unwrap();
+        assert_eq!(bindings.map(RawInput::MouseMotion {dx: 4.0, dy: 1.0}), vec![ActionEvent::Look {dx: 2.0, dy: -2.0}]);
+        assert!(bindings.map(RawInput::MouseMotion {dx: 0.0, dy: 0.0}).is_empty());
+
+        // axes bound twice add up, unbound ones stay still
+        let bindings = InputBindings::parse("mouse_x = look_x\nmouse_x = look_x 2").unwrap();
+        assert_eq!(bindings.map(RawInput::MouseMotion {dx: 1.0, dy: 5.0}), vec![ActionEvent::Look {dx: 3.0, dy: 0.0}]);
+        assert!(bindings.map(RawInput::MouseMotion {dx: 0.0, dy: 5.0}).is_empty());
+    }
+
+    #[test]
+    fn press_and_release() {
+        let bindings = InputBindings::parse("z = zoom\nz = next_mode\nmouse_right = zoom").unwrap();
+        assert_eq!(bindings.map(press("z")), vec![ActionEvent::Pressed(InputAction::Zoom), ActionEvent::Pressed(InputAction::NextMode)]);
+        assert_eq!(bindings.map(RawInput::Button {name: "Z", pressed: false}),
+            vec![ActionEvent::Released(InputAction::Zoom), ActionEvent::Released(InputAction::NextMode)]);
+        assert_eq!(bindings.map(RawInput::Button {name: "mouse_right", pressed: false}), vec![ActionEvent::Released(InputAction::Zoom)]);
+        assert!(bindings.map(press("mouse_left")).is_empty());
+    }
+}
@@ -0,0 +1,480 @@
+use std::fmt::Write;
+use std::path::Path;
+
+use glam::*;
+
+use crate::archetypes::ArchetypeSet;
+use crate::gputil::AssetSource;
+
+// One target of a layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TargetPlacement {
+    pub bottom: Vec3,
+    pub rotation: Quat,
+    pub archetype: usize, // index into the ArchetypeSet, named in files
+    pub floating: bool, // bobs on the surface over `bottom`, only the yaw of `rotation` counts
+    pub wave: usize,
+}
+
+// Where the targets of a run are, and when each appears.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelLayout {
+    pub targets: Vec<TargetPlacement>,
+    pub waves: Vec<f64>, // seconds of rail travel from the start of the run, waves at 0 or before are there from the start
+}
+
+pub const MAX_LAYOUT_TARGETS: usize = 512;
+
+// How the targets are laid out for each run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutGenerator {
+    Procedural, // scattered over the pond, differently for every seed
+    Fixed(LevelLayout), // hand authored, the same every run
+}
+
+impl LayoutGenerator {
+    // uses level.json if there is one, scattering targets otherwise
+    pub fn load(assets: &impl AssetSource, archetypes: &ArchetypeSet) -> Self {
+        let Ok(bytes) = assets.get_bytes(Path::new("level.json")) else {
+            log::info!("no level.json, scattering targets");
+            return LayoutGenerator::Procedural;
+        };
+        match std::str::from_utf8(&bytes).map_err(|e| e.to_string()).and_then(|text| LevelLayout::parse(text, archetypes)) {
+            Ok(layout) => {
+                log::info!("loaded {} targets from level.json", layout.targets.len());
+                LayoutGenerator::Fixed(layout)
+            }
+            Err(e) => {
+                log::error!("scattering targets, level.json is invalid: {}", e);
+                LayoutGenerator::Procedural
+            }
+        }
+    }
+}
+
+// Level files are JSON, of the form
+// {"waves": [0, 60], "targets": [{"archetype": "pot", "pos": [x, y, z], "rotation": [x, y, z, w], "floating": false, "wave": 0}, ...]}
+// where pos is the bottom of the target, and waves, rotation, floating and wave may be left out.
+impl LevelLayout {
+    pub fn parse(text: &str, archetypes: &ArchetypeSet) -> Result<Self, String> {
+        let root = JsonParser::parse(text)?;
+        let mut layout = LevelLayout {targets: Vec::new(), waves: vec![0.0]};
+        let mut targets = None;
+        for (key, value) in root.as_object("the level")? {
+            match key.as_str() {
+                "waves" => layout.waves = value.as_numbers("waves", None)?,
+                "targets" => targets = Some(value.as_array("targets")?),
+                _ => return Err(format!("unknown key {}", key)),
+            }
+        }
+        if layout.waves.is_empty() {
+            return Err("needs at least one wave".into());
+        }
+        let targets = targets.ok_or("no targets")?;
+        if targets.len() > MAX_LAYOUT_TARGETS {
+            return Err(format!("no more than {} targets", MAX_LAYOUT_TARGETS));
+        }
+
+        for (i, target) in targets.iter().enumerate() {
+            let err = |msg: String| format!("target {}: {}", i, msg);
+            let mut placement = TargetPlacement {bottom: Vec3::NAN, rotation: Quat::IDENTITY, archetype: usize::MAX, floating: false, wave: 0};
+            for (key, value) in target.as_object("a target").map_err(err)? {
+                match key.as_str() {
+                    "archetype" => {
+                        let name = value.as_str("archetype").map_err(err)?;
+                        placement.archetype = archetypes.archetypes.iter().position(|a| a.name == name)
+                            .ok_or_else(|| err(format!("unknown archetype {}", name)))?;
+                    }
+                    "pos" => placement.bottom = Vec3::from_slice(&value.as_numbers("pos", Some(3)).map_err(err)?.iter().map(|v| *v as f32).collect::<Vec<_>>()),
+                    "rotation" => {
+                        let q = value.as_numbers("rotation", Some(4)).map_err(err)?;
+                        let q = vec4(q[0] as f32, q[1] as f32, q[2] as f32, q[3] as f32).try_normalize()
+                            .ok_or_else(|| err("rotation can't be zero".into()))?;
+                        placement.rotation = Quat::from_vec4(q);
+                    }
+                    "floating" => placement.floating = value.as_bool("floating").map_err(err)?,
+                    "wave" => {
+                        let wave = value.as_number("wave").map_err(err)?;
+                        if wave < 0.0 || wave.fract() != 0.0 || wave as usize >= layout.waves.len() {
+                            return Err(err(format!("wave {} is not one of the {} waves", wave, layout.waves.len())));
+                        }
+                        placement.wave = wave as usize;
+                    }
+                    _ => return Err(err(format!("unknown key {}", key))),
+                }
+            }
+            if placement.bottom.is_nan() {
+                return Err(err("needs a pos".into()));
+            }
+            if placement.archetype == usize::MAX {
+                return Err(err("needs an archetype".into()));
+            }
+            layout.targets.push(placement);
+        }
+        Ok(layout)
+    }
+
+    // in the same form as read, one target per line, so saved layouts are easy to edit and diff
+    pub fn to_json(&self, archetypes: &ArchetypeSet) -> String {
+        let waves: Vec<String> = self.waves.iter().map(|w| w.to_string()).collect();
+        let mut out = format!("{{\"waves\":[{}],\"targets\":[", waves.join(","));
+        for (i, t) in self.targets.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let name = archetypes.archetypes.get(t.archetype).map_or("", |a| a.name.as_str());
+            let _ = write!(out, "\n{{\"archetype\":{},\"pos\":[{},{},{}],\"rotation\":[{},{},{},{}],\"floating\":{},\"wave\":{}}}",
+                json_string(name),
+                t.bottom.x, t.bottom.y, t.bottom.z,
+                t.rotation.x, t.rotation.y, t.rotation.z, t.rotation.w,
+                t.floating, t.wave,
+            );
+        }
+        out.push_str("\n]}\n");
+        out
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Just enough JSON for level files.
+#[derive(Clone, Debug, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>), // in file order
+}
+
+impl Json {
+    fn as_object(&self, what: &str) -> Result<&[(String, Json)], String> {
+        match self {
+            Json::Object(fields) => Ok(fields),
+            _ => Err(format!("expected {} to be an object", what)),
+        }
+    }
+
+    fn as_array(&self, what: &str) -> Result<&[Json], String> {
+        match self {
+            Json::Array(items) => Ok(items),
+            _ => Err(format!("expected {} to be an array", what)),
+        }
+    }
+
+    fn as_str(&self, what: &str) -> Result<&str, String> {
+        match self {
+            Json::String(s) => Ok(s),
+            _ => Err(format!("expected {} to be a string", what)),
+        }
+    }
+
+    fn as_bool(&self, what: &str) -> Result<bool, String> {
+        match self {
+            Json::Bool(b) => Ok(*b),
+            _ => Err(format!("expected {} to be true or false", what)),
+        }
+    }
+
+    fn as_number(&self, what: &str) -> Result<f64, String> {
+        match self {
+            Json::Number(v) => Ok(*v),
+            _ => Err(format!("expected {} to be a number", what)),
+        }
+    }
+
+    // an array of numbers, of exactly `len` if given
+    fn as_numbers(&self, what: &str, len: Option<usize>) -> Result<Vec<f64>, String> {
+        let values = self.as_array(what)?.iter().map(|v| v.as_number(what)).collect::<Result<Vec<_>, _>>()?;
+        match len {
+            Some(len) if values.len() != len => Err(format!("expected {} numbers for {}", len, what)),
+            _ => Ok(values),
+        }
+    }
+}
+
+struct JsonParser<'a> {
+    text: &'a str,
+    pos: usize, // in bytes
+}
+
+impl JsonParser<'_> {
+    fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = JsonParser {text, pos: 0};
+        let value = parser.value()?;
+        parser.skip_space();
+        if parser.pos < text.len() {
+            return Err(parser.error("expected the end of the file"));
+        }
+        Ok(value)
+    }
+
+    fn error(&self, msg: &str) -> String {
+        let line = self.text[..self.pos].matches('\n').count() + 1;
+        format!("line {}: {}", line, msg)
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_space(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    // skips past `token` if it comes next
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_space();
+        if self.eat("{") {
+            let mut fields = Vec::new();
+            if self.eat("}") {
+                return Ok(Json::Object(fields));
+            }
+            loop {
+                self.skip_space();
+                let key = self.string()?;
+                if !self.eat(":") {
+                    return Err(self.error("expected :"));
+                }
+                fields.push((key, self.value()?));
+                if self.eat("}") {
+                    return Ok(Json::Object(fields));
+                }
+                if !self.eat(",") {
+                    return Err(self.error("expected , or }"));
+                }
+            }
+        } else if self.eat("[") {
+            let mut items = Vec::new();
+            if self.eat("]") {
+                return Ok(Json::Array(items));
+            }
+            loop {
+                items.push(self.value()?);
+                if self.eat("]") {
+                    return Ok(Json::Array(items));
+                }
+                if !self.eat(",") {
+                    return Err(self.error("expected , or ]"));
+                }
+            }
+        } else if self.rest().starts_with('"') {
+            self.string().map(Json::String)
+        } else if self.eat("true") {
+            Ok(Json::Bool(true))
+        } else if self.eat("false") {
+            Ok(Json::Bool(false))
+        } else if self.eat("null") {
+            Ok(Json::Null)
+        } else {
+            self.number().map(Json::Number)
+        }
+    }
+
+    // -?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?
+    fn number(&mut self) -> Result<f64, String> {
+        let bytes = self.rest().as_bytes();
+        let digits_from = |i: usize| i + bytes[i.min(bytes.len())..].iter().take_while(|b| b.is_ascii_digit()).count();
+        let mut len = usize::from(bytes.first() == Some(&b'-'));
+        let int_end = digits_from(len);
+        if int_end == len || (bytes[len] == b'0' && int_end > len + 1) {
+            return Err(self.error("expected a value"));
+        }
+        len = int_end;
+        if bytes.get(len) == Some(&b'.') {
+            let frac_end = digits_from(len + 1);
+            if frac_end == len + 1 {
+                return Err(self.error("expected digits after ."));
+            }
+            len = frac_end;
+        }
+        if matches!(bytes.get(len), Some(b'e' | b'E')) {
+            len += 1;
+            if matches!(bytes.get(len), Some(b'+' | b'-')) {
+                len += 1;
+            }
+            let exp_end = digits_from(len);
+            if exp_end == len {
+                return Err(self.error("expected digits in the exponent"));
+            }
+            len = exp_end;
+        }
+        let number = self.rest()[..len].parse().ok().filter(|n: &f64| n.is_finite()).ok_or_else(|| self.error("invalid number"))?;
+        self.pos += len;
+        Ok(number)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if !self.rest().starts_with('"') {
+            return Err(self.error("expected a string"));
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('u') => {
+                        // characters outside the basic plane are escaped as a surrogate pair
+                        let mut units = vec![self.code_unit(&mut chars)?];
+                        if (0xd800..0xdc00).contains(&units[0]) {
+                            if chars.next().map(|(_, c)| c) != Some('\\') || chars.next().map(|(_, c)| c) != Some('u') {
+                                return Err(self.error("unpaired surrogate in \\u escape"));
+                            }
+                            units.push(self.code_unit(&mut chars)?);
+                        }
+                        let c = char::decode_utf16(units).next().and_then(Result::ok).ok_or_else(|| self.error("unpaired surrogate in \\u escape"))?;
+                        out.push(c);
+                    }
+                    Some(c @ ('"' | '\\' | '/')) => out.push(c),
+                    _ => return Err(self.error("invalid escape in string")),
+                },
+                c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                c => out.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    // the four hex digits of a \u escape
+    fn code_unit(&self, chars: &mut impl Iterator<Item = (usize, char)>) -> Result<u16, String> {
+        let hex: String = chars.take(4).map(|(_, c)| c).collect();
+        u16::from_str_radix(&hex, 16).ok().filter(|_| hex.len() == 4 && hex.chars().all(|c| c.is_ascii_hexdigit())).ok_or_else(|| self.error("invalid \\u escape"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<LevelLayout, String> {
+        LevelLayout::parse(text, &ArchetypeSet::default())
+    }
+
+    #[test]
+    fn round_trip() {
+        let archetypes = ArchetypeSet::default();
+        let layout = LevelLayout {
+            targets: vec![
+                TargetPlacement {bottom: vec3(1.5, -2.25, -3.0), rotation: Quat::IDENTITY, archetype: 0, floating: false, wave: 0},
+                TargetPlacement {bottom: vec3(-10.0, 7.125, -0.45), rotation: Quat::from_rotation_z(1.0), archetype: 1, floating: true, wave: 1},
+                TargetPlacement {bottom: vec3(0.1, 0.2, -0.3), rotation: Quat::from_euler(EulerRot::ZXY, 0.3, 0.2, -0.1), archetype: 2, floating: false, wave: 2},
+            ],
+            waves: vec![-1.0, 30.0, 62.5],
+        };
+        let text = layout.to_json(&archetypes);
+        let parsed = LevelLayout::parse(&text, &archetypes).unwrap();
+        assert_eq!(parsed.waves, layout.waves);
+        assert_eq!(parsed.targets.len(), layout.targets.len());
+        for (a, b) in parsed.targets.iter().zip(layout.targets.iter()) {
+            assert_eq!((a.bottom, a.archetype, a.floating, a.wave), (b.bottom, b.archetype, b.floating, b.wave));
+            // renormalized on the way in
+            assert!(a.rotation.abs_diff_eq(b.rotation, 1e-6));
+        }
+        // and stable from then on
+        assert_eq!(LevelLayout::parse(&parsed.to_json(&archetypes), &archetypes).unwrap(), parsed);
+    }
+
+    #[test]
+    fn defaults() {
+        let layout = parse(r#"{"targets": [{"archetype": "jar", "pos": [1, 2, -3]}]}"#).unwrap();
+        assert_eq!(layout.waves, vec![0.0]);
+        assert_eq!(layout.targets, vec![TargetPlacement {bottom: vec3(1.0, 2.0, -3.0), rotation: Quat::IDENTITY, archetype: 1, floating: false, wave: 0}]);
+        assert!(parse(r#"{"targets": []}"#).unwrap().targets.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_layouts() {
+        let target = |fields: &str| parse(&format!(r#"{{"waves": [0, 10], "targets": [{{"archetype": "pot", "pos": [0, 0, -1]{}}}]}}"#, fields));
+        assert!(target("").is_ok());
+        assert_eq!(target(r#", "colour": 3"#).unwrap_err(), "target 0: unknown key colour");
+        assert_eq!(parse(r#"{"targets": [], "seed": 1}"#).unwrap_err(), "unknown key seed");
+        assert_eq!(parse(r#"{"targets": [{"archetype": "vase", "pos": [0, 0, 0]}]}"#).unwrap_err(), "target 0: unknown archetype vase");
+        assert_eq!(target(r#", "wave": 2"#).unwrap_err(), "target 0: wave 2 is not one of the 2 waves");
+        assert!(target(r#", "wave": -1"#).is_err());
+        assert!(target(r#", "wave": 0.5"#).is_err());
+        assert_eq!(parse(r#"{"targets": [{"archetype": "pot"}]}"#).unwrap_err(), "target 0: needs a pos");
+        assert_eq!(parse(r#"{"targets": [{"pos": [0, 0, 0]}]}"#).unwrap_err(), "target 0: needs an archetype");
+        assert!(target(r#", "pos": [0, 0]"#).is_err());
+        assert!(target(r#", "rotation": [0, 0, 0, 0]"#).is_err());
+        assert!(target(r#", "floating": 1"#).is_err());
+        assert_eq!(parse(r#"{"waves": [0]}"#).unwrap_err(), "no targets");
+        assert_eq!(parse(r#"{"waves": [], "targets": [{"archetype": "pot", "pos": [0, 0, -1]}]}"#).unwrap_err(), "needs at least one wave");
+        let too_many = vec![r#"{"archetype": "pot", "pos": [0, 0, 0]}"#; MAX_LAYOUT_TARGETS + 1].join(",");
+        assert!(parse(&format!(r#"{{"targets": [{}]}}"#, too_many)).is_err());
+    }
+
+    #[test]
+    fn numbers() {
+        for (text, value) in [("0", 0.0), ("-0", 0.0), ("12", 12.0), ("-3.25", -3.25), ("1e3", 1000.0), ("2.5E-1", 0.25), ("1e+2", 100.0)] {
+            assert_eq!(JsonParser::parse(text), Ok(Json::Number(value)), "{}", text);
+        }
+        for text in ["+1", "1.", "01", ".5", "-", "1e", "1e+", "0x10", "1.2.3", "--1", "1e999", "NaN", "Infinity"] {
+            assert!(JsonParser::parse(text).is_err(), "{} parsed", text);
+        }
+    }
+
+    #[test]
+    fn strings() {
+        let string = |text: &str| JsonParser::parse(text).map(|json| match json {
+            Json::String(s) => s,
+            other => panic!("{:?}", other),
+        });
+        assert_eq!(string(r#""plain""#).unwrap(), "plain");
+        assert_eq!(string(r#""\"\\\/\b\f\n\r\t""#).unwrap(), "\"\\/\u{8}\u{c}\n\r\t");
+        assert_eq!(string(r#""é中""#).unwrap(), "\u{e9}\u{4e2d}");
+        assert_eq!(string(r#""🏺""#).unwrap(), "\u{1f3fa}");
+        assert_eq!(string("\"\u{1f3fa} as is\"").unwrap(), "\u{1f3fa} as is");
+        for text in [r#""\ud83c""#, r#""\ud83cA""#, r#""\ud83c\ud83c""#, r#""\udffa""#, r#""\u12""#, r#""\u+123""#, r#""\x41""#, "\"tab\there\"", r#""open"#] {
+            assert!(string(text).is_err(), "{} parsed", text);
+        }
+
+        // names come back as they went out
+        let mut archetypes = ArchetypeSet::default();
+        archetypes.archetypes[0].name = "pot \"deluxe\" \\ \u{1}\u{1f3fa}".into();
+        let layout = LevelLayout {targets: vec![TargetPlacement {bottom: Vec3::ZERO, rotation: Quat::IDENTITY, archetype: 0, floating: false, wave: 0}], waves: vec![0.0]};
+        let text = layout.to_json(&archetypes);
+        assert!(text.contains(r#""pot \"deluxe\" \\ \u0001"#), "{}", text);
+        assert_eq!(LevelLayout::parse(&text, &archetypes).unwrap(), layout);
+    }
+
+    #[test]
+    fn errors_have_line_numbers() {
+        assert_eq!(parse("{\n\"targets\": [\n  {\"pos\": [1, 2,]}\n]}").unwrap_err(), "line 3: expected a value");
+        assert_eq!(parse("{\"targets\": []}\n,").unwrap_err(), "line 2: expected the end of the file");
+    }
+}
@@ -1,15 +1,15 @@
 use std::borrow::Borrow;
-use web_time::Instant;
-use kira::{manager::{backend::DefaultBackend, AudioManager, AudioManagerSettings}};
+use kira::{manager::{backend::DefaultBackend, AudioManager, AudioManagerSettings}, tween::Tween, Volume};
 use wgpu::{Surface, Texture, wgt::TextureViewDescriptor};
-use glam::{UVec2, vec3};
+use glam::UVec2;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 
-use crate::{arrows::ArrowController, boat_rail::RailController, camera::ShadowSettings, deferred_renderer::DeferredRenderer, gputil::AssetSource, targets::TargetController, terrain_view::{HeightmapTerrain, TerrainView}, ui::{GameState, UIDisplay}};
+use crate::{arrows::ArrowView, audio_util::GameSounds, clock::{FixedStepper, GameClock, SystemClock}, deferred_renderer::DeferredRenderer, events::GameEvent, fish::FishView, gputil::AssetSource, game_mode::ModeKind, high_scores::{HighScoreTable, InitialsEntry}, input::{ActionEvent, InputAction, InputBindings, RawInput}, replay::{InputEvent, InputRecording, ReplayCursor}, settings::Settings, simulation::GameSimulation, targets::TargetView, telemetry::ShotLog, terrain_view::TerrainView, ui::{GameState, UIDisplay}};
 
 pub mod gputil;
+pub mod clock;
 pub mod terrain_view;
 pub mod camera;
 pub mod deferred_renderer;
@@ -19,6 +19,20 @@ pub mod targets;
 pub mod boat_rail;
 pub mod audio_util;
 pub mod ui;
+pub mod simulation;
+pub mod replay;
+pub mod input;
+pub mod settings;
+pub mod game_mode;
+pub mod events;
+pub mod scoring;
+pub mod high_scores;
+pub mod telemetry;
+pub mod collision;
+pub mod fish;
+pub mod archetypes;
+pub mod debris;
+pub mod levels;
 
 pub use gputil::GPUContext;
 
@@ -28,13 +42,25 @@ pub struct GameSystem {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen(skip))]
     pub surface: wgpu::Surface<'static>,
     audio: Option<kira::manager::AudioManager>,
-    game_state: GameState,
-    camera: RailController,
+    sounds: GameSounds,
+    clock: Box<dyn GameClock>,
+    stepper: FixedStepper,
+    bindings: InputBindings,
+    settings: Settings,
+    sim: GameSimulation,
+    recording: Option<InputRecording>,
+    replay: Option<ReplayCursor>,
+    high_scores: HighScoreTable,
+    finished_run: Option<(ModeKind, u32)>, // mode and seed of the run on the finish screen
+    initials: Option<InitialsEntry>, // takes over input while open
+    last_shot_log: Option<ShotLog>, // of the last finished run
+    #[cfg(not(target_arch = "wasm32"))]
+    telemetry_dir: Option<std::path::PathBuf>,
     renderer: Box<DeferredRenderer>,
-    terrain: HeightmapTerrain,
     terrain_view: TerrainView,
-    arrows: ArrowController,
-    targets: TargetController,
+    arrow_view: ArrowView,
+    target_view: TargetView,
+    fish_view: FishView,
     ui_disp: UIDisplay,
 }
 
@@ -44,85 +70,361 @@ pub struct FrameResult {
     pub should_release_cursor: bool,
 }
 
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct InputResult {
+    pub should_grab: bool,
+    pub should_release_cursor: bool,
+}
+
 impl GameSystem {
-    pub fn new(gpu: GPUContext, surface: wgpu::Surface<'static>, size: UVec2, assets: &impl AssetSource) -> Self {
+    // with no seed a random one is picked, and a new one after every finished run
+    pub fn new(gpu: GPUContext, surface: wgpu::Surface<'static>, size: UVec2, assets: &impl AssetSource, seed: Option<u32>) -> Self {
+        Self::with_clock(gpu, surface, size, assets, seed, Box::new(SystemClock))
+    }
+
+    pub fn with_clock(gpu: GPUContext, surface: wgpu::Surface<'static>, size: UVec2, assets: &impl AssetSource, seed: Option<u32>, clock: Box<dyn GameClock>) -> Self {
         #[cfg(not(target_arch = "wasm32"))]
         let audio = AudioManager::<DefaultBackend>::new(AudioManagerSettings::default()).ok();
         #[cfg(target_arch = "wasm32")]
         let audio = None;
 
-        let init_time = Instant::now();
-        let game_state = GameState::Title {started_at: init_time, is_restart: false };
-
-        let shadow_settings = ShadowSettings {
-            sun_dir: vec3(0.548, -0.380, 0.745),
-            range_xy: 60.0,
-            range_z: 10.0,
-        };
-
-        let camera = RailController::new(shadow_settings, init_time);
-        let renderer = DeferredRenderer::new(&gpu, assets, &camera, size);
+        let init_time = clock.now();
+        let stepper = FixedStepper::new(init_time);
+        let bindings = InputBindings::load(assets);
+        let sim = GameSimulation::new(assets, init_time, seed.unwrap_or_else(rand::random), seed.is_some(), ModeKind::default());
+        let mut sounds = GameSounds::new(assets, &sim.targets.archetypes);
+        sounds.reseed(sim.seed);
 
-        let terrain = terrain_view::HeightmapTerrain::load(assets);
-        let terrain_view = crate::terrain_view::TerrainView::new(&gpu, assets, &renderer, &terrain);
-
-        let arrows = ArrowController::new(&gpu, assets, &renderer);
-        let targets = TargetController::new(&gpu, assets, &renderer, &terrain);
+        let renderer = DeferredRenderer::new(&gpu, assets, &sim.camera, size);
+        let terrain_view = TerrainView::new(&gpu, assets, &renderer, &sim.terrain);
+        let arrow_view = ArrowView::new(&gpu, &renderer);
+        let target_view = TargetView::new(&gpu, assets, &renderer, &sim.targets.archetypes);
+        let fish_view = FishView::new(&gpu, &renderer);
 
         let ui_disp = UIDisplay::new(&gpu, assets, &renderer);
 
         GameSystem {
-            gpu, surface, audio,
-            game_state, camera, renderer,
-            terrain, terrain_view, arrows, targets, ui_disp
+            gpu, surface, audio, sounds,
+            clock, stepper, bindings,
+            settings: Settings::default(),
+            sim,
+            recording: None,
+            replay: None,
+            high_scores: HighScoreTable::default(),
+            finished_run: None,
+            initials: None,
+            last_shot_log: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            telemetry_dir: None,
+            renderer, terrain_view, arrow_view, target_view, fish_view, ui_disp
         }
     }
 
     pub fn tick_and_render(&mut self, output: &wgpu::Texture) -> bool {
         let mut should_release_cursor = false;
 
-        let now = Instant::now();
-        if self.game_state.should_reset_world(now) {
-            self.targets.reset(&self.terrain);
-            self.arrows.reset();
-            self.camera.reset(now, -GameState::COUNTDOWN_DURATION.as_secs_f64());
+        let num_steps = self.stepper.advance(self.clock.now());
+        for _ in 0..num_steps {
+            self.apply_replay_inputs();
+            should_release_cursor |= self.sim.step();
         }
-        self.game_state.do_timeout(now);
-
-        // movement and hits continue after the finish to allow for buzzer beater shots
-        if !self.game_state.is_paused() {
-            let time = self.camera.tick(now);
-            if self.game_state.is_playing() && time >= GameState::GAME_PERIOD {
-                should_release_cursor = true;
-                self.game_state = GameState::Finish { done_at: now + GameState::FINISH_DURATION };
-            }
+        let alpha = self.stepper.alpha();
 
-            self.arrows.tick(time, &self.terrain, self.audio.as_mut(), &mut [
-                &mut self.targets,
-            ]);
-            self.targets.tick(time);
+        // events from input handlers between frames are included as well
+        let events = self.sim.events.take();
+        if let Some(audio) = &mut self.audio {
+            self.sounds.play_events(audio, &events, self.sim.seed);
         }
-        self.ui_disp.tick(self.audio.as_mut(), self.game_state, now, &self.camera, &self.arrows, &self.targets);
+        self.check_finished_run(&events);
+        let sim = &self.sim;
+        self.ui_disp.tick(self.audio.as_mut(), sim, &events);
+        self.ui_disp.set_high_scores(self.high_scores.top(sim.mode.kind(), sim.seed), self.initials);
+        self.arrow_view.update(&sim.arrows, alpha);
+        self.target_view.update(&sim.targets, alpha);
+        self.fish_view.update(&sim.fish, alpha);
 
         let out_view = output.create_view(&TextureViewDescriptor{
             format: Some(self.gpu.output_format),
             ..Default::default()
         });
-        self.renderer.render(&self.gpu, &out_view, &self.camera, &mut [
+        self.renderer.render(&self.gpu, &out_view, &sim.camera.interpolated(alpha), &mut [
             &mut self.terrain_view,
-            &mut self.arrows,
-            &mut self.targets,
+            &mut self.arrow_view,
+            &mut self.target_view,
+            &mut self.fish_view,
             &mut self.ui_disp,
         ]);
 
         should_release_cursor
     }
+
+    // keeps the shot log once the results are up, and opens initials entry when they are left if the run made the table
+    fn check_finished_run(&mut self, events: &[GameEvent]) {
+        for event in events {
+            match *event {
+                GameEvent::StateChanged { new: GameState::Finish {..}, .. } => {
+                    // the seed changes when leaving the results screen, so remember the one the run used
+                    self.finished_run = Some((self.sim.mode.kind(), self.sim.seed));
+                }
+                GameEvent::StateChanged { new: GameState::Results, .. } => self.save_shot_log(),
+                GameEvent::StateChanged { old: GameState::Results, new: GameState::Title {..} } => {
+                    let Some((mode, seed)) = self.finished_run.take() else { continue };
+                    let score = self.sim.mode.score(&self.sim.run_status());
+                    if let Some(score) = score && self.replay.is_none() && self.high_scores.qualifies(mode, seed, score) {
+                        self.initials = Some(InitialsEntry::new(mode, seed, score));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn save_shot_log(&mut self) {
+        let log = self.sim.shot_log.clone();
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(dir) = &self.telemetry_dir && !log.is_empty() {
+            let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            let name = format!("shots-{}-{}", timestamp, log.seed);
+            let written = std::fs::create_dir_all(dir)
+                .and_then(|_| std::fs::write(dir.join(format!("{}.csv", name)), log.to_csv()))
+                .and_then(|_| std::fs::write(dir.join(format!("{}.json", name)), log.to_json()));
+            match written {
+                Ok(()) => log::info!("wrote {} shots to {}", log.shots.len(), dir.join(name).display()),
+                Err(e) => log::error!("could not write shot log to {}: {}", dir.display(), e),
+            }
+        }
+        self.last_shot_log = Some(log);
+    }
+
+    fn finish_initials(&mut self) {
+        let Some(entry) = self.initials.take() else { return };
+        let high_score = entry.entry();
+        if let Some(rank) = self.high_scores.insert(entry.mode, entry.seed, high_score) {
+            log::info!("new high score: {} {} at rank {}", high_score.initials_str(), high_score.score, rank + 1);
+            self.high_scores.save();
+        }
+    }
+
+    // initials entry is outside the simulation, so none of this is recorded
+    fn on_initials_input(&mut self, input: RawInput) {
+        let Some(entry) = &mut self.initials else { return };
+        if let RawInput::Button { name, pressed: true } = input {
+            let mut chars = name.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) && c.is_ascii_alphabetic() {
+                if entry.type_letter(c) {
+                    self.finish_initials();
+                }
+                return;
+            } else if name.eq_ignore_ascii_case("backspace") {
+                entry.move_cursor(false);
+                return;
+            } else if name.eq_ignore_ascii_case("enter") {
+                self.finish_initials();
+                return;
+            }
+        }
+        for action in self.bindings.map(input) {
+            let Some(entry) = &mut self.initials else { return };
+            match action {
+                // confirming moves on to the next letter, or finishes after the last one
+                ActionEvent::Pressed(InputAction::Shoot) if entry.confirm_letter() => self.finish_initials(),
+                ActionEvent::Pressed(InputAction::LetterUp) => entry.cycle_letter(true),
+                ActionEvent::Pressed(InputAction::LetterDown) => entry.cycle_letter(false),
+                ActionEvent::Pressed(InputAction::NextMode) => entry.move_cursor(true),
+                ActionEvent::Pressed(InputAction::PrevMode) => entry.move_cursor(false),
+                ActionEvent::Pressed(InputAction::Pause) => self.initials = None,
+                _ => {}
+            }
+        }
+    }
+
+    // Inputs are applied between simulation steps and tagged with the step count,
+    // so that playing back a recording reproduces the run exactly.
+    fn apply_input(&mut self, event: InputEvent) -> bool {
+        if let Some(recording) = &mut self.recording {
+            let has_effect = match event {
+                InputEvent::MouseMove {..} => self.sim.state.accepts_mouse(),
+                _ => true,
+            };
+            if has_effect {
+                recording.push(self.sim.steps, event);
+            }
+        }
+
+        match event {
+            InputEvent::Click => self.sim.on_click(),
+            InputEvent::Release => {
+                self.sim.on_release();
+                false
+            }
+            InputEvent::MouseMove { dx, dy } => {
+                self.sim.on_mouse_move(dx as f64, dy as f64);
+                false
+            }
+            InputEvent::CursorUngrab => {
+                self.sim.on_cursor_ungrab();
+                false
+            }
+            InputEvent::ToggleMusic => {
+                self.ui_disp.toggle_music();
+                false
+            }
+            InputEvent::Zoom { held } => {
+                self.sim.on_zoom(held);
+                false
+            }
+            InputEvent::CycleMode { forward } => {
+                self.sim.on_cycle_mode(forward);
+                false
+            }
+            InputEvent::EndRun => {
+                self.sim.on_end_run();
+                false
+            }
+        }
+    }
+
+    // live input is ignored during playback
+    fn apply_live_input(&mut self, event: InputEvent) -> bool {
+        if self.replay.is_some() {
+            false
+        } else {
+            self.apply_input(event)
+        }
+    }
+
+    fn apply_replay_inputs(&mut self) {
+        let Some(replay) = &mut self.replay else { return };
+        let mut due = Vec::new();
+        while let Some(event) = replay.next_due(self.sim.steps) {
+            due.push(event);
+        }
+        if replay.is_finished() {
+            log::info!("replay finished");
+            self.replay = None;
+        }
+        for event in due {
+            self.apply_input(event);
+        }
+    }
+
+    // entry point for bound input, front ends send raw input through `on_button` and `on_mouse_move`
+    pub fn on_action(&mut self, action: ActionEvent) -> InputResult {
+        let mut result = InputResult::default();
+        match action {
+            ActionEvent::Pressed(InputAction::Shoot) => {
+                result.should_grab = self.apply_live_input(InputEvent::Click);
+            }
+            ActionEvent::Released(InputAction::Shoot) => {
+                self.apply_live_input(InputEvent::Release);
+            }
+            ActionEvent::Pressed(InputAction::Pause) => {
+                // pausing again from the pause screen ends the run
+                if self.sim.state.is_paused() {
+                    self.apply_live_input(InputEvent::EndRun);
+                } else {
+                    result.should_release_cursor = true;
+                    self.apply_live_input(InputEvent::CursorUngrab);
+                }
+            }
+            ActionEvent::Pressed(InputAction::NextMode) => {
+                self.apply_live_input(InputEvent::CycleMode { forward: true });
+            }
+            ActionEvent::Pressed(InputAction::PrevMode) => {
+                self.apply_live_input(InputEvent::CycleMode { forward: false });
+            }
+            ActionEvent::Pressed(InputAction::ToggleMusic) => {
+                self.apply_live_input(InputEvent::ToggleMusic);
+            }
+            ActionEvent::Pressed(InputAction::Zoom) => {
+                self.apply_live_input(InputEvent::Zoom { held: true });
+            }
+            ActionEvent::Released(InputAction::Zoom) => {
+                self.apply_live_input(InputEvent::Zoom { held: false });
+            }
+            ActionEvent::Look { dx, dy } => {
+                // recorded after scaling so that replays do not depend on the player's settings
+                let (dx, dy) = self.settings.look_delta(dx, dy);
+                self.apply_live_input(InputEvent::MouseMove { dx, dy });
+            }
+            _ => {}
+        }
+        result
+    }
+
+    pub fn on_raw_input(&mut self, input: RawInput) -> InputResult {
+        let mut result = InputResult::default();
+        if self.initials.is_some() {
+            self.on_initials_input(input);
+            return result;
+        }
+        for action in self.bindings.map(input) {
+            let r = self.on_action(action);
+            result.should_grab |= r.should_grab;
+            result.should_release_cursor |= r.should_release_cursor;
+        }
+        result
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    // takes effect immediately, saving is up to the front end
+    pub fn apply_settings(&mut self, settings: Settings) {
+        self.settings = settings.clamped();
+        self.sim.camera.set_fov(self.settings.fov_y_deg);
+        self.ui_disp.set_music_volume(self.settings.music_volume);
+        self.apply_master_volume();
+    }
+
+    fn apply_master_volume(&mut self) {
+        if let Some(audio) = &mut self.audio {
+            let _ = audio.main_track().set_volume(Volume::Amplitude(self.settings.master_volume as f64), Tween::default());
+        }
+    }
+
+    // replaces the table, for front ends which load it from storage
+    pub fn set_high_scores(&mut self, high_scores: HighScoreTable) {
+        self.high_scores = high_scores;
+    }
+
+    pub fn high_scores(&self) -> &HighScoreTable {
+        &self.high_scores
+    }
+
+    pub fn last_shot_log(&self) -> Option<&ShotLog> {
+        self.last_shot_log.as_ref()
+    }
+
+    pub fn finish_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    // restarts the game with the recording's seed and plays its input back
+    pub fn start_replay(&mut self, recording: InputRecording) {
+        self.recording = None;
+        self.initials = None;
+        self.finished_run = None;
+        self.sim.restart(recording.seed, recording.fixed_seed, recording.mode);
+        self.sounds.reseed(self.sim.seed);
+        self.replay = Some(ReplayCursor::new(recording));
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GameSystem {
+    // writes a CSV and a JSON shot log into this folder at the end of each run
+    pub fn set_telemetry_dir(&mut self, dir: Option<std::path::PathBuf>) {
+        self.telemetry_dir = dir;
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 impl GameSystem {
-    pub async fn init_from_canvas(canvas: web_sys::HtmlCanvasElement, raw_asset_bundle: Box<[u8]>) -> Self {
+    pub async fn init_from_canvas(canvas: web_sys::HtmlCanvasElement, raw_asset_bundle: Box<[u8]>, seed: Option<u32>) -> Self {
         use crate::gputil::asset::LoadedZipBundle;
 
         let init_size = UVec2::new(canvas.width(), canvas.height());
@@ -146,7 +448,29 @@ impl GameSystem {
         let assets = LoadedZipBundle::new(&raw_asset_ref).unwrap();
         log::info!("parsed asset bundle");
 
-        Self::new(gpu, surface, init_size, &assets)
+        let mut game = Self::new(gpu, surface, init_size, &assets, seed);
+        game.apply_settings(Settings::load_local_storage());
+        game.set_high_scores(HighScoreTable::load_local_storage());
+        game
+    }
+
+    // per-shot telemetry of the last finished run
+    pub fn last_shot_log_csv(&self) -> Option<String> {
+        self.last_shot_log.as_ref().map(ShotLog::to_csv)
+    }
+
+    pub fn last_shot_log_json(&self) -> Option<String> {
+        self.last_shot_log.as_ref().map(ShotLog::to_json)
+    }
+
+    // applies the settings and saves them to localStorage
+    pub fn set_settings(&mut self, settings: Settings) {
+        self.apply_settings(settings);
+        self.settings.save_local_storage();
+    }
+
+    pub fn get_settings(&self) -> Settings {
+        self.settings()
     }
 }
 
@@ -160,57 +484,82 @@ impl GameSystem {
     }
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
-    // returns if the cursor should grab
-    pub fn on_click(&mut self) -> bool {
+    // key or mouse button, named as in bindings.cfg
+    pub fn on_button(&mut self, name: &str, pressed: bool) -> InputResult {
+        // browsers only allow audio to start from a user gesture
         #[cfg(target_arch = "wasm32")]
         if let None = self.audio {
             self.audio = AudioManager::<DefaultBackend>::new(AudioManagerSettings::default()).ok();
+            self.apply_master_volume();
             log::info!("initialized audio");
         }
-        match self.game_state {
-            GameState::Playing => {
-                self.arrows.shoot(self.audio.as_mut(), &self.camera);
-                false
-            },
-            GameState::Title {..} => {
-                self.game_state = GameState::Fade { done_at: Instant::now() + GameState::FADE_DURATION };
-                true
-            }
-            GameState::Paused => {
-                self.camera.unpause(Instant::now());
-                self.game_state = GameState::Playing;
-                true
-            }
-            _ => {false}
-        }
+        self.on_raw_input(RawInput::Button { name, pressed })
     }
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
     pub fn on_cursor_ungrab(&mut self) {
-        match self.game_state {
-            GameState::Playing => {
-                self.game_state = GameState::Paused;
-            }
-            GameState::Countdown {..} | GameState::Fade {..} => {
-                self.game_state = GameState::Title {started_at: Instant::now(), is_restart: false};
-            }
-            _ => {}
-        }
+        self.apply_live_input(InputEvent::CursorUngrab);
     }
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
     pub fn on_mouse_move(&mut self, dx: f64, dy: f64) {
-        match self.game_state {
-            GameState::Playing | GameState::Countdown {..} => {
-                self.camera.mouse(dx, dy);
-            }
-            _ => {}
+        self.on_raw_input(RawInput::MouseMotion { dx, dy });
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+    // restarts the game and records all input from here on
+    pub fn start_recording(&mut self) {
+        let (seed, fixed_seed, mode) = (self.sim.seed, self.sim.fixed_seed, self.sim.mode.kind());
+        self.replay = None;
+        self.initials = None;
+        self.finished_run = None;
+        self.sim.restart(seed, fixed_seed, mode);
+        self.sounds.reseed(self.sim.seed);
+        self.recording = Some(InputRecording::new(seed, fixed_seed, mode));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+    // restarts on the title screen, with no seed a random one is picked for each run
+    pub fn restart_with_seed(&mut self, seed: Option<u32>) {
+        self.replay = None;
+        self.initials = None;
+        self.finished_run = None;
+        self.sim.restart(seed.unwrap_or_else(rand::random), seed.is_some(), self.sim.mode.kind());
+        self.sounds.reseed(self.sim.seed);
+        if self.recording.is_some() {
+            self.recording = Some(InputRecording::new(self.sim.seed, self.sim.fixed_seed, self.sim.mode.kind()));
         }
     }
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
-    pub fn stop_music(&mut self) {
-        self.ui_disp.stop_music();
+    // the current target layout in the level file format
+    pub fn export_layout(&self) -> String {
+        self.sim.targets.layout().to_json(&self.sim.targets.archetypes)
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+    pub fn finish_recording_bytes(&mut self) -> Option<Box<[u8]>> {
+        self.finish_recording().map(|rec| rec.to_bytes().into_boxed_slice())
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+    // returns false if the data is not a valid replay
+    pub fn start_replay_bytes(&mut self, bytes: &[u8]) -> bool {
+        match InputRecording::from_bytes(bytes) {
+            Ok(recording) => {
+                self.start_replay(recording);
+                true
+            }
+            Err(e) => {
+                log::error!("failed to load replay: {}", e);
+                false
+            }
+        }
     }
 
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
@@ -2,9 +2,9 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![cfg(not(target_arch = "wasm32"))]
 
-use std::{sync::Arc, time::Duration};
+use std::{fs::File, io::{BufReader, BufWriter, Write}, path::{Path, PathBuf}, sync::Arc, time::Duration};
 
-use bowfishing_blitz::{GameSystem, gputil::{asset::LocalAssetFolder, *}};
+use bowfishing_blitz::{GameSystem, InputResult, gputil::{asset::LocalAssetFolder, *}, high_scores::HighScoreTable, replay::InputRecording, settings::Settings};
 
 use glam::*;
 
@@ -17,9 +17,38 @@ use winit::{
 };
 
 
+#[derive(Default)]
+struct Args {
+    record_to: Option<PathBuf>,
+    replay_from: Option<PathBuf>,
+    seed: Option<u32>,
+    telemetry_dir: Option<PathBuf>,
+    export_layout_to: Option<PathBuf>,
+}
+
+fn parse_args() -> Args {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--record" => args.record_to = iter.next().map(PathBuf::from),
+            "--replay" => args.replay_from = iter.next().map(PathBuf::from),
+            "--telemetry" => args.telemetry_dir = iter.next().map(PathBuf::from),
+            "--export-layout" => args.export_layout_to = iter.next().map(PathBuf::from),
+            "--seed" => match iter.next().map(|s| s.parse()) {
+                Some(Ok(seed)) => args.seed = Some(seed),
+                _ => log::warn!("--seed needs a number from 0 to {}", u32::MAX),
+            },
+            _ => log::warn!("ignoring unknown argument {}", arg),
+        }
+    }
+    args
+}
+
 fn main() {
     env_logger::builder().filter_level(log::LevelFilter::Info).init();
     log::info!("starting up");
+    let args = parse_args();
 
     let wgpu_inst = wgpu::Instance::default();
     let mut event_loop = EventLoop::new().unwrap();
@@ -40,7 +69,27 @@ fn main() {
 
     let assets = LocalAssetFolder::new("./assets");
 
-    let mut game = GameSystem::new(gpu, surface, size, &assets);
+    let mut game = GameSystem::new(gpu, surface, size, &assets, args.seed);
+    load_settings(&mut game);
+    match HighScoreTable::load_file(Path::new(HIGH_SCORES_PATH)) {
+        Ok(high_scores) => game.set_high_scores(high_scores),
+        Err(e) => log::error!("failed to load {}: {}", HIGH_SCORES_PATH, e),
+    }
+    // every run's shot log is kept, in ./telemetry unless another folder is given
+    game.set_telemetry_dir(Some(args.telemetry_dir.clone().unwrap_or_else(|| PathBuf::from(TELEMETRY_DIR))));
+
+    if let Some(path) = &args.replay_from {
+        let recording = File::open(path).and_then(|f| InputRecording::read_from(&mut BufReader::new(f)));
+        match recording {
+            Ok(recording) => {
+                log::info!("replaying {} inputs from {}", recording.inputs.len(), path.display());
+                game.start_replay(recording);
+            }
+            Err(e) => log::error!("failed to load replay {}: {}", path.display(), e),
+        }
+    } else if args.record_to.is_some() {
+        game.start_recording();
+    }
 
     let window = &window;
     'mainloop: loop{
@@ -51,11 +100,6 @@ fn main() {
         let loop_status = event_loop.pump_events(Some(Duration::ZERO),  |event, target| {
             match event {
                 Event::DeviceEvent {device_id: _, event: dev_event} => match dev_event {
-                    DeviceEvent::Key(key_event) => {
-                        if window.has_focus() {
-                            //camera.key(key_event.physical_key, key_event.state);
-                        }
-                    }
                     DeviceEvent::MouseMotion { delta: (dx, dy) } => {
                         if window.has_focus() {
                             game.on_mouse_move(dx, dy);
@@ -68,28 +112,31 @@ fn main() {
                         must_resize = Some(uvec2(new_size.width, new_size.height));
                         window.request_redraw();
                     }
-                    WindowEvent::Focused(false) |
-                    WindowEvent::KeyboardInput { device_id: _, event: KeyEvent {
-                        physical_key: _, logical_key: Key::Named(NamedKey::Escape), text: _, location: _, state: _, repeat: _, ..
-                    }, is_synthetic: _ }=> {
-                        let _ = window.set_cursor_grab(CursorGrabMode::None);
-                        window.set_cursor_visible(true);
+                    WindowEvent::Focused(false) => {
+                        release_cursor(window);
                         game.on_cursor_ungrab();
                     }
+                    // pick up edits made to the settings file while the game was in the background
+                    WindowEvent::Focused(true) => load_settings(&mut game),
                     WindowEvent::KeyboardInput { device_id: _, event: KeyEvent {
-                        physical_key: _, logical_key: Key::Character(c), text: _, location: _, state: _, repeat: _, ..
-                    }, is_synthetic: _ }=> {
-                        if c == "m" {
-                            game.stop_music();
+                        logical_key, state, repeat: false, ..
+                    }, is_synthetic: false } => {
+                        if let Some(name) = key_name(&logical_key) {
+                            let result = game.on_button(&name, state == ElementState::Pressed);
+                            apply_input_result(window, result);
                         }
                     }
-                    WindowEvent::MouseInput {device_id: _, state: ElementState::Pressed, button: MouseButton::Left} => {
-                        if window.has_focus() {
-                            let should_grab = game.on_click();
-                            if should_grab {
-                                let _ = window.set_cursor_grab(CursorGrabMode::Confined);
-                                window.set_cursor_visible(false);
-                            }
+                    WindowEvent::MouseInput {device_id: _, state, button} => {
+                        let name = match button {
+                            MouseButton::Left => "mouse_left",
+                            MouseButton::Right => "mouse_right",
+                            MouseButton::Middle => "mouse_middle",
+                            _ => return,
+                        };
+                        // still deliver releases after losing focus so that held actions end
+                        if window.has_focus() || state == ElementState::Released {
+                            let result = game.on_button(name, state == ElementState::Pressed);
+                            apply_input_result(window, result);
                         }
                     }
                     WindowEvent::CloseRequested => {
@@ -105,6 +152,24 @@ fn main() {
             PumpStatus::Continue => {}
             PumpStatus::Exit(_) => {
                 log::info!("EXITING");
+                if let (Some(path), Some(recording)) = (&args.record_to, game.finish_recording()) {
+                    let result = File::create(path).and_then(|f| {
+                        let mut out = BufWriter::new(f);
+                        recording.write_to(&mut out)?;
+                        out.flush()
+                    });
+                    match result {
+                        Ok(()) => log::info!("saved {} inputs to {}", recording.inputs.len(), path.display()),
+                        Err(e) => log::error!("failed to save recording {}: {}", path.display(), e),
+                    }
+                }
+                // the layout of the last run played, to be curated into a level.json
+                if let Some(path) = &args.export_layout_to {
+                    match std::fs::write(path, game.export_layout()) {
+                        Ok(()) => log::info!("saved the target layout to {}", path.display()),
+                        Err(e) => log::error!("failed to save the target layout {}: {}", path.display(), e),
+                    }
+                }
                 break 'mainloop
             }
         }
@@ -129,9 +194,55 @@ fn main() {
 
         let should_release_cursor = game.tick_and_render(&surface_tex.texture);
         if should_release_cursor {
-            let _ = window.set_cursor_grab(CursorGrabMode::None);
-            window.set_cursor_visible(true);
+            release_cursor(window);
         }
         surface_tex.present();
     }
+}
+
+const SETTINGS_PATH: &str = "./settings.cfg";
+const HIGH_SCORES_PATH: &str = "./high_scores.cfg";
+const TELEMETRY_DIR: &str = "./telemetry";
+
+// a missing file is created with the defaults so that players can find it and see every option
+fn load_settings(game: &mut GameSystem) {
+    let path = Path::new(SETTINGS_PATH);
+    match Settings::load_file(path) {
+        Ok(settings) => {
+            if !path.exists() {
+                if let Err(e) = settings.save_file(path) {
+                    log::warn!("failed to save {}: {}", path.display(), e);
+                }
+            }
+            if settings != game.settings() {
+                log::info!("applying settings from {}", path.display());
+                game.apply_settings(settings);
+            }
+        }
+        Err(e) => log::error!("failed to load {}: {}", path.display(), e),
+    }
+}
+
+fn release_cursor(window: &Window) {
+    let _ = window.set_cursor_grab(CursorGrabMode::None);
+    window.set_cursor_visible(true);
+}
+
+fn apply_input_result(window: &Window, result: InputResult) {
+    if result.should_release_cursor {
+        release_cursor(window);
+    } else if result.should_grab {
+        let _ = window.set_cursor_grab(CursorGrabMode::Confined);
+        window.set_cursor_visible(false);
+    }
+}
+
+// names keys the same way as the browser's KeyboardEvent.key, so that one bindings file works for both
+fn key_name(key: &Key) -> Option<String> {
+    match key {
+        Key::Named(NamedKey::Space) => Some("space".into()),
+        Key::Named(named) => Some(format!("{:?}", named).to_lowercase()),
+        Key::Character(c) => Some(c.to_lowercase()),
+        _ => None,
+    }
 }
\ No newline at end of file
@@ -0,0 +1,421 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+use crate::game_mode::ModeKind;
+
+// Inputs which reach the game from the native and wasm front ends.
+// Mouse deltas are kept at f32 precision so that live play and playback see identical values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    Click, // shoot pressed, also draws the bow
+    Release, // shoot released, lets the arrow go
+    MouseMove {dx: f32, dy: f32},
+    CursorUngrab,
+    ToggleMusic,
+    Zoom {held: bool},
+    CycleMode {forward: bool},
+    EndRun,
+}
+
+impl InputEvent {
+    const TAG_CLICK: u8 = 0;
+    const TAG_MOUSE_MOVE: u8 = 1;
+    const TAG_CURSOR_UNGRAB: u8 = 2;
+    const TAG_TOGGLE_MUSIC: u8 = 3;
+    const TAG_ZOOM: u8 = 4;
+    const TAG_CYCLE_MODE: u8 = 5;
+    const TAG_END_RUN: u8 = 6;
+    const TAG_RELEASE: u8 = 7;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimedInput {
+    pub step: u64, // number of simulation steps completed when the input arrived
+    pub event: InputEvent,
+}
+
+// A play session from a restart with a known seed.
+//
+// File layout (little-endian):
+//   magic "BBRP", version: u8, seed: u32, fixed seed: u8, mode id: u8,
+//   then until EOF: step delta (LEB128), tag: u8, payload (two f32 for mouse movement, u8 for zoom and mode cycling)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputRecording {
+    pub seed: u32,
+    pub fixed_seed: bool,
+    pub mode: ModeKind, // selected when recording started
+    pub inputs: Vec<TimedInput>,
+}
+
+impl InputRecording {
+    const MAGIC: &'static [u8; 4] = b"BBRP";
+    const VERSION: u8 = 2;
+
+    pub fn new(seed: u32, fixed_seed: bool, mode: ModeKind) -> Self {
+        InputRecording { seed, fixed_seed, mode, inputs: Vec::new() }
+    }
+
+    pub fn push(&mut self, step: u64, event: InputEvent) {
+        self.inputs.push(TimedInput {step, event});
+    }
+
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(Self::MAGIC)?;
+        out.write_all(&[Self::VERSION])?;
+        out.write_all(&self.seed.to_le_bytes())?;
+        out.write_all(&[self.fixed_seed as u8, self.mode.id()])?;
+
+        let mut last_step = 0;
+        for input in self.inputs.iter() {
+            write_varint(out, input.step - last_step)?;
+            last_step = input.step;
+            match input.event {
+                InputEvent::Click => out.write_all(&[InputEvent::TAG_CLICK])?,
+                InputEvent::Release => out.write_all(&[InputEvent::TAG_RELEASE])?,
+                InputEvent::MouseMove { dx, dy } => {
+                    out.write_all(&[InputEvent::TAG_MOUSE_MOVE])?;
+                    out.write_all(&dx.to_le_bytes())?;
+                    out.write_all(&dy.to_le_bytes())?;
+                }
+                InputEvent::CursorUngrab => out.write_all(&[InputEvent::TAG_CURSOR_UNGRAB])?,
+                InputEvent::ToggleMusic => out.write_all(&[InputEvent::TAG_TOGGLE_MUSIC])?,
+                InputEvent::Zoom { held } => out.write_all(&[InputEvent::TAG_ZOOM, held as u8])?,
+                InputEvent::CycleMode { forward } => out.write_all(&[InputEvent::TAG_CYCLE_MODE, forward as u8])?,
+                InputEvent::EndRun => out.write_all(&[InputEvent::TAG_END_RUN])?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from(input: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != Self::MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "not a replay file"));
+        }
+        let [version] = read_array(input)?;
+        if version != Self::VERSION {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("unsupported replay version {}", version)));
+        }
+        let seed = u32::from_le_bytes(read_array(input)?);
+        let [fixed_seed, mode_id] = read_array(input)?;
+        let fixed_seed = fixed_seed != 0;
+        let mode = ModeKind::from_id(mode_id).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, format!("unknown mode {}", mode_id)))?;
+
+        let mut inputs = Vec::new();
+        let mut step = 0u64;
+        while let Some(delta) = read_varint(input)? {
+            step = step.checked_add(delta).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "step count overflows"))?;
+            let [tag] = read_array(input)?;
+            let event = match tag {
+                InputEvent::TAG_CLICK => InputEvent::Click,
+                InputEvent::TAG_RELEASE => InputEvent::Release,
+                InputEvent::TAG_MOUSE_MOVE => {
+                    let dx = f32::from_le_bytes(read_array(input)?);
+                    let dy = f32::from_le_bytes(read_array(input)?);
+                    InputEvent::MouseMove { dx, dy }
+                }
+                InputEvent::TAG_CURSOR_UNGRAB => InputEvent::CursorUngrab,
+                InputEvent::TAG_TOGGLE_MUSIC => InputEvent::ToggleMusic,
+                InputEvent::TAG_ZOOM => {
+                    let [held] = read_array(input)?;
+                    InputEvent::Zoom { held: held != 0 }
+                }
+                InputEvent::TAG_CYCLE_MODE => {
+                    let [forward] = read_array(input)?;
+                    InputEvent::CycleMode { forward: forward != 0 }
+                }
+                InputEvent::TAG_END_RUN => InputEvent::EndRun,
+                _ => return Err(io::Error::new(ErrorKind::InvalidData, format!("unknown input tag {}", tag))),
+            };
+            inputs.push(TimedInput {step, event});
+        }
+        Ok(InputRecording {seed, fixed_seed, mode, inputs})
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes).unwrap(); // writing to a Vec cannot fail
+        bytes
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> io::Result<Self> {
+        Self::read_from(&mut bytes)
+    }
+}
+
+// Feeds a recording back into the game in step order.
+pub struct ReplayCursor {
+    recording: InputRecording,
+    next_input: usize,
+}
+
+impl ReplayCursor {
+    pub fn new(recording: InputRecording) -> Self {
+        ReplayCursor { recording, next_input: 0 }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.recording.seed
+    }
+
+    // next input which should be applied before simulating the step after `step`
+    pub fn next_due(&mut self, step: u64) -> Option<InputEvent> {
+        let input = self.recording.inputs.get(self.next_input)?;
+        if input.step <= step {
+            self.next_input += 1;
+            Some(input.event)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_input >= self.recording.inputs.len()
+    }
+}
+
+fn read_array<const N: usize>(input: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    input.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn write_varint(out: &mut impl Write, mut val: u64) -> io::Result<()> {
+    loop {
+        let byte = (val & 0x7f) as u8;
+        val >>= 7;
+        if val == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+// returns None on a clean EOF before the first byte
+fn read_varint(input: &mut impl Read) -> io::Result<Option<u64>> {
+    let mut val = 0u64;
+    let mut shift = 0;
+    loop {
+        let mut buf = [0u8; 1];
+        if input.read(&mut buf)? == 0 {
+            return if shift == 0 {
+                Ok(None)
+            } else {
+                Err(io::Error::from(ErrorKind::UnexpectedEof))
+            };
+        }
+        // the tenth byte only has room for the top bit
+        let bits = (buf[0] & 0x7f) as u64;
+        if shift > 63 || (shift == 63 && bits > 1) {
+            return Err(io::Error::new(ErrorKind::InvalidData, "varint overflows 64 bits"));
+        }
+        val |= bits << shift;
+        if buf[0] & 0x80 == 0 {
+            return Ok(Some(val));
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web_time::Instant;
+    use crate::{gputil::asset::LocalAssetFolder, simulation::GameSimulation};
+
+    fn every_event() -> InputRecording {
+        let mut rec = InputRecording::new(0xdead_beef, true, ModeKind::Hunter);
+        rec.push(0, InputEvent::Click);
+        rec.push(0, InputEvent::Release);
+        rec.push(1, InputEvent::MouseMove { dx: -1.5, dy: f32::MIN_POSITIVE });
+        rec.push(127, InputEvent::CursorUngrab);
+        rec.push(128, InputEvent::ToggleMusic);
+        rec.push(300, InputEvent::Zoom { held: true });
+        rec.push(300, InputEvent::Zoom { held: false });
+        rec.push(70_000, InputEvent::CycleMode { forward: true });
+        rec.push(70_001, InputEvent::CycleMode { forward: false });
+        rec.push(u64::MAX, InputEvent::EndRun);
+        rec
+    }
+
+    fn invalid_data(bytes: &[u8]) -> bool {
+        InputRecording::from_bytes(bytes).is_err_and(|e| e.kind() == ErrorKind::InvalidData)
+    }
+
+    #[test]
+    fn round_trip() {
+        let rec = every_event();
+        assert_eq!(InputRecording::from_bytes(&rec.to_bytes()).unwrap(), rec);
+        for mode in ModeKind::ALL {
+            let rec = InputRecording::new(7, false, mode);
+            assert_eq!(InputRecording::from_bytes(&rec.to_bytes()).unwrap(), rec);
+        }
+    }
+
+    #[test]
+    fn varints() {
+        for val in [0, 1, 127, 128, 16_383, 16_384, u32::MAX as u64, 1 << 63, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, val).unwrap();
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), Some(val));
+        }
+        assert_eq!(read_varint(&mut [].as_slice()).unwrap(), None);
+
+        // 2^64 and more would be truncated without the overflow check
+        let overflow = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02];
+        assert_eq!(read_varint(&mut overflow.as_slice()).unwrap_err().kind(), ErrorKind::InvalidData);
+        let too_long = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00];
+        assert_eq!(read_varint(&mut too_long.as_slice()).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(read_varint(&mut [0x80].as_slice()).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn truncated() {
+        let rec = every_event();
+        let bytes = rec.to_bytes();
+        let mut ends = vec![11]; // header only
+        for n in 1..=rec.inputs.len() {
+            let prefix = InputRecording { inputs: rec.inputs[..n].to_vec(), ..rec.clone() };
+            ends.push(prefix.to_bytes().len());
+        }
+        // cutting between inputs leaves a shorter valid recording, anywhere else is an error
+        for len in 0..bytes.len() {
+            match InputRecording::from_bytes(&bytes[..len]) {
+                Ok(read) => {
+                    assert!(ends.contains(&len), "accepted {} bytes", len);
+                    assert_eq!(read.inputs[..], rec.inputs[..read.inputs.len()]);
+                }
+                Err(e) => {
+                    assert!(!ends.contains(&len), "rejected {} bytes", len);
+                    assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt() {
+        let bytes = every_event().to_bytes();
+        let with = |i: usize, b: u8| { let mut bytes = bytes.clone(); bytes[i] = b; bytes };
+        assert!(invalid_data(&with(0, b'X'))); // magic
+        assert!(invalid_data(&with(4, 1))); // version
+        assert!(invalid_data(&with(10, 200))); // mode
+        assert!(invalid_data(&with(12, 99))); // tag of the first input
+
+        // step deltas which add up past u64::MAX
+        let mut rec = InputRecording::new(0, false, ModeKind::Zen);
+        rec.push(1, InputEvent::Click);
+        let mut bytes = rec.to_bytes();
+        write_varint(&mut bytes, u64::MAX).unwrap();
+        bytes.push(InputEvent::TAG_CLICK);
+        assert!(invalid_data(&bytes));
+    }
+
+    #[test]
+    fn cursor_order() {
+        let mut rec = InputRecording::new(0, false, ModeKind::Zen);
+        rec.push(0, InputEvent::Click);
+        rec.push(2, InputEvent::Release);
+        rec.push(2, InputEvent::EndRun);
+        let mut cursor = ReplayCursor::new(rec);
+        assert_eq!(cursor.next_due(0), Some(InputEvent::Click));
+        assert_eq!(cursor.next_due(0), None);
+        assert_eq!(cursor.next_due(1), None);
+        assert!(!cursor.is_finished());
+        assert_eq!(cursor.next_due(5), Some(InputEvent::Release));
+        assert_eq!(cursor.next_due(5), Some(InputEvent::EndRun));
+        assert_eq!(cursor.next_due(5), None);
+        assert!(cursor.is_finished());
+    }
+
+    // same as GameSystem::apply_input, leaving out what only affects presentation
+    fn apply(sim: &mut GameSimulation, recording: Option<&mut InputRecording>, event: InputEvent) {
+        if let Some(recording) = recording
+            && (!matches!(event, InputEvent::MouseMove {..}) || sim.state.accepts_mouse()) {
+            recording.push(sim.steps, event);
+        }
+        match event {
+            InputEvent::Click => { sim.on_click(); }
+            InputEvent::Release => sim.on_release(),
+            InputEvent::MouseMove { dx, dy } => sim.on_mouse_move(dx as f64, dy as f64),
+            InputEvent::CursorUngrab => sim.on_cursor_ungrab(),
+            InputEvent::ToggleMusic => {}
+            InputEvent::Zoom { held } => sim.on_zoom(held),
+            InputEvent::CycleMode { forward } => sim.on_cycle_mode(forward),
+            InputEvent::EndRun => sim.on_end_run(),
+        }
+    }
+
+    // a short run of mode selection, aiming, shooting, pausing and quitting
+    fn script() -> Vec<TimedInput> {
+        let mut script = vec![
+            TimedInput { step: 0, event: InputEvent::CycleMode { forward: true } },
+            TimedInput { step: 1, event: InputEvent::Click },
+            TimedInput { step: 1800, event: InputEvent::CursorUngrab },
+            TimedInput { step: 1900, event: InputEvent::Click },
+            TimedInput { step: 3300, event: InputEvent::CursorUngrab },
+            TimedInput { step: 3301, event: InputEvent::EndRun },
+        ];
+        for step in (2..3300).step_by(3) {
+            let t = step as f32 * 0.01;
+            script.push(TimedInput { step, event: InputEvent::MouseMove { dx: 9.0 * t.sin(), dy: 4.0 * (0.7 * t).cos() } });
+        }
+        for step in (700..3200).step_by(90) {
+            script.push(TimedInput { step, event: InputEvent::Click });
+            script.push(TimedInput { step: step + 45, event: InputEvent::Release });
+        }
+        script.push(TimedInput { step: 1000, event: InputEvent::Zoom { held: true } });
+        script.push(TimedInput { step: 1200, event: InputEvent::Zoom { held: false } });
+        script.sort_by_key(|input| input.step);
+        script
+    }
+
+    const RUN_STEPS: u64 = 4000;
+
+    // debug output of every event, which includes scores, hits and state changes
+    fn drain(sim: &mut GameSimulation, log: &mut Vec<String>) {
+        log.extend(sim.events.take().iter().map(|e| format!("{:?}", e)));
+    }
+
+    #[test]
+    fn replay_reproduces_run() {
+        let assets = LocalAssetFolder::new(concat!(env!("CARGO_MANIFEST_DIR"), "/assets"));
+        let start = Instant::now();
+
+        let mut live = GameSimulation::new(&assets, start, 1, false, ModeKind::default());
+        live.restart(1234, true, ModeKind::default());
+        let mut recording = InputRecording::new(live.seed, live.fixed_seed, live.mode.kind());
+        let mut live_log = Vec::new();
+        let mut script = script().into_iter().peekable();
+        while live.steps < RUN_STEPS {
+            while let Some(input) = script.next_if(|input| input.step <= live.steps) {
+                apply(&mut live, Some(&mut recording), input.event);
+            }
+            live.step();
+            drain(&mut live, &mut live_log);
+        }
+        assert!(live.arrows.arrows_shot > 10 && live.scoring.score.shots_hit > 0);
+        assert!(matches!(live.state, crate::ui::GameState::Results));
+
+        // a different seed up front, which the restart has to undo
+        let recording = InputRecording::from_bytes(&recording.to_bytes()).unwrap();
+        let mut replay = GameSimulation::new(&assets, start, 99, false, ModeKind::Zen);
+        replay.restart(recording.seed, recording.fixed_seed, recording.mode);
+        let mut replay_log = Vec::new();
+        let mut cursor = ReplayCursor::new(recording);
+        while replay.steps < RUN_STEPS {
+            while let Some(event) = cursor.next_due(replay.steps) {
+                apply(&mut replay, None, event);
+            }
+            replay.step();
+            drain(&mut replay, &mut replay_log);
+        }
+        assert!(cursor.is_finished());
+
+        assert_eq!(live_log, replay_log);
+        assert_eq!(live.scoring.score, replay.scoring.score);
+        assert_eq!(live.mode.kind(), replay.mode.kind());
+        assert_eq!(live.arrows.arrows_shot, replay.arrows.arrows_shot);
+        assert_eq!(live.targets.targets_hit, replay.targets.targets_hit);
+    }
+}
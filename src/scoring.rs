@@ -0,0 +1,273 @@
+use glam::*;
+
+use crate::events::GameEvent;
+
+// Totals for a run, as shown in the HUD and on the finish screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunScore {
+    pub points: u32,
+    pub shots: u32,
+    pub shots_hit: u32, // arrows which smashed or cracked at least one pot, or caught a fish
+    pub targets_hit: u32, // pots smashed
+    pub fish_hit: u32,
+    pub streak: u32, // hitting arrows in a row
+    pub best_streak: u32,
+    pub hits_by_depth: [u32; DEPTH_BUCKETS.len() + 1], // pots smashed in each depth range
+}
+
+impl RunScore {
+    // percentage of arrows which hit something, None before the first arrow lands
+    pub fn accuracy(&self) -> Option<u32> {
+        (100 * self.shots_hit + self.shots / 2).checked_div(self.shots)
+    }
+
+    // applied to pots smashed while on a streak
+    pub fn streak_multiplier(&self) -> f32 {
+        1.0 + STREAK_STEP * self.streak.min(MAX_STREAK_STEPS) as f32
+    }
+}
+
+// upper limits of the depth ranges for `RunScore::hits_by_depth` in meters, the last range is unbounded
+pub const DEPTH_BUCKETS: [f32; 3] = [0.5, 1.0, 2.0];
+
+// One circuit of the rail, or the part of one that a run ended in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LapSplit {
+    pub time: f64, // seconds, including time spent paused
+    pub targets_hit: u32,
+}
+
+const BASE_POINTS: f32 = 100.0;
+const DEPTH_POINTS_PER_M: f32 = 100.0;
+const DISTANCE_POINTS_PER_M: f32 = 5.0;
+const CLOSE_RANGE: f32 = 10.0; // no distance bonus within this horizontal range
+const STREAK_STEP: f32 = 0.1;
+const MAX_STREAK_STEPS: u32 = 10;
+
+// an arrow which may still smash pots
+#[derive(Clone, Copy, Debug)]
+struct Shot {
+    arrow: u32,
+    pos: Vec3,
+    hits: u32, // pots smashed and fish caught
+    cracked: bool, // struck a pot that held
+}
+
+// Scores a run from its gameplay events.
+// Each kind of pot has its own base value, and refraction makes deep and far away pots harder to hit, so they are worth more,
+// each further pot or fish hit by the same arrow counts one more time over (2x, 3x, ...),
+// and every arrow in a row that smashes something adds to a streak multiplier.
+#[derive(Clone, Debug, Default)]
+pub struct ScoreKeeper {
+    pub score: RunScore,
+    pub laps: Vec<LapSplit>,
+    shots: Vec<Shot>,
+    lap_started_at: f64,
+    lap_targets_hit: u32,
+}
+
+impl ScoreKeeper {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn process(&mut self, events: &[GameEvent]) {
+        for event in events {
+            match *event {
+                GameEvent::ArrowReleased { arrow, pos, .. } => {
+                    self.shots.push(Shot { arrow, pos, hits: 0, cracked: false });
+                }
+                GameEvent::TargetCracked { arrow, .. } => {
+                    if let Some(shot) = self.shots.iter_mut().find(|s| s.arrow == arrow) {
+                        shot.cracked = true;
+                    }
+                }
+                GameEvent::TargetSmashed { arrow, pos, depth, .. } | GameEvent::FishHit { arrow, pos, depth, .. } => {
+                    let Some(shot) = self.shots.iter_mut().find(|s| s.arrow == arrow) else { continue };
+                    shot.hits += 1;
+                    let distance = (pos.xy() - shot.pos.xy()).length();
+                    let base = match *event {
+                        GameEvent::TargetSmashed { points, .. } => {
+                            self.score.targets_hit += 1;
+                            self.lap_targets_hit += 1;
+                            let bucket = DEPTH_BUCKETS.iter().position(|limit| depth < *limit).unwrap_or(DEPTH_BUCKETS.len());
+                            self.score.hits_by_depth[bucket] += 1;
+                            points as f32
+                        }
+                        _ => {
+                            self.score.fish_hit += 1;
+                            BASE_POINTS
+                        }
+                    };
+                    let points = pot_points(base, depth, distance) * shot.hits as f32 * self.score.streak_multiplier();
+                    self.score.points += points.round() as u32;
+                }
+                GameEvent::ArrowSpent { arrow, .. } => {
+                    let Some(i) = self.shots.iter().position(|s| s.arrow == arrow) else { continue };
+                    let shot = self.shots.swap_remove(i);
+                    self.score.shots += 1;
+                    if shot.hits > 0 || shot.cracked {
+                        self.score.shots_hit += 1;
+                        self.score.streak += 1;
+                        self.score.best_streak = self.score.best_streak.max(self.score.streak);
+                    } else {
+                        self.score.streak = 0;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // `elapsed` is seconds since the start of the run including pauses, laps with nothing in them are skipped
+    pub fn end_lap(&mut self, elapsed: f64) {
+        if elapsed > self.lap_started_at {
+            self.laps.push(LapSplit {time: elapsed - self.lap_started_at, targets_hit: self.lap_targets_hit});
+            self.lap_started_at = elapsed;
+            self.lap_targets_hit = 0;
+        }
+    }
+}
+
+// points for a single pot worth `base` before multipliers
+pub fn pot_points(base: f32, depth: f32, distance: f32) -> f32 {
+    base + DEPTH_POINTS_PER_M * depth + DISTANCE_POINTS_PER_M * (distance - CLOSE_RANGE).max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(arrow: u32) -> GameEvent {
+        GameEvent::ArrowReleased { arrow, eye: Vec3::ZERO, pos: Vec3::ZERO, dir: Vec3::X }
+    }
+
+    // close enough for no distance bonus
+    fn smash(arrow: u32, depth: f32) -> GameEvent {
+        GameEvent::TargetSmashed { arrow, id: 0, pos: vec3(5.0, 0.0, -depth), depth, archetype: 0, points: 100 }
+    }
+
+    fn crack(arrow: u32) -> GameEvent {
+        GameEvent::TargetCracked { arrow, id: 0, pos: Vec3::ZERO }
+    }
+
+    fn spent(arrow: u32) -> GameEvent {
+        GameEvent::ArrowSpent { arrow, pos: Vec3::ZERO }
+    }
+
+    fn run(events: &[GameEvent]) -> RunScore {
+        let mut keeper = ScoreKeeper::default();
+        keeper.process(events);
+        keeper.score
+    }
+
+    #[test]
+    fn streaks() {
+        let score = run(&[
+            release(0), smash(0, 0.0), spent(0),
+            release(1), crack(1), spent(1), // a pot that held still keeps the streak going
+            release(2), smash(2, 0.0), spent(2),
+            release(3), spent(3),
+            release(4), smash(4, 0.0), spent(4),
+        ]);
+        assert_eq!((score.shots, score.shots_hit, score.targets_hit), (5, 4, 3));
+        assert_eq!((score.streak, score.best_streak), (1, 3));
+        assert_eq!(score.accuracy(), Some(80));
+
+        // each arrow in the streak before a smash adds a step to its multiplier
+        let points = [100.0, 100.0 * 1.2, 100.0];
+        assert_eq!(score.points, points.iter().sum::<f32>().round() as u32);
+    }
+
+    #[test]
+    fn streak_multiplier_is_capped() {
+        let mut events = Vec::new();
+        for arrow in 0..15 {
+            events.extend([release(arrow), smash(arrow, 0.0), spent(arrow)]);
+        }
+        let score = run(&events);
+        assert_eq!(score.best_streak, 15);
+        assert_eq!(score.streak_multiplier(), 1.0 + STREAK_STEP * MAX_STREAK_STEPS as f32);
+        let expected: f32 = (0..15).map(|i| 100.0 * (1.0 + STREAK_STEP * i.min(MAX_STREAK_STEPS) as f32)).sum();
+        assert_eq!(score.points, expected.round() as u32);
+    }
+
+    #[test]
+    fn multi_pot_multiplier() {
+        let score = run(&[release(0), smash(0, 0.0), smash(0, 0.0), smash(0, 0.0), spent(0)]);
+        assert_eq!(score.points, 100 + 200 + 300);
+        assert_eq!((score.targets_hit, score.shots_hit, score.streak), (3, 1, 1));
+
+        // arrows in flight at the same time are counted separately
+        let score = run(&[release(0), release(1), smash(1, 0.0), smash(0, 0.0), smash(1, 0.0), spent(0), spent(1)]);
+        assert_eq!(score.points, 100 + 100 + 200);
+        assert_eq!(score.shots_hit, 2);
+    }
+
+    #[test]
+    fn fish_are_counted_apart_from_pots() {
+        let fish = |arrow| GameEvent::FishHit { arrow, id: 0, pos: vec3(5.0, 0.0, -1.0), depth: 1.0 };
+        let mut keeper = ScoreKeeper::default();
+        keeper.process(&[release(0), fish(0), spent(0), release(1), smash(1, 0.0), fish(1), spent(1)]);
+        keeper.end_lap(30.0);
+        let score = keeper.score;
+        assert_eq!((score.targets_hit, score.fish_hit), (1, 2));
+        assert_eq!(score.hits_by_depth, [1, 0, 0, 0]);
+        assert_eq!(keeper.laps[0].targets_hit, 1);
+        // fish still hit arrows, keep streaks going and add to the multiplier
+        assert_eq!((score.shots_hit, score.best_streak), (2, 2));
+        let fish_points = pot_points(BASE_POINTS, 1.0, 5.0);
+        let expected = fish_points + 1.1 * (100.0 + 2.0 * fish_points);
+        assert_eq!(score.points, expected.round() as u32);
+    }
+
+    #[test]
+    fn depth_and_distance() {
+        let score = run(&[
+            release(0), smash(0, 0.0), smash(0, 0.5), spent(0),
+            release(1), smash(1, 0.99), spent(1),
+            release(2), smash(2, 1.0), smash(2, 1.99), spent(2),
+            release(3), smash(3, 2.0), smash(3, 7.0), spent(3),
+        ]);
+        assert_eq!(score.hits_by_depth, [1, 2, 2, 2]);
+
+        assert_eq!(pot_points(100.0, 0.0, 0.0), 100.0);
+        assert_eq!(pot_points(100.0, 1.5, 0.0), 100.0 + 1.5 * DEPTH_POINTS_PER_M);
+        assert_eq!(pot_points(150.0, 0.0, CLOSE_RANGE), 150.0);
+        assert_eq!(pot_points(150.0, 0.0, CLOSE_RANGE + 4.0), 150.0 + 4.0 * DISTANCE_POINTS_PER_M);
+
+        // distance is horizontal from where the arrow was released
+        let far = GameEvent::TargetSmashed { arrow: 0, id: 0, pos: vec3(0.0, 30.0, -50.0), depth: 0.0, archetype: 0, points: 100 };
+        assert_eq!(run(&[release(0), far, spent(0)]).points, pot_points(100.0, 0.0, 30.0).round() as u32);
+    }
+
+    #[test]
+    fn unknown_arrows_and_accuracy() {
+        // hits from arrows released before a reset are ignored
+        let score = run(&[smash(7, 1.0), spent(7)]);
+        assert_eq!(score, RunScore::default());
+        assert_eq!(score.accuracy(), None);
+
+        let mut events = Vec::new();
+        for arrow in 0..3 {
+            events.push(release(arrow));
+            if arrow == 0 {
+                events.push(smash(arrow, 0.0));
+            }
+            events.push(spent(arrow));
+        }
+        assert_eq!(run(&events).accuracy(), Some(33));
+        assert_eq!(run(&events[..3]).accuracy(), Some(100));
+    }
+
+    #[test]
+    fn laps() {
+        let mut keeper = ScoreKeeper::default();
+        keeper.process(&[release(0), smash(0, 0.0), smash(0, 0.0), spent(0)]);
+        keeper.end_lap(60.0);
+        keeper.end_lap(60.0); // nothing since the last one
+        keeper.process(&[release(1), smash(1, 0.0), spent(1)]);
+        keeper.end_lap(95.5);
+        assert_eq!(keeper.laps, [LapSplit {time: 60.0, targets_hit: 2}, LapSplit {time: 35.5, targets_hit: 1}]);
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt::Write;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+// Player preferences, stored as `<name> = <value>` lines in the same style as bindings.cfg.
+// Natively this lives in settings.cfg in the working directory, on the web in localStorage.
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    pub mouse_sensitivity: f32, // multiplier on the default turn rate
+    pub invert_y: bool,
+    pub fov_y_deg: f32,
+    pub master_volume: f32, // linear, 0 to 1
+    pub music_volume: f32,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            mouse_sensitivity: 1.0,
+            invert_y: false,
+            fov_y_deg: 60.0,
+            master_volume: 1.0,
+            music_volume: 1.0,
+        }
+    }
+}
+
+impl Settings {
+    // the sliders in html/index.html cover the same ranges
+    pub const SENSITIVITY_RANGE: (f32, f32) = (0.1, 10.0);
+    pub const FOV_RANGE: (f32, f32) = (40.0, 100.0);
+
+    // unknown or invalid entries are skipped with a warning, so an old or hand edited file never locks anyone out
+    pub fn parse(text: &str) -> Self {
+        let mut settings = Settings::default();
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((name, value)) = line.split_once('=') else {
+                log::warn!("settings line {}: expected <name> = <value>", line_num + 1);
+                continue;
+            };
+            let (name, value) = (name.trim(), value.trim());
+            let ok = match name {
+                "mouse_sensitivity" => value.parse().map(|v| settings.mouse_sensitivity = v).is_ok(),
+                "invert_y" => value.parse().map(|v| settings.invert_y = v).is_ok(),
+                "fov_y_deg" => value.parse().map(|v| settings.fov_y_deg = v).is_ok(),
+                "master_volume" => value.parse().map(|v| settings.master_volume = v).is_ok(),
+                "music_volume" => value.parse().map(|v| settings.music_volume = v).is_ok(),
+                _ => {
+                    log::warn!("settings line {}: unknown setting {}", line_num + 1, name);
+                    continue;
+                }
+            };
+            if !ok {
+                log::warn!("settings line {}: invalid value {} for {}", line_num + 1, value, name);
+            }
+        }
+        settings.clamped()
+    }
+
+    pub fn to_config_string(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "mouse_sensitivity = {}", self.mouse_sensitivity);
+        let _ = writeln!(out, "invert_y = {}", self.invert_y);
+        let _ = writeln!(out, "fov_y_deg = {}", self.fov_y_deg);
+        let _ = writeln!(out, "master_volume = {}", self.master_volume);
+        let _ = writeln!(out, "music_volume = {}", self.music_volume);
+        out
+    }
+
+    pub fn clamped(self) -> Self {
+        // NaN from a bad edit falls back to the default instead of propagating
+        let clamp = |v: f32, (lo, hi): (f32, f32), default: f32| if v.is_nan() {default} else {v.clamp(lo, hi)};
+        let default = Settings::default();
+        Settings {
+            mouse_sensitivity: clamp(self.mouse_sensitivity, Self::SENSITIVITY_RANGE, default.mouse_sensitivity),
+            invert_y: self.invert_y,
+            fov_y_deg: clamp(self.fov_y_deg, Self::FOV_RANGE, default.fov_y_deg),
+            master_volume: clamp(self.master_volume, (0.0, 1.0), default.master_volume),
+            music_volume: clamp(self.music_volume, (0.0, 1.0), default.music_volume),
+        }
+    }
+
+    // scales raw mouse movement into look movement
+    pub fn look_delta(&self, dx: f32, dy: f32) -> (f32, f32) {
+        let y_sign = if self.invert_y {-1.0} else {1.0};
+        (dx * self.mouse_sensitivity, dy * self.mouse_sensitivity * y_sign)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Settings {
+    // a missing file gives the defaults
+    pub fn load_file(path: &std::path::Path) -> std::io::Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save_file(&self, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_config_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Settings {
+    const STORAGE_KEY: &'static str = "bowfishing_blitz.settings";
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    pub fn load_local_storage() -> Self {
+        let text = Self::local_storage().and_then(|s| s.get_item(Self::STORAGE_KEY).ok().flatten());
+        text.map(|t| Self::parse(&t)).unwrap_or_default()
+    }
+
+    pub fn save_local_storage(&self) {
+        let saved = Self::local_storage().map(|s| s.set_item(Self::STORAGE_KEY, &self.to_config_string()));
+        if !matches!(saved, Some(Ok(()))) {
+            log::warn!("could not save settings to localStorage");
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+impl Settings {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let settings = Settings {mouse_sensitivity: 2.5, invert_y: true, fov_y_deg: 75.0, master_volume: 0.8, music_volume: 0.0};
+        assert_eq!(Settings::parse(&settings.to_config_string()), settings);
+        assert_eq!(Settings::parse(&Settings::default().to_config_string()), Settings::default());
+        assert_eq!(Settings::parse(""), Settings::default());
+    }
+
+    #[test]
+    fn clamps_out_of_range() {
+        let settings = Settings::parse("mouse_sensitivity = 50\nfov_y_deg = 10\nmaster_volume = -1\nmusic_volume = inf");
+        assert_eq!(settings.mouse_sensitivity, Settings::SENSITIVITY_RANGE.1);
+        assert_eq!(settings.fov_y_deg, Settings::FOV_RANGE.0);
+        assert_eq!(settings.master_volume, 0.0);
+        assert_eq!(settings.music_volume, 1.0);
+
+        let settings = Settings::parse("mouse_sensitivity = NaN\nfov_y_deg = NaN\nmaster_volume = 0.5");
+        assert_eq!(settings, Settings {master_volume: 0.5, ..Settings::default()});
+    }
+
+    #[test]
+    fn skips_unknown_and_invalid() {
+        let text = "# settings\nbrightness = 3\ninvert_y = yes\nfov_y_deg\nfov_y_deg = wide\nmusic_volume = 0.25 # quieter";
+        assert_eq!(Settings::parse(text), Settings {music_volume: 0.25, ..Settings::default()});
+    }
+
+    #[test]
+    fn look_delta() {
+        let settings = Settings {mouse_sensitivity: 2.0, ..Settings::default()};
+        assert_eq!(settings.look_delta(1.0, 3.0), (2.0, 6.0));
+        assert_eq!(Settings {invert_y: true, ..settings}.look_delta(1.0, 3.0), (2.0, -6.0));
+    }
+}
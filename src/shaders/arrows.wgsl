@@ -5,10 +5,13 @@ enable clip_distances;
 #include global.wgsl
 
 struct Arrow {
-    end_pos: vec3f,
+    end_pos: vec3f, // relative to the parent
     state: u32,
     dir: vec3f,
     len: f32,
+    parent_rot: vec4f, // the target an arrow is stuck in, identity otherwise
+    parent_pos: vec3f,
+    pad: f32,
 }
 
 @group(1) @binding(0) var<storage, read> arrows: array<Arrow>;
@@ -37,15 +40,17 @@ struct ArrowFragIn {
 
 @vertex fn arrow_vert(vert: ArrowVSIn, @builtin(instance_index) inst: u32) -> ArrowVSOut {
     let arr = arrows[inst];
-    let right = -normalize(cross(vec3f(0.0, 0.0, 1.0), arr.dir));
-    let up = normalize(cross(right, arr.dir));
+    let end_pos = arr.parent_pos + quat_rotate(arr.parent_rot, arr.end_pos);
+    let dir = quat_rotate(arr.parent_rot, arr.dir);
+    let right = -normalize(cross(vec3f(0.0, 0.0, 1.0), dir));
+    let up = normalize(cross(right, dir));
 
     // includes stretch and squash for visibility
-    let width = clamp(sqrt(length(arr.end_pos - camera.eye) / 8), 1.0, 3.0);
-    let pos_mat = mat3x3f(width * right, arr.len * normalize(arr.dir), width * up);
-    let norm_mat = mat3x3f(right, normalize(arr.dir), up);
+    let width = clamp(sqrt(length(end_pos - camera.eye) / 8), 1.0, 3.0);
+    let pos_mat = mat3x3f(width * right, arr.len * normalize(dir), width * up);
+    let norm_mat = mat3x3f(right, normalize(dir), up);
 
-    let world_pos = arr.end_pos + pos_mat * vert.pos;
+    let world_pos = end_pos + pos_mat * vert.pos;
     let world_norm = norm_mat * vert.norm;
 
     var out: ArrowVSOut;
@@ -61,14 +66,16 @@ struct ArrowFragIn {
 
 @vertex fn arrow_vert_shadow(vert: ArrowVSIn, @builtin(instance_index) inst: u32) -> @builtin(position) vec4f {
     let arr = arrows[inst];
-    let right = -normalize(cross(vec3f(0.0, 0.0, 1.0), arr.dir));
-    let up = normalize(cross(right, arr.dir));
+    let end_pos = arr.parent_pos + quat_rotate(arr.parent_rot, arr.end_pos);
+    let dir = quat_rotate(arr.parent_rot, arr.dir);
+    let right = -normalize(cross(vec3f(0.0, 0.0, 1.0), dir));
+    let up = normalize(cross(right, dir));
 
     // includes stretch and squash for visibility
-    let width = clamp(sqrt(length(arr.end_pos - camera.eye) / 8), 1.0, 3.0);
-    let pos_mat = mat3x3f(width * right, arr.len * normalize(arr.dir), width * up);
+    let width = clamp(sqrt(length(end_pos - camera.eye) / 8), 1.0, 3.0);
+    let pos_mat = mat3x3f(width * right, arr.len * normalize(dir), width * up);
 
-    let world_pos = arr.end_pos + pos_mat * vert.pos;
+    let world_pos = end_pos + pos_mat * vert.pos;
 
     return shadow_clip_point(world_pos);
 }
@@ -111,6 +118,7 @@ struct ArrowFragIn {
 struct Splish {
     @location(0) center: vec2f,
     @location(1) start_time: f32,
+    @location(2) skip_dir: vec2f, // zero for arrows going in
 }
 
 @group(1) @binding(0) var<storage, read> splish_buf: array<Splish>;
@@ -119,16 +127,28 @@ struct SplishVSOut {
     @builtin(position) clip_pos: vec4f,
     @location(0) local_xy: vec2f,
     @location(1) time: f32,
+    @location(2) @interpolate(flat) skip_dir: vec2f,
+}
+
+// skips leave a streak stretched along the arrow's path
+const SKIP_STRETCH: vec2f = vec2f(2.0, 0.4);
+
+fn splish_basis(skip_dir: vec2f) -> mat2x2f {
+    if dot(skip_dir, skip_dir) == 0.0 {
+        return mat2x2f(1.0, 0.0, 0.0, 1.0);
+    }
+    return mat2x2f(SKIP_STRETCH.x * skip_dir, SKIP_STRETCH.y * vec2f(-skip_dir.y, skip_dir.x));
 }
 
 @vertex fn splish_vert(@builtin(vertex_index) vert: u32, inst: Splish) -> SplishVSOut {
     let local_xy = vec2f(2.0 * f32(vert / 2) - 1.0, 1.0 - 2.0 * f32(vert % 2));
-    let world_pos = vec3f(inst.center + local_xy, 0.0);
+    let world_pos = vec3f(inst.center + splish_basis(inst.skip_dir) * local_xy, 0.0);
     
     var out: SplishVSOut;
     out.clip_pos = camera.matrix * vec4f(world_pos, 1.0);
     out.local_xy = local_xy;
     out.time = 0.5 * (camera.time - inst.start_time);
+    out.skip_dir = inst.skip_dir;
     return out;
 }
 
@@ -144,7 +164,9 @@ struct SplishVSOut {
     let alpha = 0.5 * t_fac * r_fac;
 
     let dzdr = sin(TAU * 20 * (r - 1.2 * v.time));
-    let r_dir = normalize(v.local_xy);
+    // the ripple slopes along the gradient of r in world space
+    let basis = splish_basis(v.skip_dir);
+    let r_dir = normalize(basis * (v.local_xy / vec2f(dot(basis[0], basis[0]), dot(basis[1], basis[1]))));
 
     var out: GBufferPoint;
     out.normal = vec4f(0.5 - 0.4 * dzdr * r_dir, 0.0, alpha);
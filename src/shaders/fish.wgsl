@@ -0,0 +1,97 @@
+#if CAN_CLIP
+enable clip_distances;
+#endif
+
+#include global.wgsl
+
+struct FishInst {
+    pos: vec3f,
+    phase: f32,
+    rotation: vec4f,
+    color: vec3f,
+    wiggle: f32,
+}
+
+@group(1) @binding(0) var<storage, read> fish: array<FishInst>;
+
+struct FishVSIn {
+    @location(0) pos: vec3f,
+    @location(1) norm: vec3f,
+    @location(2) fin: f32,
+}
+
+struct FishVSOut {
+    #if CAN_CLIP
+        @builtin(clip_distances) clip: array<f32, 1>,
+    #endif
+    @builtin(position) clip_pos: vec4f,
+    @location(0) world_pos: vec3f,
+    @location(1) world_norm: vec3f,
+    @location(2) local_up: f32,
+    @location(3) fin: f32,
+    @location(4) color: vec3f,
+}
+
+struct FishFragIn {
+    @location(0) world_pos: vec3f,
+    @location(1) world_norm: vec3f,
+    @location(2) local_up: f32,
+    @location(3) fin: f32,
+    @location(4) color: vec3f,
+}
+
+const WIGGLE_AMP: f32 = 0.08;
+const WAVE_NUMBER: f32 = 10.0; // radians per meter along the body
+
+// the body bends side to side in a wave running back to the tail, the head barely moves
+fn swim_offset(inst: FishInst, local_pos: vec3f) -> vec3f {
+    let bend = smoothstep(0.15, -0.42, local_pos.x);
+    let sway = WIGGLE_AMP * inst.wiggle * bend * sin(TAU * inst.phase - WAVE_NUMBER * local_pos.x);
+    return vec3f(local_pos.x, local_pos.y + sway, local_pos.z);
+}
+
+@vertex fn fish_vert(vert: FishVSIn, @builtin(instance_index) inst_idx: u32) -> FishVSOut {
+    let inst = fish[inst_idx];
+    let world_pos = inst.pos + quat_rotate(inst.rotation, swim_offset(inst, vert.pos));
+    let world_norm = quat_rotate(inst.rotation, vert.norm);
+
+    var out: FishVSOut;
+    #if CAN_CLIP
+        out.clip[0] = clip_dist(world_pos);
+    #endif
+    out.clip_pos = clip_point(world_pos);
+    out.world_pos = world_pos;
+    out.world_norm = world_norm;
+    out.local_up = vert.norm.z;
+    out.fin = vert.fin;
+    out.color = inst.color;
+    return out;
+}
+
+@vertex fn fish_vert_shadow(vert: FishVSIn, @builtin(instance_index) inst_idx: u32) -> @builtin(position) vec4f {
+    let inst = fish[inst_idx];
+    let world_pos = inst.pos + quat_rotate(inst.rotation, swim_offset(inst, vert.pos));
+    return shadow_clip_point(world_pos);
+}
+
+@fragment fn fish_frag(v: FishFragIn, @builtin(front_facing) is_forward: bool) -> GBufferPoint {
+    #if !CAN_CLIP
+        guard_frag(v.world_pos.z);
+    #endif
+
+    let norm = normalize(v.world_norm) * select(-1.0, 1.0, is_forward);
+
+    // dark back and pale belly, fins a little see-through looking
+    let shade = mix(0.35, 1.0, smoothstep(-0.8, 0.6, v.local_up));
+    let belly = smoothstep(-0.3, -0.9, v.local_up);
+    var albedo = mix(v.color * shade, vec3f(0.85, 0.82, 0.75), belly);
+    albedo = mix(albedo, 0.6 * v.color, v.fin);
+
+    var out: GBufferPoint;
+    out.albedo = vec4f(albedo, 1.0);
+    out.normal = vec4f(0.5 * (norm + 1), 1.0);
+    out.rough_metal = vec2f(mix(0.3, 0.6, v.fin), mix(0.3, 0.0, v.fin));
+    out.occlusion = 1.0;
+    out.mat_type = MAT_SOLID;
+    return out;
+}
@@ -18,4 +18,6 @@ pub const ARROWS: &str = include_str!("arrows.wgsl");
 
 pub const TARGETS: &str = include_str!("targets.wgsl");
 
+pub const FISH: &str = include_str!("fish.wgsl");
+
 pub const UI: &str = include_str!("ui.wgsl");
\ No newline at end of file
@@ -3,7 +3,6 @@ enable clip_distances;
 #endif
 
 #include global.wgsl
-#include noise.wgsl
 
 struct PotInst {
     // must preserve angles
@@ -12,6 +11,10 @@ struct PotInst {
     rotate: vec4f,
     colors_packed: vec3u,
     seed: u32,
+    scale: f32,
+    archetype: u32,
+    hits_left: u32,
+    wave: u32,
 }
 
 @group(1) @binding(0) var<storage, read> pots: array<PotInst>;
@@ -23,7 +26,11 @@ struct LathePoint {
     v: f32,
 }
 
-@group(1) @binding(1) var<uniform> pot_model: array<LathePoint, 12>;
+const MAX_ARCHETYPES: u32 = 8;
+const MAX_PROFILE_POINTS: u32 = 12;
+
+// the profiles of every archetype one after another
+@group(1) @binding(1) var<uniform> pot_model: array<LathePoint, MAX_ARCHETYPES * MAX_PROFILE_POINTS>;
 
 struct PotVSOut {
     #if CAN_CLIP
@@ -52,75 +59,53 @@ struct PotFragIn {
 }
 
 const POT_U_DIVS: u32 = 8;
-const EXPLODE_TIME: f32 = 0.5;
-const SINK_TIME: f32 = 1.0;
 
 var<private> QUAD_U: array<f32, 6> = array(0, 0.5, 1, 1, 0.5, 1.5);
 var<private> QUAD_V: array<u32, 6> = array(0, 1, 0, 0, 1, 1);
 
-@vertex fn pot_vert(@builtin(vertex_index) vert_idx: u32, @builtin(instance_index) inst_idx: u32) -> PotVSOut {
-    let ring_idx = vert_idx / (6 * POT_U_DIVS);
-    let quad_idx = (vert_idx / 6) % (POT_U_DIVS);
-    let quad_corner = vert_idx % 6;
+struct LatheVertex {
+    pos: vec3f,
+    norm: vec3f,
+    tan: vec3f,
+    uv: vec2f,
+}
 
-    // solid of rotation
+// a corner of one of the quads making up a solid of rotation, odd rings are turned half a quad
+fn lathe_vertex(archetype: u32, ring_idx: u32, quad_idx: u32, quad_corner: u32) -> LatheVertex {
     let phi: f32 = f32(quad_idx) + QUAD_U[quad_corner] + 0.5 * f32(ring_idx % 2);
     let u = phi / f32(POT_U_DIVS);
     let rho = vec2f(cos(TAU * u), sin(TAU * u));
     let ring_offset = QUAD_V[quad_corner];
-    let point = pot_model[ring_idx + ring_offset];
-    
-    var local_pos = vec3f(rho * point.pos_rz.x, point.pos_rz.y);
+    let point = pot_model[archetype * MAX_PROFILE_POINTS + ring_idx + ring_offset];
     let norm_rz = select(point.norm2_rz, point.norm1_rz, ring_offset == 1);
-    var local_norm = vec3f(rho * norm_rz.x, norm_rz.y);
-    var local_tan = vec3f(-rho.y, rho.x, 0.0);
 
-    let pot = pots[inst_idx];
+    var out: LatheVertex;
+    out.pos = vec3f(rho * point.pos_rz.x, point.pos_rz.y);
+    out.norm = vec3f(rho * norm_rz.x, norm_rz.y);
+    out.tan = vec3f(-rho.y, rho.x, 0.0);
+    out.uv = vec2f(u, point.v);
+    return out;
+}
 
-    var explode_progress:f32 = 0;
-    if pot.time_hit > 0 {
-        let explode_time = saturate((camera.time - pot.time_hit) / EXPLODE_TIME);
-        explode_progress = (2.0 - explode_time) * explode_time;
-        let sink_progress = smoothstep(0.0, 1.0, (camera.time - pot.time_hit) / SINK_TIME);
-
-        let tri_idx = (vert_idx / 3) % (2 * POT_U_DIVS);
-        let tri_u = f32(tri_idx + (ring_idx % 2)) / f32(2 * POT_U_DIVS);
-        let tri_rho = vec2f(cos(TAU * tri_u), sin(TAU * tri_u));
-        let tri_point = pot_model[ring_idx + (quad_corner / 3)];
-        let anchor_pos = vec3f(tri_rho * tri_point.pos_rz.x, tri_point.pos_rz.y);
-        let corner_delta = local_pos - anchor_pos;
-
-        let noise = pcg3d_snorm(vec3i(vec3u(ring_idx, tri_idx, pot.seed)));
-        let explode_delta = explode_progress * (vec3f(tri_rho * tri_point.pos_rz.x, 1.0) + 0.5 * noise);
-        let exploded_pos = anchor_pos + explode_delta;
-
-        let shard_rot = normalize(vec4f(0.3 * explode_progress * noise.z * local_tan, 1.0));
-
-        let world_down = quat_rotate(pot.rotate * vec4f(1, 1, 1, -1), vec3f(0, 0, -1));
-        let world_down_adj = mix(1.0, -1/world_down.z, 0.7);
-        let down = mix(vec3f(0, 0, -1), world_down * world_down_adj, 0.6);
-        let sink_delta = 0.9 * sink_progress * exploded_pos.z * down;
-        let sunk_pos = exploded_pos + sink_delta;
-        local_pos = sunk_pos + quat_rotate(shard_rot, corner_delta);
-        local_norm = quat_rotate(shard_rot, local_norm);
-        // local_tan is the axis so it does not rotate
-    }
+fn pot_vertex(pot: PotInst, vert_idx: u32) -> LatheVertex {
+    return lathe_vertex(pot.archetype, vert_idx / (6 * POT_U_DIVS), (vert_idx / 6) % POT_U_DIVS, vert_idx % 6);
+}
 
-    let world_pos = quat_rotate(pot.rotate, local_pos) + pot.base_point;
-    let world_norm = quat_rotate(pot.rotate, local_norm);
-    let world_tan = quat_rotate(pot.rotate, local_tan);
+fn lathe_out(vert: LatheVertex, world_pos: vec3f, rotate: vec4f, colors_packed: vec3u, explode_progress: f32) -> PotVSOut {
+    let world_norm = quat_rotate(rotate, vert.norm);
+    let world_tan = quat_rotate(rotate, vert.tan);
+
+    let color_a = unpack2x16float(colors_packed.x);
+    let color_ab = unpack2x16float(colors_packed.y);
+    let color_b = unpack2x16float(colors_packed.z);
 
-    let color_a = unpack2x16float(pot.colors_packed.x);
-    let color_ab = unpack2x16float(pot.colors_packed.y);
-    let color_b = unpack2x16float(pot.colors_packed.z);
-    
     var out: PotVSOut;
     #if CAN_CLIP
         out.clip[0] = clip_dist(world_pos);
     #endif
     out.clip_pos = clip_point(world_pos);
     out.world_pos = world_pos;
-    out.uv = vec2f(u, point.v);
+    out.uv = vert.uv;
     out.world_norm = world_norm;
     out.world_tan = world_tan;
     out.world_bitan = cross(world_norm, world_tan);
@@ -130,52 +115,59 @@ var<private> QUAD_V: array<u32, 6> = array(0, 1, 0, 0, 1, 1);
     return out;
 }
 
+@vertex fn pot_vert(@builtin(vertex_index) vert_idx: u32, @builtin(instance_index) inst_idx: u32) -> PotVSOut {
+    let pot = pots[inst_idx];
+    let vert = pot_vertex(pot, vert_idx);
+    let world_pos = quat_rotate(pot.rotate, pot.scale * vert.pos) + pot.base_point;
+    return lathe_out(vert, world_pos, pot.rotate, pot.colors_packed, 0.0);
+}
+
 @vertex fn pot_vert_shadow(@builtin(vertex_index) vert_idx: u32, @builtin(instance_index) inst_idx: u32) -> @builtin(position) vec4f {
-    let ring_idx = vert_idx / (6 * POT_U_DIVS);
-    let quad_idx = (vert_idx / 6) % (POT_U_DIVS);
-    let quad_corner = vert_idx % 6;
+    let pot = pots[inst_idx];
+    let vert = pot_vertex(pot, vert_idx);
+    let world_pos = quat_rotate(pot.rotate, pot.scale * vert.pos) + pot.base_point;
+    return shadow_clip_point(world_pos);
+}
 
-    // solid of rotation
-    let phi: f32 = f32(quad_idx) + QUAD_U[quad_corner] + 0.5 * f32(ring_idx % 2);
-    let u = phi / f32(POT_U_DIVS);
-    let rho = vec2f(cos(TAU * u), sin(TAU * u));
-    let ring_offset = QUAD_V[quad_corner];
-    let point = pot_model[ring_idx + ring_offset];
-    
-    var local_pos = vec3f(rho * point.pos_rz.x, point.pos_rz.y);
-    var local_tan = vec3f(-rho.y, rho.x, 0.0);
+// Pieces of smashed pots, simulated on the CPU.
+// Only shard entry points use this binding, in place of the pots.
+struct ShardInst {
+    pos: vec3f,
+    pad: f32,
+    rotate: vec4f,
+    center: vec3f,
+    scale: f32,
+    colors_packed: vec3u,
+    archetype: u32,
+    first_ring: u32,
+    num_rings: u32,
+    first_quad: u32,
+    num_quads: u32,
+}
 
-    let pot = pots[inst_idx];
+@group(1) @binding(0) var<storage, read> shards: array<ShardInst>;
 
-    var explode_progress:f32 = 0;
-    if pot.time_hit > 0 {
-        let explode_time = saturate((camera.time - pot.time_hit) / EXPLODE_TIME);
-        explode_progress = (2.0 - explode_time) * explode_time;
-        let sink_progress = smoothstep(0.0, 1.0, (camera.time - pot.time_hit) / SINK_TIME);
-
-        let tri_idx = (vert_idx / 3) % (2 * POT_U_DIVS);
-        let tri_u = f32(tri_idx + (ring_idx % 2)) / f32(2 * POT_U_DIVS);
-        let tri_rho = vec2f(cos(TAU * tri_u), sin(TAU * tri_u));
-        let tri_point = pot_model[ring_idx + (quad_corner / 3)];
-        let anchor_pos = vec3f(tri_rho * tri_point.pos_rz.x, tri_point.pos_rz.y);
-        let corner_delta = local_pos - anchor_pos;
-
-        let noise = pcg3d_snorm(vec3i(vec3u(ring_idx, tri_idx, pot.seed)));
-        let explode_delta = explode_progress * (vec3f(tri_rho * tri_point.pos_rz.x, 1.0) + 0.5 * noise);
-        let exploded_pos = anchor_pos + explode_delta;
-
-        let shard_rot = normalize(vec4f(0.3 * explode_progress * noise.z * local_tan, 1.0));
-
-        let world_down = quat_rotate(pot.rotate * vec4f(1, 1, 1, -1), vec3f(0, 0, -1));
-        let world_down_adj = mix(1.0, -1/world_down.z, 0.7);
-        let down = mix(vec3f(0, 0, -1), world_down * world_down_adj, 0.6);
-        let sink_delta = 0.9 * sink_progress * exploded_pos.z * down;
-        let sunk_pos = exploded_pos + sink_delta;
-        local_pos = sunk_pos + quat_rotate(shard_rot, corner_delta);
-    }
+const MAX_SHARD_RINGS: u32 = 3;
+const MAX_SHARD_QUADS: u32 = 2;
 
-    let world_pos = quat_rotate(pot.rotate, local_pos) + pot.base_point;
-    
+// smaller shards repeat their last quad over the unused vertices
+fn shard_vertex(shard: ShardInst, vert_idx: u32) -> LatheVertex {
+    let ring = min(vert_idx / (6 * MAX_SHARD_QUADS), shard.num_rings - 1);
+    let quad = min((vert_idx / 6) % MAX_SHARD_QUADS, shard.num_quads - 1);
+    return lathe_vertex(shard.archetype, shard.first_ring + ring, (shard.first_quad + quad) % POT_U_DIVS, vert_idx % 6);
+}
+
+@vertex fn shard_vert(@builtin(vertex_index) vert_idx: u32, @builtin(instance_index) inst_idx: u32) -> PotVSOut {
+    let shard = shards[inst_idx];
+    let vert = shard_vertex(shard, vert_idx);
+    let world_pos = quat_rotate(shard.rotate, shard.scale * vert.pos - shard.center) + shard.pos;
+    return lathe_out(vert, world_pos, shard.rotate, shard.colors_packed, 1.0);
+}
+
+@vertex fn shard_vert_shadow(@builtin(vertex_index) vert_idx: u32, @builtin(instance_index) inst_idx: u32) -> @builtin(position) vec4f {
+    let shard = shards[inst_idx];
+    let vert = shard_vertex(shard, vert_idx);
+    let world_pos = quat_rotate(shard.rotate, shard.scale * vert.pos - shard.center) + shard.pos;
     return shadow_clip_point(world_pos);
 }
 
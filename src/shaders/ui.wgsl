@@ -9,7 +9,7 @@ struct SDFTextParams {
     margin_vh: f32,
     sdf_rad: f32,
     num_chars: u32,
-    chars: array<u32,4>,
+    chars: array<u32,16>, // MAX_TEXT_CHARS
 }
 
 @group(1) @binding(0) var<storage, read> params_buf: array<SDFTextParams>;
@@ -0,0 +1,260 @@
+use std::mem::discriminant;
+use web_time::Instant;
+use glam::vec3;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{archetypes::ArchetypeSet, arrows::ArrowController, boat_rail::RailController, camera::ShadowSettings, clock::FixedStepper, events::{EventQueue, GameEvent}, fish::FishController, game_mode::{GameMode, ModeConfig, ModeKind, RunStatus, RAIL_PERIOD}, gputil::AssetSource, levels::LayoutGenerator, scoring::ScoreKeeper, targets::TargetController, telemetry::ShotLog, terrain_view::HeightmapTerrain, ui::GameState};
+
+// All gameplay state, stepped at a fixed rate and independent of any GPU or window.
+// GameSystem wraps this with rendering and UI, and it can be driven directly for headless runs.
+pub struct GameSimulation {
+    pub state: GameState,
+    pub mode: Box<dyn GameMode>,
+    mode_config: ModeConfig,
+    pub camera: RailController,
+    pub terrain: HeightmapTerrain,
+    pub arrows: ArrowController,
+    pub targets: TargetController,
+    pub fish: FishController,
+    pub scoring: ScoreKeeper,
+    pub shot_log: ShotLog, // kept after the finish until the next run starts
+    pub now: Instant, // advances by exactly one step per simulation step
+    pub steps: u64, // steps since the last restart
+    playing_since: u64, // step the current run started on, for lap times
+    pub seed: u32, // seed of the current run, shown on the title and finish screens
+    pub fixed_seed: bool, // if false a new seed is picked after each finish
+    pub events: EventQueue, // emptied by whoever is driving the simulation
+    rng: StdRng,
+}
+
+impl GameSimulation {
+    pub fn new(assets: &impl AssetSource, start: Instant, seed: u32, fixed_seed: bool, mode: ModeKind) -> Self {
+        let state = GameState::Title {started_at: start, is_restart: false };
+
+        let shadow_settings = ShadowSettings {
+            sun_dir: vec3(0.548, -0.380, 0.745),
+            range_xy: 60.0,
+            range_z: 10.0,
+        };
+        let camera = RailController::new(shadow_settings);
+
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let terrain = HeightmapTerrain::load(assets);
+        let arrows = ArrowController::new();
+        let archetypes = ArchetypeSet::load(assets);
+        let layouts = LayoutGenerator::load(assets, &archetypes);
+        let targets = TargetController::new(&terrain, archetypes, layouts, &mut rng);
+        let fish = FishController::new(&terrain, &mut rng);
+        let mode_config = ModeConfig::load(assets);
+
+        GameSimulation {
+            state, camera, terrain, arrows, targets, fish, mode_config,
+            scoring: ScoreKeeper::default(),
+            shot_log: ShotLog::default(),
+            mode: mode.create(&mode_config),
+            now: start,
+            steps: 0,
+            playing_since: 0,
+            seed, fixed_seed, rng,
+            events: EventQueue::default(),
+        }
+    }
+
+    // returns to the title screen in the same state as a freshly created simulation with this seed
+    pub fn restart(&mut self, seed: u32, fixed_seed: bool, mode: ModeKind) {
+        self.mode = mode.create(&self.mode_config);
+        self.seed = seed;
+        self.fixed_seed = fixed_seed;
+        self.rng = StdRng::seed_from_u64(seed as u64);
+        self.steps = 0;
+        self.events.clear();
+        let old_state = self.state;
+        self.state = GameState::Title {started_at: self.now, is_restart: false };
+        self.report_state_change(old_state);
+        self.camera.reset(0.0);
+        self.arrows.reset(self.mode.quiver());
+        self.targets.reset(&self.terrain, &mut self.rng);
+        self.fish.reset(&self.terrain, &mut self.rng);
+        self.scoring.reset();
+        self.shot_log.reset(seed, mode);
+    }
+
+    // advances the game by one fixed step, returns if the cursor should be released
+    pub fn step(&mut self) -> bool {
+        let mut should_release_cursor = false;
+        let old_state = self.state;
+        let first_event = self.events.len();
+
+        self.now += FixedStepper::STEP;
+        self.steps += 1;
+        let now = self.now;
+        if self.state.should_reset_world(now) {
+            // every run with the same seed plays out identically, whatever happened on the title screen
+            self.rng = StdRng::seed_from_u64(self.seed as u64);
+            self.targets.reset(&self.terrain, &mut self.rng);
+            self.fish.reset(&self.terrain, &mut self.rng);
+            self.arrows.reset(self.mode.quiver());
+            self.scoring.reset();
+            self.shot_log.reset(self.seed, self.mode.kind());
+            self.camera.reset(-GameState::COUNTDOWN_DURATION.as_secs_f64());
+        }
+        self.state.do_timeout(now);
+        if matches!(old_state, GameState::Finish {..}) && matches!(self.state, GameState::Results) {
+            self.shot_log.finish(&self.targets);
+        }
+        if matches!(old_state, GameState::Countdown {..}) && self.state.is_playing() {
+            self.playing_since = self.steps;
+        }
+
+        // movement and hits continue after the finish to allow for buzzer beater shots
+        if !self.state.is_paused() {
+            let prev_time = self.camera.current_time;
+            let time = self.camera.step(FixedStepper::STEP_SECS);
+            if self.state.is_playing() && (time / RAIL_PERIOD).floor() > (prev_time / RAIL_PERIOD).floor() {
+                self.scoring.end_lap(self.run_elapsed());
+                self.arrows.refill_quiver();
+            }
+            if self.state.is_playing() && self.mode.is_finished(&self.run_status()) {
+                should_release_cursor = true;
+                self.finish();
+            }
+
+            self.arrows.tick(time, &self.terrain, &mut self.events, &mut [
+                &mut self.targets,
+                &mut self.fish,
+            ]);
+            self.camera.set_sway(self.arrows.aim_sway());
+            self.targets.tick(time, &self.terrain, self.events.since(first_event), self.mode.respawn_delay());
+            self.fish.tick(time, &self.terrain, self.events.since(first_event), self.mode.respawn_delay());
+        } else {
+            self.camera.hold();
+            self.arrows.hold();
+            self.targets.hold();
+            self.fish.hold();
+        }
+
+        self.process_events(first_event);
+        self.report_state_change(old_state);
+        should_release_cursor
+    }
+
+    // returns if the cursor should grab
+    pub fn on_click(&mut self) -> bool {
+        let old_state = self.state;
+        let should_grab = match self.state {
+            GameState::Playing => {
+                let has_arrows = self.mode.arrow_limit().is_none_or(|limit| self.arrows.arrows_shot < limit);
+                if has_arrows {
+                    self.arrows.start_draw();
+                }
+                false
+            },
+            GameState::Title {..} => {
+                self.state = GameState::Fade { done_at: self.now + GameState::FADE_DURATION };
+                true
+            }
+            GameState::Paused => {
+                self.state = GameState::Playing;
+                true
+            }
+            GameState::Results => {
+                self.state = GameState::Title {started_at: self.now, is_restart: true};
+                if !self.fixed_seed {
+                    self.seed = self.rng.random();
+                    self.rng = StdRng::seed_from_u64(self.seed as u64);
+                    self.targets.reset(&self.terrain, &mut self.rng);
+                    self.fish.reset(&self.terrain, &mut self.rng);
+                }
+                false
+            }
+            _ => {false}
+        };
+        self.report_state_change(old_state);
+        should_grab
+    }
+
+    // shoots if the bow is drawn
+    pub fn on_release(&mut self) {
+        if self.state.is_playing() {
+            let first_event = self.events.len();
+            self.arrows.release(&mut self.events, &self.camera);
+            self.process_events(first_event);
+        }
+    }
+
+    pub fn on_cursor_ungrab(&mut self) {
+        let old_state = self.state;
+        match self.state {
+            GameState::Playing => {
+                self.state = GameState::Paused;
+                self.arrows.cancel_draw();
+            }
+            GameState::Countdown {..} | GameState::Fade {..} => {
+                self.state = GameState::Title {started_at: self.now, is_restart: false};
+            }
+            _ => {}
+        }
+        self.report_state_change(old_state);
+    }
+
+    pub fn on_mouse_move(&mut self, dx: f64, dy: f64) {
+        if self.state.accepts_mouse() {
+            self.camera.mouse(dx, dy);
+        }
+    }
+
+    // mode selection is only open on the title screen
+    pub fn on_cycle_mode(&mut self, forward: bool) {
+        if let GameState::Title {..} = self.state {
+            self.mode = self.mode.kind().cycle(forward).create(&self.mode_config);
+        }
+    }
+
+    // quitting from the pause screen is the only way to finish modes without a limit
+    pub fn on_end_run(&mut self) {
+        if self.state.is_paused() {
+            self.finish();
+            self.report_state_change(GameState::Paused);
+        }
+    }
+
+    pub fn run_status(&self) -> RunStatus {
+        RunStatus {
+            time: self.camera.current_time,
+            arrows_shot: self.arrows.arrows_shot,
+            arrows_in_flight: self.arrows.arrows_in_flight(),
+            targets_hit: self.targets.targets_hit,
+            points: self.scoring.score.points,
+        }
+    }
+
+    // updates the score and shot log from events pushed since `first_event`
+    fn process_events(&mut self, first_event: usize) {
+        let events = self.events.since(first_event);
+        self.scoring.process(events);
+        self.shot_log.process(events, self.camera.current_time, &self.targets);
+        self.shot_log.trace(self.arrows.live_positions());
+    }
+
+    fn report_state_change(&mut self, old: GameState) {
+        if discriminant(&old) != discriminant(&self.state) {
+            self.events.push(GameEvent::StateChanged { old, new: self.state });
+        }
+    }
+
+    // seconds since the run started, including pauses
+    fn run_elapsed(&self) -> f64 {
+        (self.steps - self.playing_since) as f64 * FixedStepper::STEP_SECS
+    }
+
+    fn finish(&mut self) {
+        self.scoring.end_lap(self.run_elapsed());
+        self.arrows.cancel_draw();
+        self.state = GameState::Finish { done_at: self.now + GameState::FINISH_DURATION };
+        self.camera.finish();
+    }
+
+    pub fn on_zoom(&mut self, held: bool) {
+        self.camera.set_zoom(held);
+    }
+}
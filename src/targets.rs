@@ -1,32 +1,71 @@
 use glam::*;
 use half::f16;
-use kira::manager::AudioManager;
 use wgpu::{util::BufferInitDescriptor, *};
 use wgpu::util::DeviceExt;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::f32::consts::TAU;
 use std::mem::size_of;
 use std::time::Instant;
 
-use crate::arrows::{collide_ray_sphere, ArrowTarget};
-use crate::audio_util::SoundAtlas;
+use crate::archetypes::{ArchetypeSet, TargetArchetype, MAX_ARCHETYPES, MAX_PROFILE_POINTS};
+use crate::arrows::{segment_point_dist_sq, ArrowTarget, HitResponse, TargetHit};
+use crate::collision::{intersect_placed, Lathe};
+use crate::debris::{Breakage, DebrisController, ShardInst, MAX_SHARDS, NUM_SHARD_VERTS};
+use crate::events::{EventQueue, GameEvent};
+use crate::levels::{LayoutGenerator, LevelLayout, TargetPlacement, MAX_LAYOUT_TARGETS};
 use crate::boat_rail::LoopedRail;
 use crate::camera::sphere_visible;
-use crate::{deferred_renderer::{DeferredRenderer, RenderObject}, gputil::*, terrain_view::HeightmapTerrain};
+use crate::{deferred_renderer::{DeferredRenderer, RenderObject}, gputil::*, terrain_view::{water_ripples, HeightmapTerrain}};
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Target {
     bottom: Vec3,
-    time_hit: f32, // 0 is live, 1 is hit
+    time_hit: f32, // negative while live, when it was smashed otherwise
     orientation: Quat,
     color_a: [f16; 3],
     color_b: [f16; 3],
-    seed: u32
+    seed: u32,
+    scale: f32,
+    archetype: u32,
+    hits_left: u32, // arrows still needed to smash it, only used by the simulation
+    wave: u32,
 }
 
-const NUM_TARGETS: usize = 128;
-const TARGET_RADIUS: f32 = 0.5;
+const NUM_TARGETS: usize = 128; // of procedural layouts
+const PENDING: f32 = f32::MAX; // time_hit of targets whose wave hasn't come yet
+
+// some pots float, straddling the waterline
+const NUM_FLOATING: usize = 12;
+const MIN_FLOAT_WATER_DEPTH: f32 = 1.5; // of the bottom below a floating pot
+const FLOAT_DRAFT: f32 = 0.45; // how deep the bottom of a floating pot sits at rest, for a scale of 1
+const RIPPLE_HEAVE_SCALE: f32 = 5.0; // ripples are only drawn as normals, so their height is exaggerated to be seen
+const BOB_STIFFNESS: f32 = 27.0; // per second squared, bobs about once a second
+const BOB_DAMPING: f32 = 1.5; // per second
+const SPLASH_RADIUS: f32 = 4.0; // floats within this of an arrow hitting the water are rocked by it
+const SPLASH_HEAVE: f32 = 0.5; // m/s downwards right at the splash
+const SPLASH_TILT: f32 = 0.4; // slope per second away from the splash
+
+// Buoyancy state of a floating pot, which follows the ripples on a damped spring.
+#[derive(Clone, Copy, Debug)]
+struct Float {
+    target: usize,
+    anchor: Vec2,
+    yaw: f32,
+    draft: f32,
+    heave: f32, // of the bottom above its rest depth
+    heave_vel: f32,
+    tilt: Vec2, // slope of the surface it is sitting level on
+    tilt_vel: Vec2,
+    prev: (Vec3, Quat), // placement at the step before, for interpolation
+}
+
+impl Float {
+    fn placement(&self) -> (Vec3, Quat) {
+        let up = vec3(-self.tilt.x, -self.tilt.y, 1.0).normalize();
+        (self.anchor.extend(self.heave - self.draft), Quat::from_rotation_arc(Vec3::Z, up) * Quat::from_rotation_z(self.yaw))
+    }
+}
 
 #[repr(C, align(8))]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -39,130 +78,280 @@ struct LathePoint {
 }
 
 impl LathePoint {
-    pub fn smooth(pos: Vec2, v: f32, norm: Vec2) -> Self {
-        let norm_rz = norm.normalize();
+    pub fn new(pos: Vec2, v: f32, norm1: Vec2, norm2: Vec2) -> Self {
         LathePoint {
             pos_rz: pos,
-            norm1_rz: norm_rz,
-            norm2_rz: norm_rz,
-            v,
-            pad: 0.0,
-        }
-    }
-
-    pub fn sharp(pos: Vec2, v: f32, norm1: Vec2, norm2: Vec2) -> Self {
-        let norm1_rz = norm1.normalize();
-        let norm2_rz = norm2.normalize();
-        LathePoint {
-            pos_rz: pos,
-            norm1_rz,
-            norm2_rz,
+            norm1_rz: norm1.normalize(),
+            norm2_rz: norm2.normalize(),
             v,
             pad: 0.0,
         }
     }
 }
 
-fn pot_model() -> Box<[LathePoint]> {
-    // Modelled on graph paper
-    let points = vec![
-        LathePoint::smooth(vec2(4.0/16.0, 15.0/16.0), 0.0, vec2(0.0, -1.0)),
-        LathePoint::smooth(vec2(3.0/16.0, 16.0/16.0), 1.0/16.0, vec2(-1.0, 0.0)),
-        LathePoint::smooth(vec2(4.0/16.0, 17.0/16.0), 2.0/16.0, vec2(0.0, 1.0)),
-        LathePoint::smooth(vec2(5.0/16.0, 16.0/16.0), 3.0/16.0, vec2(1.0, 0.0)),
-        LathePoint::sharp(vec2(4.0/16.0, 15.0/16.0), 0.25, vec2(0.0, -1.0), vec2(1.0, 2.0)),
-
-        LathePoint::smooth(vec2(7.0/16.0, 13.0/16.0), 1.0 - (23.5 / 28.0)*0.75, vec2(1.0, 1.0)),
-        LathePoint::smooth(vec2(8.0/16.0, 11.0/16.0), 1.0 - (20.5 / 28.0)*0.75, vec2(4.0, 1.0)),
-        LathePoint::smooth(vec2(8.0/16.0, 9.0/16.0), 1.0 - (18.0 / 28.0)*0.75, vec2(8.0, -1.0)),
-        LathePoint::smooth(vec2(7.0/16.0, 5.0/16.0), 1.0 - (12.5 / 28.0)*0.75, vec2(3.0, -1.0)),
-        LathePoint::smooth(vec2(6.0/16.0, 3.0/16.0), 1.0 - (9.5 / 28.0)*0.75, vec2(1.75, -1.0)),
-        LathePoint::sharp(vec2(4.0/16.0, 0.0), 1.0 - (5.0 / 28.0)*0.75, vec2(1.0, -1.0), vec2(-1.0, -3.0), ),
-        LathePoint::smooth(vec2(0.0, 1.0/16.0), 1.0, vec2(0.0, -1.0)),
-    ];
-    points.into_boxed_slice()
+// the profile of an archetype, padded to the full number of rings by repeating the last point
+fn lathe_model(archetype: &TargetArchetype) -> [LathePoint; MAX_PROFILE_POINTS] {
+    let profile = archetype.full_profile();
+    std::array::from_fn(|i| {
+        let (pos, v, norm1, norm2) = profile[i.min(profile.len() - 1)];
+        LathePoint::new(pos, v, norm1, norm2)
+    })
 }
 
-const POT_U_DIVS: u32 = 8;
-const NUM_POT_VERTS: u32 = 11 * 6 * POT_U_DIVS;
-
-fn color_rail() -> LoopedRail<Vec3> {
-    // rainbow palette
-    // https://iamkate.com/data/12-bit-rainbow/
-    LoopedRail {points: vec![
-        vec3(0.24575, 0.00582, 0.18417),
-        vec3(0.40228, 0.03304, 0.09082),
-        vec3(0.60439, 0.13263, 0.13266),
-        vec3(0.85646, 0.31808, 0.05692),
-        vec3(0.85454, 0.723, 0.00119),
-        vec3(0.31869, 0.72314, 0.0909),
-        vec3(0.05705, 0.72332, 0.24573),
-        vec3(0.05705, 0.72332, 0.24573),
-        vec3(0.0, 0.49693, 0.60383),
-        vec3(0.00033, 0.31845, 0.6031),
-        vec3(0.03299, 0.13279, 0.49739),
-        vec3(0.13277, 0.03324, 0.31803),
-    ].into_boxed_slice()}
-}
+pub const POT_U_DIVS: u32 = 8;
+const NUM_POT_VERTS: u32 = (MAX_PROFILE_POINTS as u32 - 1) * 6 * POT_U_DIVS;
 
 fn pack_h3(v: Vec3) -> [f16; 3] {
     [f16::from_f32(v.x), f16::from_f32(v.y), f16::from_f32(v.z)]
 }
 
+// Simulation state for targets, usable without a GPU.
 pub struct TargetController {
-    targets_pipeline: RenderPipeline,
-    targets_refr_pipeline: RenderPipeline,
-    targets_refl_pipeline: RenderPipeline,
-    shadow_targets_pipeline: RenderPipeline,
-    targets_buf: Buffer,
-    targets_bg: BindGroup,
-    shadow_targets_bg: BindGroup,
-    smash_sounds: SoundAtlas,
-    max_target_inst: u32,
-
     updated_at: f64,
     pub all_targets: Box<[Target]>,
     pub targets_hit: u32,
+    pub archetypes: ArchetypeSet,
+    generator: LayoutGenerator,
+    layout: LevelLayout,
+    shapes: Vec<Lathe>, // the scaled profile of each archetype, for hit testing
+    floats: Vec<Float>,
+    debris: DebrisController,
+}
+
+// the first deep enough spots are taken by floating pots
+pub fn procedural_layout(num_targets: usize, terrain: &HeightmapTerrain, archetypes: &ArchetypeSet, inner_radius: f32, rng: &mut impl Rng) -> LevelLayout {
+    let mut targets = Vec::with_capacity(num_targets);
+    let mut num_floating = 0;
+
+    let seed = rng.random();
+    let mut i = 0;
+    while targets.len() < num_targets {
+        let rand = sobol_burley::sample_4d(i, 0, seed);
+        let xy = (vec2(rand[0], rand[1]) - 0.5) * 2.0 * inner_radius;
+        if let Some(z) = terrain.height_at(xy) && z < -0.1 {
+            let rot_z: f32 = TAU * rand[2];
+            let archetype = archetypes.pick(rng.random());
+            let floating = num_floating < NUM_FLOATING && z < -MIN_FLOAT_WATER_DEPTH;
+            let placement = if floating {
+                num_floating += 1;
+                TargetPlacement {
+                    bottom: xy.extend(-FLOAT_DRAFT * archetypes.archetypes[archetype].scale),
+                    rotation: Quat::from_rotation_z(rot_z),
+                    archetype, floating, wave: 0,
+                }
+            } else {
+                let norm = terrain.normal_at(xy).unwrap(); //same domain as height
+                TargetPlacement {
+                    bottom: vec3(xy.x, xy.y, z),
+                    rotation: Quat::from_rotation_arc(vec3(0.0, 0.0, 1.0), norm) * Quat::from_rotation_z(rot_z),
+                    archetype, floating, wave: 0,
+                }
+            };
+            targets.push(placement);
+        }
+        i += 1
+    }
+    LevelLayout {targets, waves: vec![0.0]}
+}
+
+// targets of the first waves are there from the start, the rest are pending until the rail gets to their wave
+fn place_targets(layout: &LevelLayout, archetypes: &ArchetypeSet, rng: &mut impl Rng) -> (Box<[Target]>, Vec<Float>) {
+    let mut targets = Vec::with_capacity(layout.targets.len());
+    let mut floats = Vec::new();
+
+    let palettes: Vec<LoopedRail<Vec3>> = archetypes.archetypes.iter()
+        .map(|a| LoopedRail {points: a.palette.clone().into_boxed_slice()})
+        .collect();
+
+    for placement in layout.targets.iter() {
+        let archetype = &archetypes.archetypes[placement.archetype];
+        let (mut bottom, mut rot) = (placement.bottom, placement.rotation);
+        if placement.floating {
+            let heading = placement.rotation * Vec3::X;
+            let float = Float {
+                target: targets.len(),
+                anchor: placement.bottom.xy(),
+                yaw: heading.y.atan2(heading.x),
+                draft: FLOAT_DRAFT * archetype.scale,
+                heave: 0.0,
+                heave_vel: 0.0,
+                tilt: Vec2::ZERO,
+                tilt_vel: Vec2::ZERO,
+                prev: (Vec3::ZERO, Quat::IDENTITY),
+            };
+            (bottom, rot) = float.placement();
+            floats.push(Float {prev: (bottom, rot), ..float});
+        }
+        let col_idx: f64 = rng.random();
+        let col_step = if rng.random_bool(0.5) {0.33} else {-0.33};
+        let col_fac = 0.4 * smoothstep((col_idx as f32 - 0.33).abs() * 5.0);
+        let colors = &palettes[placement.archetype];
+        targets.push(Target {
+            bottom,
+            time_hit: if layout.waves[placement.wave] <= 0.0 {-1.0} else {PENDING},
+            orientation: rot,
+            color_a: pack_h3(colors.sample(col_idx)),
+            color_b: pack_h3(colors.sample(col_idx + col_step).lerp(vec3(0.7, 0.7, 0.7), col_fac)),
+            seed: rng.random(),
+            scale: archetype.scale,
+            archetype: placement.archetype as u32,
+            hits_left: archetype.hit_points,
+            wave: placement.wave as u32,
+        });
+    }
+    (targets.into_boxed_slice(), floats)
 }
 
 impl TargetController {
-    fn gen_targets(num_targets: usize, terrain: &HeightmapTerrain, inner_radius: f32) -> Box<[Target]> {
-        let mut targets = Vec::with_capacity(num_targets);
-        let mut rng = thread_rng();
-
-        let colors = color_rail();
-
-        let seed = rng.random();
-        let mut i = 0;
-        while targets.len() < num_targets {
-            let rand = sobol_burley::sample_4d(i, 0, seed);
-            let xy = (vec2(rand[0], rand[1]) - 0.5) * 2.0 * inner_radius;
-            if let Some(z) = terrain.height_at(xy) {
-                if z < -0.1 {
-                    let rot_z: f32 = TAU * rand[2];
-                    let norm = terrain.normal_at(xy).unwrap(); //same domain as height
-                    let rot = Quat::from_rotation_arc(vec3(0.0, 0.0, 1.0), norm) * Quat::from_rotation_z(rot_z);
-                    let col_idx: f64 = rand[3] as f64;
-                    let col_step = if rng.gen_bool(0.5) {0.33} else {-0.33};
-                    let col_fac = 0.4 * smoothstep((col_idx as f32 - 0.33).abs() * 5.0);
-                    targets.push(Target {
-                        bottom: vec3(xy.x, xy.y, z),
-                        time_hit: -1.0,
-                        orientation: rot,
-                        color_a: pack_h3(colors.sample(col_idx)),
-                        color_b: pack_h3(colors.sample(col_idx + col_step).lerp(vec3(0.7, 0.7, 0.7), col_fac)),
-                        seed: rng.random(),
-                    });
+    pub fn new(terrain: &HeightmapTerrain, archetypes: ArchetypeSet, generator: LayoutGenerator, rng: &mut impl Rng) -> Self {
+        let layout = Self::gen_layout(&generator, terrain, &archetypes, rng);
+        let (all_targets, floats) = place_targets(&layout, &archetypes, rng);
+        let shapes = archetypes.archetypes.iter()
+            .map(|a| Lathe::new(a.profile.iter().map(|p| a.scale * p.pos).collect::<Vec<_>>()))
+            .collect();
+
+        TargetController {
+            updated_at: 0.0,
+            all_targets,
+            targets_hit: 0,
+            archetypes,
+            generator,
+            layout,
+            shapes,
+            floats,
+            debris: DebrisController::new(),
+        }
+    }
+
+    fn gen_layout(generator: &LayoutGenerator, terrain: &HeightmapTerrain, archetypes: &ArchetypeSet, rng: &mut impl Rng) -> LevelLayout {
+        match generator {
+            LayoutGenerator::Procedural => procedural_layout(NUM_TARGETS, terrain, archetypes, 40.0, rng),
+            LayoutGenerator::Fixed(layout) => layout.clone(),
+        }
+    }
+
+    pub fn reset(&mut self, terrain: &HeightmapTerrain, rng: &mut impl Rng) {
+        self.layout = Self::gen_layout(&self.generator, terrain, &self.archetypes, rng);
+        (self.all_targets, self.floats) = place_targets(&self.layout, &self.archetypes, rng);
+        self.updated_at = 0.0;
+        self.targets_hit = 0;
+        self.debris.reset();
+    }
+
+    // where the targets of the current run were placed, for saving
+    pub fn layout(&self) -> &LevelLayout {
+        &self.layout
+    }
+
+    fn archetype(&self, t: &Target) -> &TargetArchetype {
+        &self.archetypes.archetypes[t.archetype as usize]
+    }
+
+    fn center(&self, t: &Target) -> Vec3 {
+        t.bottom + t.orientation.mul_vec3(vec3(0.0, 0.0, self.archetype(t).center_height()))
+    }
+
+    // nearest live pot to a path through `points` as (index, distance from the path to its center, depth of its bottom)
+    pub fn closest_live(&self, points: &[Vec3]) -> Option<(u32, f32, f32)> {
+        let path_dist_sq = |center: Vec3| points.windows(2)
+            .map(|seg| segment_point_dist_sq(seg[0], seg[1], center))
+            .fold(f32::INFINITY, f32::min);
+        self.all_targets.iter().enumerate()
+            .filter(|(_, t)| t.time_hit < 0.0)
+            .map(|(id, t)| (id as u32, path_dist_sq(self.center(t)).sqrt(), (-t.bottom.z).max(0.0)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    // `events` are those pushed this step, smashed pots come back after `respawn_delay` seconds if set
+    // and pending targets appear once the rail time reaches their wave
+    pub fn tick(&mut self, time: f64, terrain: &HeightmapTerrain, events: &[GameEvent], respawn_delay: Option<f64>) {
+        let dt = (time - self.updated_at).max(0.0) as f32;
+        if time >= 0.0 {
+            self.updated_at = time;
+        }
+        self.bob(dt, time as f32, events);
+        self.debris.tick(time, terrain);
+        for t in self.all_targets.iter_mut() {
+            if t.time_hit == PENDING && self.layout.waves[t.wave as usize] <= time {
+                t.time_hit = -1.0;
+            }
+        }
+        if let Some(delay) = respawn_delay {
+            for t in self.all_targets.iter_mut() {
+                if t.time_hit >= 0.0 && (t.time_hit as f64) + delay <= time {
+                    t.time_hit = -1.0;
+                    t.hits_left = self.archetypes.archetypes[t.archetype as usize].hit_points;
+                }
+            }
+        }
+    }
+
+    // keep floating pots and shards still while paused
+    pub fn hold(&mut self) {
+        for float in self.floats.iter_mut() {
+            let target = &self.all_targets[float.target];
+            float.prev = (target.bottom, target.orientation);
+        }
+        self.debris.hold();
+    }
+
+    // floating pots ride the ripples, and get rocked by arrows hitting the water nearby
+    fn bob(&mut self, dt: f32, time: f32, events: &[GameEvent]) {
+        for float in self.floats.iter_mut() {
+            let target = &mut self.all_targets[float.target];
+            float.prev = (target.bottom, target.orientation);
+            if target.time_hit >= 0.0 {
+                continue;
+            }
+            for event in events {
+                if let GameEvent::WaterEntry { pos, .. } | GameEvent::ArrowSkipped { pos, .. } = *event {
+                    let offset = float.anchor - pos.xy();
+                    let falloff = 1.0 - offset.length() / SPLASH_RADIUS;
+                    if falloff > 0.0 {
+                        float.heave_vel -= SPLASH_HEAVE * falloff;
+                        float.tilt_vel += SPLASH_TILT * falloff * offset.normalize_or_zero();
+                    }
                 }
             }
-            i += 1
+
+            // semi-implicit Euler on a damped spring towards the surface
+            let ripple = water_ripples(float.anchor, time);
+            float.heave_vel += dt * (BOB_STIFFNESS * (RIPPLE_HEAVE_SCALE * ripple.z - float.heave) - BOB_DAMPING * float.heave_vel);
+            float.heave += dt * float.heave_vel;
+            float.tilt_vel += dt * (BOB_STIFFNESS * (ripple.xy() - float.tilt) - BOB_DAMPING * float.tilt_vel);
+            float.tilt += dt * float.tilt_vel;
+            (target.bottom, target.orientation) = float.placement();
         }
-        targets.into_boxed_slice()
     }
+}
 
-    pub fn new(gpu: &GPUContext, assets: &impl AssetSource, renderer: &DeferredRenderer, terrain: &HeightmapTerrain) -> Self {
-        let all_targets = Self::gen_targets(NUM_TARGETS, terrain, 40.0);
+pub struct TargetView {
+    targets_pipeline: RenderPipeline,
+    targets_refr_pipeline: RenderPipeline,
+    targets_refl_pipeline: RenderPipeline,
+    shadow_targets_pipeline: RenderPipeline,
+    targets_buf: Buffer,
+    targets_bg: BindGroup,
+    shadow_targets_bg: BindGroup,
+    max_target_inst: u32,
+
+    // pieces of smashed pots, drawn from the same lathe profiles
+    shards_pipeline: RenderPipeline,
+    shards_refr_pipeline: RenderPipeline,
+    shards_refl_pipeline: RenderPipeline,
+    shadow_shards_pipeline: RenderPipeline,
+    shards_buf: Buffer,
+    shards_bg: BindGroup,
+    shadow_shards_bg: BindGroup,
+    max_shard_inst: u32,
+
+    // instances copied from the simulation each frame
+    target_insts: Vec<Target>,
+    shard_insts: Vec<ShardInst>,
+}
 
+impl TargetView {
+    pub fn new(gpu: &GPUContext, assets: &impl AssetSource, renderer: &DeferredRenderer, archetypes: &ArchetypeSet) -> Self {
         let shaders = gpu.process_shader_module("pots.wgsl", crate::shaders::TARGETS);
 
         let targets_bg_layout = gpu.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
@@ -236,7 +425,11 @@ impl TargetController {
             immediate_size: 0,
         });
 
-        let target_lathe_points = pot_model();
+        // every archetype's profile, the shader picks one by index
+        let mut target_lathe_points = vec![LathePoint::new(Vec2::ZERO, 0.0, Vec2::X, Vec2::X); MAX_ARCHETYPES * MAX_PROFILE_POINTS];
+        for (i, archetype) in archetypes.archetypes.iter().enumerate() {
+            target_lathe_points[i * MAX_PROFILE_POINTS..(i + 1) * MAX_PROFILE_POINTS].copy_from_slice(&lathe_model(archetype));
+        }
         let target_lathe_buf = gpu.device.create_buffer_init(&BufferInitDescriptor {
             label: Some("target_lathe_buf"),
             contents: bytemuck::cast_slice(&target_lathe_points),
@@ -294,9 +487,30 @@ impl TargetController {
         };
         let shadow_targets_pipeline = gpu.device.create_render_pipeline(&shadow_targets_pipeline_desc);
 
+        let shards_pipeline_desc = RenderPipelineDescriptor {
+            label: Some("shards"),
+            vertex: VertexState {entry_point: Some("shard_vert"), ..targets_pipeline_desc.vertex.clone()},
+            ..targets_pipeline_desc.clone()
+        };
+        let shards_pipeline = gpu.device.create_render_pipeline(&shards_pipeline_desc);
+        let shards_refr_pipeline = DeferredRenderer::create_refracted_pipeline(&gpu.device, &shards_pipeline_desc);
+        let shards_refl_pipeline = DeferredRenderer::create_reflected_pipeline(&gpu.device, &shards_pipeline_desc);
+        let shadow_shards_pipeline = gpu.device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("shards"),
+            vertex: VertexState {entry_point: Some("shard_vert_shadow"), ..shadow_targets_pipeline_desc.vertex.clone()},
+            ..shadow_targets_pipeline_desc.clone()
+        });
+
         let targets_buf = gpu.device.create_buffer(&BufferDescriptor {
             label: Some("pots_buf"),
-            size: (size_of::<Target>() * NUM_TARGETS) as u64,
+            size: (size_of::<Target>() * MAX_LAYOUT_TARGETS) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let shards_buf = gpu.device.create_buffer(&BufferDescriptor {
+            label: Some("shards_buf"),
+            size: (size_of::<ShardInst>() * MAX_SHARDS) as u64,
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
             mapped_at_creation: false
         });
@@ -314,119 +528,158 @@ impl TargetController {
         let pot_co_tex = gpu.load_texture_make_mips::<u32>(assets, "pot-co.png", TextureFormat::Rgba8UnormSrgb, 4).unwrap();
         let pot_nr_tex = gpu.load_texture_make_mips::<u32>(assets, "pot-nr.png", TextureFormat::Rgba8Unorm, 4).unwrap();
 
-        let targets_bg = gpu.device.create_bind_group(&BindGroupDescriptor {
-            label: Some("pots_bg"),
-            layout: &targets_bg_layout,
-            entries: &[
-                BindGroupEntry {binding: 0, resource: targets_buf.as_entire_binding()},
-                BindGroupEntry {binding: 1, resource: target_lathe_buf.as_entire_binding()},
-                BindGroupEntry {binding: 2, resource: BindingResource::Sampler(&tex_sampler)},
-                BindGroupEntry {binding: 3, resource: BindingResource::TextureView(&pot_co_tex.create_view(&Default::default()))},
-                BindGroupEntry {binding: 4, resource: BindingResource::TextureView(&pot_nr_tex.create_view(&Default::default()))},
-            ]
-        });
-        let shadow_targets_bg = gpu.device.create_bind_group(&BindGroupDescriptor {
-            label: Some("pots_bg"),
-            layout: &shadow_targets_bg_layout,
-            entries: &[
-                BindGroupEntry {binding: 0, resource: targets_buf.as_entire_binding()},
-                BindGroupEntry {binding: 1, resource: target_lathe_buf.as_entire_binding()},
-            ]
-        });
-
-        let smash_sounds = SoundAtlas::load_with_starts(assets, "glass_smash.ogg", -3.0,
-            &[0.0, 1.57, 2.84, 4.02, 5.43, 6.98, 8.38, 9.68, 10.97, 12.32, 13.58, 15.30, 16.73, 18.12]).unwrap();
+        let pot_co_view = pot_co_tex.create_view(&Default::default());
+        let pot_nr_view = pot_nr_tex.create_view(&Default::default());
+        // pots and shards are bound the same way, each to their own instances
+        let make_bgs = |label: &str, insts_buf: &Buffer| {
+            let bg = gpu.device.create_bind_group(&BindGroupDescriptor {
+                label: Some(label),
+                layout: &targets_bg_layout,
+                entries: &[
+                    BindGroupEntry {binding: 0, resource: insts_buf.as_entire_binding()},
+                    BindGroupEntry {binding: 1, resource: target_lathe_buf.as_entire_binding()},
+                    BindGroupEntry {binding: 2, resource: BindingResource::Sampler(&tex_sampler)},
+                    BindGroupEntry {binding: 3, resource: BindingResource::TextureView(&pot_co_view)},
+                    BindGroupEntry {binding: 4, resource: BindingResource::TextureView(&pot_nr_view)},
+                ]
+            });
+            let shadow_bg = gpu.device.create_bind_group(&BindGroupDescriptor {
+                label: Some(label),
+                layout: &shadow_targets_bg_layout,
+                entries: &[
+                    BindGroupEntry {binding: 0, resource: insts_buf.as_entire_binding()},
+                    BindGroupEntry {binding: 1, resource: target_lathe_buf.as_entire_binding()},
+                ]
+            });
+            (bg, shadow_bg)
+        };
+        let (targets_bg, shadow_targets_bg) = make_bgs("pots_bg", &targets_buf);
+        let (shards_bg, shadow_shards_bg) = make_bgs("shards_bg", &shards_buf);
 
-        TargetController {
+        TargetView {
             targets_pipeline, targets_refr_pipeline, targets_refl_pipeline, shadow_targets_pipeline,
             targets_buf, targets_bg, shadow_targets_bg,
-            smash_sounds,
             max_target_inst: 0,
-
-            updated_at: 0.0,
-            all_targets,
-            targets_hit: 0,
+            shards_pipeline, shards_refr_pipeline, shards_refl_pipeline, shadow_shards_pipeline,
+            shards_buf, shards_bg, shadow_shards_bg,
+            max_shard_inst: 0,
+            target_insts: Vec::new(),
+            shard_insts: Vec::new(),
         }
     }
 
-    pub fn reset(&mut self, terrain: &HeightmapTerrain) {
-        self.all_targets = Self::gen_targets(NUM_TARGETS, terrain, 40.0);
-        self.updated_at = 0.0;
-        self.targets_hit = 0;
+    // copy instances to draw from the simulation, placing floating pots and shards between the last two steps
+    pub fn update(&mut self, targets: &TargetController, alpha: f32) {
+        self.target_insts.clear();
+        self.target_insts.extend_from_slice(&targets.all_targets);
+        for float in targets.floats.iter() {
+            let inst = &mut self.target_insts[float.target];
+            inst.bottom = float.prev.0.lerp(inst.bottom, alpha);
+            inst.orientation = float.prev.1.slerp(inst.orientation, alpha);
+        }
+        self.shard_insts.clear();
+        self.shard_insts.extend(targets.debris.instances(alpha));
     }
 
-    pub fn tick(&mut self, time: f64) {
-        if time >= 0.0 {
-            self.updated_at = time;
+    fn draw<'a>(&'a self, pass: &mut RenderPass<'a>, targets: (&'a RenderPipeline, &'a BindGroup), shards: (&'a RenderPipeline, &'a BindGroup)) {
+        if self.max_target_inst != 0 {
+            pass.set_pipeline(targets.0);
+            pass.set_bind_group(1, targets.1, &[]);
+            pass.draw(0..NUM_POT_VERTS, 0..self.max_target_inst);
+        }
+        if self.max_shard_inst != 0 {
+            pass.set_pipeline(shards.0);
+            pass.set_bind_group(1, shards.1, &[]);
+            pass.draw(0..NUM_SHARD_VERTS, 0..self.max_shard_inst);
         }
     }
 }
 
-impl RenderObject for TargetController {
+impl RenderObject for TargetView {
     fn prepass(&mut self, gpu: &GPUContext, renderer: &DeferredRenderer, encoder: &mut CommandEncoder) {
         let planes = renderer.camera.perspective_clipping_planes();
 
-        let mut visible_targets: Vec<Target> = self.all_targets.iter().copied().filter(|t| {
-            sphere_visible(planes, t.bottom, 3.0)
+        // smashed pots are only drawn as their shards
+        let visible_targets: Vec<Target> = self.target_insts.iter().copied().filter(|t| {
+            t.time_hit < 0.0 && sphere_visible(planes, t.bottom, 3.0)
         }).collect();
 
         self.max_target_inst = visible_targets.len() as u32;
         if self.max_target_inst != 0 {
             gpu.queue.write_buffer(&self.targets_buf, 0, bytemuck::cast_slice(&visible_targets));
         }
+
+        let visible_shards: Vec<ShardInst> = self.shard_insts.iter().copied().filter(|s| {
+            sphere_visible(planes, s.pos(), 1.0)
+        }).collect();
+        self.max_shard_inst = visible_shards.len() as u32;
+        if self.max_shard_inst != 0 {
+            gpu.queue.write_buffer(&self.shards_buf, 0, bytemuck::cast_slice(&visible_shards));
+        }
     }
 
     fn draw_shadow_casters<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut RenderPass<'a>) {
-        if self.max_target_inst != 0 {
-            pass.set_pipeline(&self.shadow_targets_pipeline);
-            pass.set_bind_group(1, &self.shadow_targets_bg, &[]);
-            pass.draw(0..NUM_POT_VERTS, 0..self.max_target_inst);
-        }
+        self.draw(pass, (&self.shadow_targets_pipeline, &self.shadow_targets_bg), (&self.shadow_shards_pipeline, &self.shadow_shards_bg));
     }
 
     fn draw_underwater<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut RenderPass<'a>) {
-        if self.max_target_inst != 0 {
-            pass.set_pipeline(&self.targets_refr_pipeline);
-            pass.set_bind_group(1, &self.targets_bg, &[]);
-            pass.draw(0..NUM_POT_VERTS, 0..self.max_target_inst);
-        }
+        self.draw(pass, (&self.targets_refr_pipeline, &self.targets_bg), (&self.shards_refr_pipeline, &self.shards_bg));
     }
     fn draw_reflected<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut RenderPass<'a>) {
-        if self.max_target_inst != 0 {
-            pass.set_pipeline(&self.targets_refl_pipeline);
-            pass.set_bind_group(1, &self.targets_bg, &[]);
-            pass.draw(0..NUM_POT_VERTS, 0..self.max_target_inst);
-        }
+        self.draw(pass, (&self.targets_refl_pipeline, &self.targets_bg), (&self.shards_refl_pipeline, &self.shards_bg));
     }
 
     fn draw_opaque<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut RenderPass<'a>) {
-        if self.max_target_inst != 0 {
-            pass.set_pipeline(&self.targets_pipeline);
-            pass.set_bind_group(1, &self.targets_bg, &[]);
-            pass.draw(0..NUM_POT_VERTS, 0..self.max_target_inst);
-        }
+        self.draw(pass, (&self.targets_pipeline, &self.targets_bg), (&self.shards_pipeline, &self.shards_bg));
     }
 }
 
 impl ArrowTarget for TargetController {
-    fn process_hits(&mut self, mut audio: Option<&mut AudioManager>, start: Vec3, end: Vec3) -> bool {
-        let mut was_hit = false;
+    // pots shatter and the arrow carries on through, smashing anything else in its way, unless it is too slow
+    // tougher pots hold the arrow until the last one it takes
+    fn find_hits(&self, start: Vec3, end: Vec3) -> Vec<TargetHit> {
+        self.all_targets.iter().enumerate()
+            .filter(|(_, t)| t.time_hit < 0.0)
+            .filter_map(|(id, t)| {
+                let hit = intersect_placed(&self.shapes[t.archetype as usize], t.bottom, t.orientation, start, end)?;
+                let response = if t.hits_left > 1 {HitResponse::Embed} else {HitResponse::Pierce};
+                Some(TargetHit {id: id as u32, t: hit.t, pos: start.lerp(end, hit.t), normal: hit.normal, response})
+            })
+            .collect()
+    }
 
-        for t in self.all_targets.iter_mut() {
-            if t.time_hit < 0.0 {
-                let center = t.bottom + t.orientation.mul_vec3(vec3(0.0, 0.0, TARGET_RADIUS));
-                if collide_ray_sphere(start, end, center, TARGET_RADIUS) {
-                    t.time_hit = self.updated_at as f32;
-                    was_hit = true;
-                    self.targets_hit += 1;
-
-                    if let Some(audio) = &mut audio {
-                        audio.play(self.smash_sounds.random_sound()).unwrap();
-                    }
-                }
+    fn apply_hit(&mut self, events: &mut EventQueue, arrow: u32, vel: Vec3, hit: &TargetHit) {
+        let archetype = &self.archetypes.archetypes[self.all_targets[hit.id as usize].archetype as usize];
+        let t = &mut self.all_targets[hit.id as usize];
+        if t.time_hit < 0.0 {
+            t.hits_left = t.hits_left.saturating_sub(1);
+            if t.hits_left > 0 {
+                events.push(GameEvent::TargetCracked { arrow, id: hit.id, pos: hit.pos });
+                return;
             }
+            t.time_hit = self.updated_at as f32;
+            self.targets_hit += 1;
+            events.push(GameEvent::TargetSmashed {
+                arrow, id: hit.id, pos: hit.pos,
+                depth: (-t.bottom.z).max(0.0),
+                archetype: t.archetype,
+                points: archetype.points,
+            });
+            self.debris.shatter(&Breakage {
+                bottom: t.bottom,
+                orientation: t.orientation,
+                scale: t.scale,
+                archetype: t.archetype,
+                colors: (t.color_a, t.color_b),
+                hit_pos: hit.pos,
+                arrow_vel: vel,
+                seed: (t.seed as u64) << 32 | self.targets_hit as u64,
+            }, archetype);
         }
+    }
 
-        was_hit
+    fn attachment(&self, id: u32) -> Option<(Vec3, Quat)> {
+        let t = self.all_targets.get(id as usize)?;
+        (t.time_hit < 0.0).then_some((t.bottom, t.orientation))
     }
 }
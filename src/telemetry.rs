@@ -0,0 +1,243 @@
+use std::fmt::Write;
+
+use glam::*;
+
+use crate::{events::GameEvent, game_mode::ModeKind, targets::TargetController};
+
+// nearest live pot to an arrow which smashed nothing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NearMiss {
+    pub target: u32,
+    pub distance: f32, // from the arrow's path to the center of the pot
+    pub depth: f32, // of the bottom of the pot below the water surface
+}
+
+// Everything about one arrow needed to see how its shooter misjudged refraction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShotRecord {
+    pub arrow: u32,
+    pub time: f64, // seconds of rail travel at release
+    pub eye: Vec3,
+    pub look_dir: Vec3,
+    pub water_entry: Option<Vec3>,
+    pub skips: u32, // times it bounced off the surface
+    pub stopped_at: Option<Vec3>, // where it stuck in the terrain, None if it left the pond or was replaced
+    pub targets_hit: Vec<u32>,
+    pub targets_cracked: Vec<u32>, // struck without smashing
+    pub fish_hit: Vec<u32>,
+    pub near_miss: Option<NearMiss>,
+}
+
+impl ShotRecord {
+    fn find_near_miss(&mut self, path: &[Vec3], targets: &TargetController) {
+        if self.targets_hit.is_empty() && self.targets_cracked.is_empty() && self.fish_hit.is_empty() {
+            self.near_miss = targets.closest_live(path).map(|(target, distance, depth)| NearMiss {target, distance, depth});
+        }
+    }
+}
+
+// Per-shot log of a run, exported as CSV or JSON by the front ends at the end of each run.
+#[derive(Clone, Debug, Default)]
+pub struct ShotLog {
+    pub seed: u32,
+    pub mode: ModeKind,
+    pub shots: Vec<ShotRecord>, // in release order
+    in_flight: Vec<(usize, Vec<Vec3>)>, // index into shots and the path so far
+}
+
+impl ShotLog {
+    pub fn reset(&mut self, seed: u32, mode: ModeKind) {
+        *self = ShotLog {seed, mode, ..Default::default()};
+    }
+
+    pub fn process(&mut self, events: &[GameEvent], time: f64, targets: &TargetController) {
+        for event in events {
+            match *event {
+                GameEvent::ArrowReleased { arrow, eye, pos, dir } => {
+                    self.in_flight.push((self.shots.len(), vec![pos]));
+                    self.shots.push(ShotRecord {
+                        arrow, time, eye,
+                        look_dir: dir,
+                        water_entry: None,
+                        skips: 0,
+                        stopped_at: None,
+                        targets_hit: Vec::new(),
+                        targets_cracked: Vec::new(),
+                        fish_hit: Vec::new(),
+                        near_miss: None,
+                    });
+                }
+                GameEvent::WaterEntry { arrow, pos } => {
+                    if let Some(shot) = self.shot_in_flight(arrow) {
+                        shot.water_entry.get_or_insert(pos);
+                    }
+                }
+                GameEvent::ArrowSkipped { arrow, .. } => {
+                    if let Some(shot) = self.shot_in_flight(arrow) {
+                        shot.skips += 1;
+                    }
+                }
+                GameEvent::ArrowHitTerrain { arrow, pos } => {
+                    if let Some(shot) = self.shot_in_flight(arrow) {
+                        shot.stopped_at = Some(pos);
+                    }
+                }
+                GameEvent::TargetSmashed { arrow, id, .. } => {
+                    if let Some(shot) = self.shot_in_flight(arrow) {
+                        shot.targets_hit.push(id);
+                    }
+                }
+                GameEvent::TargetCracked { arrow, id, .. } => {
+                    if let Some(shot) = self.shot_in_flight(arrow) {
+                        shot.targets_cracked.push(id);
+                    }
+                }
+                GameEvent::FishHit { arrow, id, .. } => {
+                    if let Some(shot) = self.shot_in_flight(arrow) {
+                        shot.fish_hit.push(id);
+                    }
+                }
+                GameEvent::ArrowSpent { arrow, pos } => {
+                    let Some(i) = self.in_flight.iter().position(|(s, _)| self.shots[*s].arrow == arrow) else { continue };
+                    let (s, mut path) = self.in_flight.swap_remove(i);
+                    path.push(pos);
+                    self.shots[s].find_near_miss(&path, targets);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // extends the paths of arrows still in flight, once per step
+    pub fn trace(&mut self, positions: impl Iterator<Item = (u32, Vec3)>) {
+        for (arrow, pos) in positions {
+            if let Some((_, path)) = self.in_flight.iter_mut().find(|(s, _)| self.shots[*s].arrow == arrow) {
+                path.push(pos);
+            }
+        }
+    }
+
+    // arrows still flying when the run ends get their near misses from the path so far
+    pub fn finish(&mut self, targets: &TargetController) {
+        for (s, path) in self.in_flight.drain(..) {
+            self.shots[s].find_near_miss(&path, targets);
+        }
+    }
+
+    fn shot_in_flight(&mut self, arrow: u32) -> Option<&mut ShotRecord> {
+        let (s, _) = self.in_flight.iter().find(|(s, _)| self.shots[*s].arrow == arrow)?;
+        Some(&mut self.shots[*s])
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shots.is_empty()
+    }
+
+    // one row per arrow, with empty cells for missing values and targets and fish separated by spaces
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("arrow,time,eye_x,eye_y,eye_z,dir_x,dir_y,dir_z,water_x,water_y,skips,stop_x,stop_y,stop_z,targets_hit,targets_cracked,fish_hit,miss_target,miss_distance,miss_depth\n");
+        let opt = |v: Option<f32>| v.map_or(String::new(), |v| v.to_string());
+        for shot in self.shots.iter() {
+            let targets_hit: Vec<String> = shot.targets_hit.iter().map(|t| t.to_string()).collect();
+            let targets_cracked: Vec<String> = shot.targets_cracked.iter().map(|t| t.to_string()).collect();
+            let fish_hit: Vec<String> = shot.fish_hit.iter().map(|t| t.to_string()).collect();
+            let _ = writeln!(out, "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                shot.arrow, shot.time,
+                shot.eye.x, shot.eye.y, shot.eye.z,
+                shot.look_dir.x, shot.look_dir.y, shot.look_dir.z,
+                opt(shot.water_entry.map(|p| p.x)), opt(shot.water_entry.map(|p| p.y)), shot.skips,
+                opt(shot.stopped_at.map(|p| p.x)), opt(shot.stopped_at.map(|p| p.y)), opt(shot.stopped_at.map(|p| p.z)),
+                targets_hit.join(" "), targets_cracked.join(" "), fish_hit.join(" "),
+                shot.near_miss.map_or(String::new(), |m| m.target.to_string()),
+                opt(shot.near_miss.map(|m| m.distance)), opt(shot.near_miss.map(|m| m.depth)),
+            );
+        }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let vec = |v: Vec3| format!("[{},{},{}]", v.x, v.y, v.z);
+        let mut out = String::new();
+        let _ = write!(out, "{{\"seed\":{},\"mode\":\"{}\",\"shots\":[", self.seed, self.mode.name());
+        for (i, shot) in self.shots.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let targets_hit: Vec<String> = shot.targets_hit.iter().map(|t| t.to_string()).collect();
+            let targets_cracked: Vec<String> = shot.targets_cracked.iter().map(|t| t.to_string()).collect();
+            let fish_hit: Vec<String> = shot.fish_hit.iter().map(|t| t.to_string()).collect();
+            let near_miss = shot.near_miss.map_or("null".into(), |m| {
+                format!("{{\"target\":{},\"distance\":{},\"depth\":{}}}", m.target, m.distance, m.depth)
+            });
+            let _ = write!(out, "\n{{\"arrow\":{},\"time\":{},\"eye\":{},\"look_dir\":{},\"water_entry\":{},\"skips\":{},\"stopped_at\":{},\"targets_hit\":[{}],\"targets_cracked\":[{}],\"fish_hit\":[{}],\"near_miss\":{}}}",
+                shot.arrow, shot.time, vec(shot.eye), vec(shot.look_dir),
+                shot.water_entry.map_or("null".into(), vec), shot.skips, shot.stopped_at.map_or("null".into(), vec),
+                targets_hit.join(","), targets_cracked.join(","), fish_hit.join(","), near_miss,
+            );
+        }
+        out.push_str("\n]}\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use half::f16;
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{archetypes::ArchetypeSet, gputil::PlanarImage, levels::{LayoutGenerator, LevelLayout, TargetPlacement}, terrain_view::HeightmapTerrain};
+
+    fn one_pot(bottom: Vec3) -> TargetController {
+        let terrain = HeightmapTerrain::new(PlanarImage {width: 33, height: 33, data: vec![f16::from_f32(-3.0); 33 * 33].into_boxed_slice()}, 16.5, 1.0);
+        let layout = LevelLayout {
+            targets: vec![TargetPlacement {bottom, rotation: Quat::IDENTITY, archetype: 0, floating: false, wave: 0}],
+            waves: vec![0.0],
+        };
+        TargetController::new(&terrain, ArchetypeSet::default(), LayoutGenerator::Fixed(layout), &mut StdRng::seed_from_u64(0))
+    }
+
+    fn release(arrow: u32, pos: Vec3) -> GameEvent {
+        GameEvent::ArrowReleased { arrow, eye: pos, pos, dir: Vec3::X }
+    }
+
+    #[test]
+    fn near_misses_at_the_end_of_a_run() {
+        let targets = one_pot(vec3(10.0, 0.0, -2.0));
+        let center_z = -2.0 + ArchetypeSet::default().archetypes[0].center_height();
+        let mut log = ShotLog::default();
+        log.reset(5, ModeKind::Zen);
+        log.process(&[release(0, vec3(0.0, 1.0, center_z)), release(1, vec3(0.0, -3.0, center_z))], 12.0, &targets);
+        log.trace([(0, vec3(5.0, 1.0, center_z)), (1, vec3(5.0, -3.0, center_z))].into_iter());
+        log.trace([(0, vec3(10.0, 1.0, center_z)), (1, vec3(10.0, -3.0, center_z))].into_iter());
+        log.process(&[GameEvent::ArrowSpent { arrow: 0, pos: vec3(12.0, 1.0, center_z) }], 12.5, &targets);
+        assert!(log.shots[1].near_miss.is_none());
+
+        // arrow 1 is still flying when the run ends
+        log.finish(&targets);
+        for (shot, distance) in log.shots.iter().zip([1.0, 3.0]) {
+            let miss = shot.near_miss.unwrap();
+            assert_eq!((miss.target, miss.depth), (0, 2.0));
+            assert!((miss.distance - distance).abs() < 1e-4, "{} for {}", miss.distance, distance);
+        }
+        assert!(log.in_flight.is_empty());
+
+        // anything it does afterwards is no longer part of the run
+        log.process(&[GameEvent::ArrowHitTerrain { arrow: 1, pos: Vec3::ZERO }, GameEvent::ArrowSpent { arrow: 1, pos: Vec3::ZERO }], 13.0, &targets);
+        assert!(log.shots[1].stopped_at.is_none());
+        assert_eq!(log.shots.len(), 2);
+    }
+
+    #[test]
+    fn hits_have_no_near_miss() {
+        let targets = one_pot(vec3(10.0, 0.0, -2.0));
+        let mut log = ShotLog::default();
+        log.process(&[release(0, Vec3::ZERO), release(1, Vec3::ZERO)], 0.0, &targets);
+        log.process(&[
+            GameEvent::TargetCracked { arrow: 0, id: 0, pos: Vec3::ZERO },
+            GameEvent::FishHit { arrow: 1, id: 3, pos: Vec3::ZERO, depth: 1.0 },
+        ], 0.5, &targets);
+        log.finish(&targets);
+        assert!(log.shots.iter().all(|s| s.near_miss.is_none()));
+        assert_eq!((log.shots[0].targets_cracked.as_slice(), log.shots[1].fish_hit.as_slice()), ([0].as_slice(), [3].as_slice()));
+    }
+}
@@ -2,7 +2,7 @@ use crate::deferred_renderer::DeferredRenderer;
 use crate::deferred_renderer::RenderObject;
 use crate::gputil::{*, mip::*};
 use crate::camera::*;
-use glam::f32::*;
+use glam::*;
 use half::f16;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::*;
@@ -14,6 +14,15 @@ pub struct HeightmapTerrain {
     pub radius: f32,
     pub z_scale: f32,
     pub heightmap: PlanarImage<f16>,
+    range_mips: BakedRangeMips,
+}
+
+// where a segment first meets the terrain
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainHit {
+    pub t: f32, // fraction of the way along the segment
+    pub pos: Vec3,
+    pub normal: Vec3,
 }
 
 impl HeightmapTerrain {
@@ -21,7 +30,19 @@ impl HeightmapTerrain {
         let radius = 60.0;
         let z_scale = 1.0;
         let heightmap = load_png::<f16>(assets, Path::new("terrain_heightmap.png")).expect("Failed to load terrain");
-        HeightmapTerrain { radius, z_scale, heightmap}
+        Self::new(heightmap, radius, z_scale)
+    }
+
+    // `heightmap` covers the square from -radius to radius, with its first row at the top (+y)
+    pub fn new(heightmap: PlanarImage<f16>, radius: f32, z_scale: f32) -> Self {
+        let range_mips = BakedRangeMips::from_heightmap(&heightmap);
+        HeightmapTerrain { radius, z_scale, heightmap, range_mips}
+    }
+
+    // position in texels, with texel centers on integers
+    fn texel_pos(&self, xy: Vec2) -> Vec2 {
+        let uv = xy * vec2(1.0, -1.0) / self.radius / 2.0 + 0.5;
+        uv * self.heightmap.width as f32 - 0.5
     }
 
     pub fn height_at(&self, xy: Vec2) -> Option<f32> {
@@ -43,6 +64,158 @@ impl HeightmapTerrain {
             Some(vec3(-grad.x, grad.y, 2.0 * self.radius / self.z_scale).normalize())
         }
     }
+
+    // Finds the first point where the segment from start to end goes into the terrain, exactly as height_at
+    // interpolates it. Bins of the range mips are skipped wherever the segment passes above their highest point,
+    // the cells that are left are solved as bilinear patches. Segments starting underground hit at their start.
+    pub fn raycast(&self, start: Vec3, end: Vec3) -> Option<TerrainHit> {
+        let p0 = self.texel_pos(start.xy());
+        let delta = self.texel_pos(end.xy()) - p0;
+        let (z0, dz) = (start.z / self.z_scale, (end.z - start.z) / self.z_scale);
+        let num_cells = self.heightmap.width as u32 - 1;
+
+        // part of the segment over the cells of a bin, or None if it misses them
+        let clip = |level: usize, bin: UVec2| -> Option<(f32, f32)> {
+            let lo = (bin << level as u32).as_vec2();
+            let hi = ((bin + 1) << level as u32).min(UVec2::splat(num_cells)).as_vec2();
+            let (mut t0, mut t1) = (0.0f32, 1.0f32);
+            for axis in 0..2 {
+                if delta[axis].abs() < 1e-9 {
+                    if p0[axis] < lo[axis] || p0[axis] > hi[axis] {
+                        return None;
+                    }
+                } else {
+                    let ta = (lo[axis] - p0[axis]) / delta[axis];
+                    let tb = (hi[axis] - p0[axis]) / delta[axis];
+                    t0 = t0.max(ta.min(tb));
+                    t1 = t1.min(ta.max(tb));
+                }
+            }
+            (t0 <= t1).then_some((t0, t1))
+        };
+
+        let top = self.range_mips.num_mip_levels;
+        let mut stack = Vec::new();
+        if let Some((t0, t1)) = clip(top, UVec2::ZERO) {
+            stack.push((top, UVec2::ZERO, t0, t1));
+        }
+        while let Some((level, bin, t0, t1)) = stack.pop() {
+            if level == 0 {
+                if let Some((t, grad)) = self.raycast_cell(bin, (p0, delta), (z0, dz), (t0, t1)) {
+                    // texels run down the image as y goes up in the world
+                    let texel_size = 2.0 * self.radius / self.heightmap.width as f32;
+                    let slope = self.z_scale * grad * vec2(1.0, -1.0) / texel_size;
+                    let normal = vec3(-slope.x, -slope.y, 1.0).normalize();
+                    return Some(TerrainHit {t, pos: start.lerp(end, t), normal});
+                }
+                continue;
+            }
+            let (_, max_height) = self.range_mips.get_bin(bin, level);
+            if z0 + dz * t0 > max_height && z0 + dz * t1 > max_height {
+                continue;
+            }
+            let sub_size = if level == 1 {num_cells} else {self.range_mips.level_size(level - 1)};
+            let mut subs: Vec<_> = BakedRangeMips::sub_bins(bin).into_iter()
+                .filter(|sub| sub.x < sub_size && sub.y < sub_size)
+                .filter_map(|sub| clip(level - 1, sub).map(|(t0, t1)| (level - 1, sub, t0, t1)))
+                .collect();
+            // nearest is popped first, so the first hit found is the earliest
+            subs.sort_by(|a, b| b.2.total_cmp(&a.2));
+            stack.extend(subs);
+        }
+        None
+    }
+
+    // solves the segment against the bilinear patch between four texel centers,
+    // giving where along it the hit is and the slope there in heights per texel
+    fn raycast_cell(&self, cell: UVec2, (p0, delta): (Vec2, Vec2), (z0, dz): (f32, f32), (t0, t1): (f32, f32)) -> Option<(f32, Vec2)> {
+        let h00 = self.heightmap.pixel_at(cell).to_f64();
+        let h10 = self.heightmap.pixel_at(cell + uvec2(1, 0)).to_f64();
+        let h01 = self.heightmap.pixel_at(cell + uvec2(0, 1)).to_f64();
+        let h11 = self.heightmap.pixel_at(cell + uvec2(1, 1)).to_f64();
+        // h(u, v) = a + b u + c v + d u v within the cell
+        let (a, b, c, d) = (h00, h10 - h00, h01 - h00, h00 - h10 - h01 + h11);
+        let (u0, v0) = ((p0.x - cell.x as f32) as f64, (p0.y - cell.y as f32) as f64);
+        let (du, dv) = (delta.x as f64, delta.y as f64);
+        let (z0, dz) = (z0 as f64, dz as f64);
+
+        // height above the terrain along the segment, as a quadratic in t
+        let qa = -d * du * dv;
+        let qb = dz - (b * du + c * dv + d * (u0 * dv + v0 * du));
+        let qc = z0 - (a + b * u0 + c * v0 + d * u0 * v0);
+        let above = |t: f64| (qa * t + qb) * t + qc;
+
+        let (t0, t1) = (t0 as f64, t1 as f64);
+        let t = if above(t0) <= 0.0 {
+            t0
+        } else {
+            let mut roots = if qa.abs() < 1e-12 {
+                if qb == 0.0 {vec![]} else {vec![-qc / qb]}
+            } else {
+                let disc = qb * qb - 4.0 * qa * qc;
+                if disc < 0.0 {
+                    vec![]
+                } else {
+                    vec![(-qb - disc.sqrt()) / (2.0 * qa), (-qb + disc.sqrt()) / (2.0 * qa)]
+                }
+            };
+            roots.sort_by(f64::total_cmp);
+            match roots.into_iter().find(|t| (t0..=t1).contains(t)) {
+                Some(t) => t,
+                None if above(t1) <= 0.0 => t1,
+                None => return None,
+            }
+        };
+
+        let (u, v) = (u0 + du * t, v0 + dv * t);
+        Some((t as f32, vec2((b + d * v) as f32, (c + d * u) as f32)))
+    }
+}
+
+// Height of the ripples on the water surface as (gradient, value), the same as `water_ripples` in terrain.wgsl.
+// They are only ever drawn as normals, so the surface itself stays flat.
+pub fn water_ripples(xy: Vec2, time: f32) -> Vec3 {
+    0.010 * perlin_noise_deriv(xy + vec2(0.1, -0.55) * time, Mat2::from_cols(vec2(0.8, -1.9), vec2(3.8, 0.4)), 1)
+        + 0.007 * perlin_noise_deriv(xy + vec2(-0.05, 0.4) * time, Mat2::from_cols(vec2(3.5, 0.0), vec2(0.0, 6.7)), 0)
+}
+
+// gradients at the four corners of a cell, as pcg3d_snorm_perlin_quad in noise.wgsl
+fn perlin_grads(cell: IVec2, seed: i32) -> [Vec2; 4] {
+    let lcg = |v: i32| (v as u32).wrapping_mul(1664525).wrapping_add(1013904223);
+    let (ax, ay, az) = (lcg(cell.x), lcg(cell.y), lcg(seed));
+    let (bx, by) = (lcg(cell.x.wrapping_add(1)), lcg(cell.y.wrapping_add(1)));
+    [(ax, ay), (ax, by), (bx, ay), (bx, by)].map(|(mut x, mut y)| {
+        let mut z = az;
+        x = x.wrapping_add(y.wrapping_mul(z));
+        y = y.wrapping_add(z.wrapping_mul(x));
+        z = z.wrapping_add(x.wrapping_mul(y));
+        x ^= x >> 16;
+        y ^= y >> 16;
+        z ^= z >> 16;
+        x = x.wrapping_add(y.wrapping_mul(z));
+        y = y.wrapping_add(z.wrapping_mul(x));
+        vec2(x as f32, y as f32) / 2f32.powi(31) - 1.0
+    })
+}
+
+fn perlin_noise_deriv(xy: Vec2, freq: Mat2, seed: i32) -> Vec3 {
+    let uv = freq * xy;
+    let cell = uv.floor();
+    let f = uv - cell;
+
+    let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let du = 30.0 * f * f * (f * (f - 2.0) + 1.0);
+
+    let [gsw, gnw, gse, gne] = perlin_grads(cell.as_ivec2(), seed);
+    let sw = gsw.extend(gsw.dot(f));
+    let nw = gnw.extend(gnw.dot(f - vec2(0.0, 1.0)));
+    let se = gse.extend(gse.dot(f - vec2(1.0, 0.0)));
+    let ne = gne.extend(gne.dot(f - vec2(1.0, 1.0)));
+
+    let w = sw.lerp(nw, u.y) + vec3(0.0, (nw.z - sw.z) * du.y, 0.0);
+    let e = se.lerp(ne, u.y) + vec3(0.0, (ne.z - se.z) * du.y, 0.0);
+    let n = w.lerp(e, u.x) + vec3((e.z - w.z) * du.x, 0.0, 0.0);
+    (freq.transpose() * n.xy()).extend(n.z)
 }
 
 #[repr(C)]
@@ -363,3 +536,130 @@ impl RenderObject for TerrainView {
         pass.draw(0..4, 0..1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    const SIZE: usize = 33;
+    const RADIUS: f32 = SIZE as f32 / 2.0; // one meter per texel
+
+    fn terrain_from(heights: impl Fn(usize, usize) -> f32) -> HeightmapTerrain {
+        let data = (0..SIZE * SIZE).map(|i| f16::from_f32(heights(i % SIZE, i / SIZE))).collect();
+        HeightmapTerrain::new(PlanarImage {width: SIZE, height: SIZE, data}, RADIUS, 1.0)
+    }
+
+    fn random_terrain(seed: u64) -> HeightmapTerrain {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let heights: Vec<f32> = (0..SIZE * SIZE).map(|_| rng.random_range(-3.0..1.0)).collect();
+        terrain_from(|x, y| heights[SIZE * y + x])
+    }
+
+    // first point along the segment at or below height_at, sampled densely
+    fn march(terrain: &HeightmapTerrain, start: Vec3, end: Vec3, steps: usize) -> Option<f32> {
+        (0..=steps).map(|i| i as f32 / steps as f32).find(|t| {
+            let pos = start.lerp(end, *t);
+            terrain.height_at(pos.xy()).is_some_and(|h| pos.z <= h)
+        })
+    }
+
+    #[test]
+    fn matches_dense_march() {
+        const STEPS: usize = 20000;
+        let terrain = random_terrain(1);
+        let mut rng = StdRng::seed_from_u64(2);
+        // bilinear interpolation only covers the cells between texel centers
+        let inner = RADIUS - 0.5;
+        let mut hits = 0;
+        for _ in 0..500 {
+            let mut point = |z: std::ops::Range<f32>| vec3(rng.random_range(-inner..inner), rng.random_range(-inner..inner), rng.random_range(z));
+            let (start, end) = (point(1.5..4.0), point(-4.0..2.0));
+            let cast = terrain.raycast(start, end);
+            let marched = march(&terrain, start, end, STEPS);
+            match (cast, marched) {
+                (Some(hit), marched) => {
+                    hits += 1;
+                    assert!((hit.pos - start.lerp(end, hit.t)).length() < 1e-4);
+                    let height = terrain.height_at(hit.pos.xy()).unwrap();
+                    assert!((hit.pos.z - height).abs() < 1e-3, "hit {:?} is off the surface at {}", hit, height);
+                    // a crossing too thin for the march to see is fine, missing one it saw is not
+                    if let Some(t) = marched {
+                        assert!(hit.t <= t + 1e-4, "cast hit at {} after the march at {}", hit.t, t);
+                        assert!(t - hit.t < 0.01, "cast hit at {} well before the march at {}", hit.t, t);
+                    }
+                }
+                (None, Some(t)) => panic!("cast missed a hit at {} from {} to {}", t, start, end),
+                (None, None) => {}
+            }
+        }
+        assert!(hits > 100, "only {} segments hit", hits);
+    }
+
+    #[test]
+    fn range_mips_bound_their_cells() {
+        let terrain = random_terrain(6);
+        let mips = &terrain.range_mips;
+        let num_cells = SIZE as u32 - 1;
+        for level in 1..=mips.num_mip_levels {
+            for cell_y in 0..num_cells {
+                for cell_x in 0..num_cells {
+                    let (lo, hi) = mips.get_bin(uvec2(cell_x, cell_y) >> level as u32, level);
+                    for corner in [uvec2(0, 0), uvec2(1, 0), uvec2(0, 1), uvec2(1, 1)] {
+                        let h = terrain.heightmap.pixel_at(uvec2(cell_x, cell_y) + corner).to_f32();
+                        assert!(lo <= h && h <= hi, "level {} cell {}, {}: {} outside {}..{}", level, cell_x, cell_y, h, lo, hi);
+                    }
+                }
+            }
+        }
+        assert_eq!(mips.level_size(mips.num_mip_levels), 1);
+    }
+
+    #[test]
+    fn normal_faces_up_the_slope() {
+        // rises 0.5 per meter in x
+        let terrain = terrain_from(|x, _| -4.0 + 0.5 * x as f32);
+        let hit = terrain.raycast(vec3(0.3, 0.2, 5.0), vec3(0.3, 0.2, -5.0)).unwrap();
+        assert!(hit.normal.abs_diff_eq(vec3(-0.5, 0.0, 1.0).normalize(), 1e-3), "{:?}", hit.normal);
+    }
+
+    #[test]
+    fn thin_ridge_between_samples() {
+        // a single texel sticking up out of a flat bottom
+        let ridge = SIZE / 2;
+        let terrain = terrain_from(|x, y| if x == ridge && y == ridge {1.0} else {-2.0});
+        let (start, end) = (vec3(-10.0, 0.0, 0.5), vec3(10.0, 0.0, 0.5));
+        assert!(march(&terrain, start, end, 7).is_none());
+        let hit = terrain.raycast(start, end).unwrap();
+        assert!(hit.pos.x < 0.0 && hit.pos.x > -0.5, "{:?}", hit);
+        assert!((hit.pos.z - terrain.height_at(hit.pos.xy()).unwrap()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn start_underground() {
+        let terrain = random_terrain(3);
+        let start = vec3(2.0, -3.0, -5.0);
+        let hit = terrain.raycast(start, vec3(2.0, -3.0, 5.0)).unwrap();
+        assert_eq!(hit.t, 0.0);
+        assert_eq!(hit.pos, start);
+    }
+
+    #[test]
+    fn outside_the_domain() {
+        let terrain = terrain_from(|_, _| 0.0);
+        assert!(terrain.raycast(vec3(RADIUS + 1.0, 0.0, 1.0), vec3(RADIUS + 5.0, 3.0, -10.0)).is_none());
+        assert!(terrain.raycast(vec3(-40.0, -40.0, -1.0), vec3(-30.0, -35.0, -1.0)).is_none());
+    }
+
+    #[test]
+    fn vertical_ray() {
+        let terrain = random_terrain(4);
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..50 {
+            let xy = vec2(rng.random_range(-15.0..15.0), rng.random_range(-15.0..15.0));
+            let hit = terrain.raycast(xy.extend(5.0), xy.extend(-5.0)).unwrap();
+            assert!((hit.pos.z - terrain.height_at(xy).unwrap()).abs() < 1e-3);
+            assert!(hit.pos.xy().abs_diff_eq(xy, 1e-5));
+        }
+    }
+}
@@ -4,7 +4,7 @@ use kira::{manager::AudioManager, sound::{SoundData, static_sound::{StaticSoundD
 use wgpu::{util::{BufferInitDescriptor, DeviceExt}, *};
 use glam::*;
 
-use crate::{arrows::ArrowController, audio_util::{MusicData, MusicHandle, load_music_data, load_static_sound}, boat_rail::RailController, deferred_renderer::{DeferredRenderer, RenderObject}, gputil::{AssetSource, GPUContext, load_png}, targets::TargetController};
+use crate::{arrows::QuiverStatus, audio_util::{MusicData, MusicHandle, load_music_data, load_static_sound}, deferred_renderer::{DeferredRenderer, RenderObject}, gputil::{AssetSource, GPUContext, load_png}, events::GameEvent, game_mode::ModeKind, high_scores::{HighScore, InitialsEntry}, scoring::{LapSplit, RunScore, DEPTH_BUCKETS}, simulation::GameSimulation};
 
 // state machine for title screen, pausing, and restart
 #[derive(Clone, Copy, Debug)]
@@ -15,13 +15,13 @@ pub enum GameState {
     Playing,
     Paused,
     Finish {done_at: Instant},
+    Results, // until the player clicks
 }
 
 impl GameState {
     pub const FADE_DURATION: Duration = Duration::from_millis(600);
     pub const COUNTDOWN_DURATION: Duration = Duration::from_millis(4000);
     pub const FINISH_DURATION: Duration = Duration::from_millis(5000);
-    pub const GAME_PERIOD: f64 = 180.0;
 
     pub fn is_playing(&self) -> bool {
         if let GameState::Playing = self { true } else {false}
@@ -29,6 +29,9 @@ impl GameState {
     pub fn is_paused(&self) -> bool {
         if let GameState::Paused = self { true } else {false}
     }
+    pub fn accepts_mouse(&self) -> bool {
+        matches!(self, GameState::Playing | GameState::Countdown {..})
+    }
 
     pub fn should_reset_world(&self, now: Instant) -> bool {
         if let GameState::Fade { done_at } = *self {
@@ -55,9 +58,10 @@ impl GameState {
             GameState::Paused => {},
             GameState::Finish { done_at } => {
                 if done_at <= now {
-                    *self = GameState::Title { started_at: now, is_restart: true }
+                    *self = GameState::Results;
                 }
             },
+            GameState::Results => {},
         }
     }
 }
@@ -73,7 +77,46 @@ struct SDFTextParams {
     margin_vh: f32,
     sdf_rad: f32,
     num_chars: u32,
-    chars: [u32;4],
+    chars: [u32; MAX_TEXT_CHARS],
+}
+
+const MAX_TEXT_CHARS: usize = 16;
+const MAX_NUMBER_INSTS: usize = 8;
+
+// glyph indices in numbers.sdf.png
+const GLYPH_TARGET: u32 = 10;
+const GLYPH_CLOCK: u32 = 11;
+const GLYPH_ARROW: u32 = 12;
+const GLYPH_CROSSHAIR: u32 = 13;
+
+// glyphs in letters.sdf.png: A-Z, then space, dash, 0-9 and percent
+const NUM_LETTER_GLYPHS: u32 = 39;
+const MAX_LETTER_INSTS: usize = 24;
+const MAX_RESULT_LAPS: usize = 4;
+const DRAW_METER_LEN: usize = 12;
+
+fn letter_glyphs(text: &str) -> Vec<u32> {
+    text.bytes().map(|b| match b {
+        b'A'..=b'Z' => (b - b'A') as u32,
+        b'a'..=b'z' => (b - b'a') as u32,
+        b'-' => 27,
+        b'0'..=b'9' => (b - b'0') as u32 + 28,
+        b'%' => 38,
+        _ => 26,
+    }).collect()
+}
+
+// pads (or truncates) a glyph list to the fixed length used by the text shader
+fn glyphs(chars: &[u32]) -> [u32; MAX_TEXT_CHARS] {
+    let mut out = [0; MAX_TEXT_CHARS];
+    let n = chars.len().min(MAX_TEXT_CHARS);
+    out[..n].copy_from_slice(&chars[..n]);
+    out
+}
+
+// decimal digits of a number without leading zeros
+fn digit_glyphs(n: u64) -> Vec<u32> {
+    n.to_string().bytes().map(|b| (b - b'0') as u32).collect()
 }
 
 pub struct UIDisplay {
@@ -85,10 +128,15 @@ pub struct UIDisplay {
     title_bg: BindGroup,
     states_bg: BindGroup,
     numbers_bg: BindGroup,
+    letters_buf: Buffer,
+    letters_bg: BindGroup,
 
 
     music: MusicData,
     playing_music: Option<MusicHandle>,
+    music_muted: bool,
+    music_faded: bool, // turned down after the finish
+    music_volume: f64, // player setting, linear
     small_bell_sound: StaticSoundData,
     big_bell_sound: StaticSoundData,
     whistle_sound: StaticSoundData,
@@ -97,8 +145,17 @@ pub struct UIDisplay {
     old_state: GameState,
     cycle_time: f64,
     arrows_shot: u32,
-    targets_hit: u32,
-    secs_left: u32,
+    score: RunScore,
+    laps: Vec<LapSplit>,
+    clock_secs: u32, // time left, or time elapsed in modes without a limit
+    arrows_left: Option<u32>,
+    quiver: Option<QuiverStatus>,
+    draw_strength: Option<f32>,
+    is_scored: bool,
+    mode: ModeKind,
+    seed: u32,
+    high_scores: Vec<HighScore>, // for the current mode and seed
+    initials: Option<InitialsEntry>,
     updated_at: Instant,
 }
 
@@ -110,6 +167,8 @@ impl UIDisplay {
         let states_atlas = gpu.upload_texture_atlas("states_atlas", TextureFormat::R8Unorm, &states_img, 4);
         let numbers_img = load_png::<u8>(assets, "numbers.sdf.png").unwrap();
         let numbers_atlas = gpu.upload_texture_atlas("numbers_atlas", TextureFormat::R8Unorm, &numbers_img, 14);
+        let letters_img = load_png::<u8>(assets, "letters.sdf.png").unwrap();
+        let letters_atlas = gpu.upload_texture_atlas("letters_atlas", TextureFormat::R8Unorm, &letters_img, NUM_LETTER_GLYPHS);
 
         let bilinear_sampler = gpu.device.create_sampler(&SamplerDescriptor {
             label: Some("bilinear_sampler"),
@@ -218,7 +277,7 @@ impl UIDisplay {
             margin_vh: 0.05,
             sdf_rad: 20.0,
             num_chars: 1,
-            chars: [0, 0, 0, 0],
+            chars: glyphs(&[0]),
         };
 
         let title_buf = gpu.device.create_buffer_init(&BufferInitDescriptor {
@@ -264,7 +323,7 @@ impl UIDisplay {
 
         let numbers_buf = gpu.device.create_buffer(&BufferDescriptor {
             label: Some("numbers_buf"),
-            size: 5 * size_of::<SDFTextParams>() as u64,
+            size: (MAX_NUMBER_INSTS * size_of::<SDFTextParams>()) as u64,
             usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
             mapped_at_creation: false
         });
@@ -283,37 +342,114 @@ impl UIDisplay {
             ]
         });
 
+        let letters_buf = gpu.device.create_buffer(&BufferDescriptor {
+            label: Some("letters_buf"),
+            size: (MAX_LETTER_INSTS * size_of::<SDFTextParams>()) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
 
-        let music = load_music_data(assets, "river_valley_breakdown.ogg", -6.0).unwrap();
+        let letters_bg = gpu.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("letters_bg"),
+            layout: &text_bg_layout,
+            entries: &[
+                BindGroupEntry {binding: 0, resource: letters_buf.as_entire_binding()},
+                BindGroupEntry {binding: 1, resource: BindingResource::TextureView(&letters_atlas.create_view(&TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::D2Array),
+                    format: Some(TextureFormat::R8Unorm),
+                    ..Default::default()
+                }))},
+                BindGroupEntry {binding: 2, resource: BindingResource::Sampler(&bilinear_sampler)},
+            ]
+        });
+
+        let music = load_music_data(assets, "river_valley_breakdown.ogg", Self::MUSIC_VOLUME_DB).unwrap();
         let small_bell_sound = load_static_sound(assets, "small_bell.ogg", 0.0).unwrap();
         let big_bell_sound = load_static_sound(assets, "big_bell.ogg", 0.0).unwrap();
         let whistle_sound = load_static_sound(assets, "whistle.ogg", 0.0).unwrap();
 
         UIDisplay {
             text_pipeline, blackout_pipeline,
-            title_buf, states_buf, numbers_buf,
-            title_bg, states_bg, numbers_bg,
+            title_buf, states_buf, numbers_buf, letters_buf,
+            title_bg, states_bg, numbers_bg, letters_bg,
             
             music, small_bell_sound, big_bell_sound, whistle_sound,
             playing_music: None,
+            music_muted: false,
+            music_faded: false,
+            music_volume: 1.0,
 
             old_state: GameState::Title { started_at: Instant::now(), is_restart: false },
             cycle_time: 0.0,
             arrows_shot: 0,
-            targets_hit: 0,
-            secs_left: 0,
+            score: RunScore::default(),
+            laps: Vec::new(),
+            clock_secs: 0,
+            arrows_left: None,
+            quiver: None,
+            draw_strength: None,
+            is_scored: true,
+            mode: ModeKind::default(),
+            seed: 0,
+            high_scores: Vec::new(),
+            initials: None,
             updated_at: Instant::now(),
         }
     }
 
-    pub fn tick(&mut self, audio: Option<&mut AudioManager>, new_state: GameState, now: Instant, camera: &RailController, arrows: &ArrowController, targets: &TargetController) {
-        
+    pub fn tick(&mut self, mut audio: Option<&mut AudioManager>, sim: &GameSimulation, events: &[GameEvent]) {
+        let new_state = sim.state;
+        let camera = &sim.camera;
 
-        // state transition audio
-        if let Some(audio) = audio { match (self.old_state, new_state) {
-            (GameState::Countdown {..}, GameState::Playing) => {
+        for event in events {
+            match *event {
+                GameEvent::ArrowReleased {..} => self.arrows_shot += 1,
+                GameEvent::StateChanged { old, new } => self.on_state_changed(audio.as_deref_mut(), old, new),
+                _ => {}
+            }
+        }
+
+        // countdown bells
+        if let (Some(audio), GameState::Countdown {..}, GameState::Countdown {..}) = (audio, self.old_state, new_state) {
+            let old_countdown_num = (-self.cycle_time).ceil() as u32;
+            let countdown_num = (-camera.current_time).ceil() as u32;
+            if countdown_num > 0 && countdown_num <= 3 && countdown_num != old_countdown_num {
+                audio.play(self.small_bell_sound.clone());
+            }
+        }
+        self.cycle_time = camera.current_time;
+        let run = sim.run_status();
+        self.clock_secs = match sim.mode.time_limit() {
+            Some(limit) => (limit - camera.current_time).ceil().max(0.0) as u32,
+            None => camera.current_time.max(0.0) as u32,
+        };
+        self.score = sim.scoring.score;
+        self.laps.clone_from(&sim.scoring.laps);
+        self.arrows_left = sim.mode.arrow_limit().map(|limit| limit.saturating_sub(self.arrows_shot));
+        self.quiver = sim.arrows.quiver_status();
+        self.draw_strength = sim.arrows.draw_strength();
+        self.is_scored = sim.mode.score(&run).is_some();
+        self.mode = sim.mode.kind();
+        self.seed = sim.seed;
 
-                let sound_handle = audio.play(self.music.clone()).unwrap();
+        self.old_state = new_state;
+        self.updated_at = sim.now;
+    }
+
+    fn on_state_changed(&mut self, audio: Option<&mut AudioManager>, old: GameState, new: GameState) {
+        if let GameState::Countdown {..} = new {
+            self.arrows_shot = 0;
+        }
+
+        let Some(audio) = audio else { return };
+        match (old, new) {
+            (GameState::Countdown {..}, GameState::Playing) => {
+                let mut sound_handle = audio.play(self.music.clone()).unwrap();
+                self.music_faded = false;
+                let _ = sound_handle.set_volume(self.music_level(), Tween::default());
+                if self.music_muted {
+                    let _ = sound_handle.pause(Tween::default());
+                }
                 self.playing_music = Some(sound_handle);
                 audio.play(self.big_bell_sound.clone());
                 audio.play(self.small_bell_sound.clone());
@@ -321,9 +457,11 @@ impl UIDisplay {
             (GameState::Title {..}, GameState::Fade {..}) => {
                 self.stop_music();
             }
-            (GameState::Playing, GameState::Finish {..}) => {
+            (GameState::Playing | GameState::Paused, GameState::Finish {..}) => {
+                self.music_faded = true;
+                let volume = self.music_level();
                 if let Some(audio_handle) = &mut self.playing_music {
-                    let _ = audio_handle.set_volume(Volume::Decibels(-16.0), Tween {
+                    let _ = audio_handle.set_volume(volume, Tween {
                         start_time: kira::StartTime::Immediate,
                         duration: Duration::from_millis(500),
                         easing: Easing::InOutPowf(2.0),
@@ -331,22 +469,44 @@ impl UIDisplay {
                 }
                 audio.play(self.whistle_sound.clone());
             }
-            (GameState::Countdown {..}, GameState::Countdown {..}) => {
-                let old_countdown_num = (-self.cycle_time).ceil() as u32;
-                let countdown_num = (-camera.current_time).ceil() as u32;
-                if countdown_num > 0 && countdown_num <= 3 && countdown_num != old_countdown_num {
-                    audio.play(self.small_bell_sound.clone());
-                }
-            }
             _ => {}
-        }};
-        self.cycle_time = camera.current_time;
-        self.arrows_shot = arrows.arrows_shot;
-        self.targets_hit = targets.targets_hit;
-        self.secs_left = (GameState::GAME_PERIOD - camera.current_time).ceil() as u32;
+        }
+    }
 
-        self.old_state = new_state;
-        self.updated_at = now;
+    // the initials entry screen replaces the title screen while open
+    pub fn set_high_scores(&mut self, high_scores: &[HighScore], initials: Option<InitialsEntry>) {
+        self.high_scores.clear();
+        self.high_scores.extend_from_slice(high_scores);
+        self.initials = initials;
+    }
+
+    const MUSIC_VOLUME_DB: f64 = -6.0;
+    const FADED_MUSIC_VOLUME_DB: f64 = -16.0;
+
+    fn music_level(&self) -> Volume {
+        let db = if self.music_faded {Self::FADED_MUSIC_VOLUME_DB} else {Self::MUSIC_VOLUME_DB};
+        Volume::Amplitude(Volume::Decibels(db).as_amplitude() * self.music_volume)
+    }
+
+    pub fn set_music_volume(&mut self, volume: f32) {
+        self.music_volume = volume as f64;
+        let level = self.music_level();
+        if let Some(audio_handle) = &mut self.playing_music {
+            let _ = audio_handle.set_volume(level, Tween::default());
+        }
+    }
+
+    // mutes the music for the rest of the session, or brings it back
+    pub fn toggle_music(&mut self) {
+        self.music_muted = !self.music_muted;
+        if let Some(audio_handle) = &mut self.playing_music {
+            let tween = Tween {
+                start_time: kira::StartTime::Immediate,
+                duration: Duration::from_millis(500),
+                easing: Easing::InOutPowf(2.0),
+            };
+            let _ = if self.music_muted {audio_handle.pause(tween)} else {audio_handle.resume(tween)};
+        }
     }
 
     pub fn stop_music(&mut self) {
@@ -361,12 +521,80 @@ impl UIDisplay {
     }
 }
 
+impl UIDisplay {
+    // two columns of stats under the mode name, padded to a fixed width so that each column lines up
+    fn push_results(&self, letters_data: &mut Vec<SDFTextParams>) {
+        letters_data.push(letters_line(self.mode.name(), vec2(0.5, 0.05), vec2(0.054, 0.072)));
+
+        let row = |label: &str, value: String| format!("{:<10}{:>6}", label, value);
+        let mut left = Vec::new();
+        if self.is_scored {
+            left.push(row("SCORE", self.score.points.to_string()));
+            // the table isn't updated until initials are entered, so its top entry is the best before this run
+            let best = self.high_scores.first().map(|best| best.score);
+            if self.score.points > 0 && best.is_none_or(|best| self.score.points > best) {
+                left.push(row("NEW BEST", String::new()));
+            } else {
+                left.push(row("BEST", best.map_or("-".into(), |best| best.to_string())));
+            }
+        }
+        left.push(row("POTS", self.score.targets_hit.to_string()));
+        left.push(row("FISH", self.score.fish_hit.to_string()));
+        left.push(row("ARROWS", self.arrows_shot.to_string()));
+        left.push(row("ACCURACY", self.score.accuracy().map_or("-".into(), |a| format!("{}%", a))));
+        left.push(row("STREAK", self.score.best_streak.to_string()));
+
+        let mut right = vec![row("DEPTH", "POTS".into())];
+        let mut lower = 0;
+        for (i, hits) in self.score.hits_by_depth.iter().enumerate() {
+            let label = match DEPTH_BUCKETS.get(i) {
+                Some(upper) if lower == 0 => format!("UNDER {}CM", (upper * 100.0) as u32),
+                Some(upper) => format!("{}-{}CM", lower, (upper * 100.0) as u32),
+                None => format!("OVER {}CM", lower),
+            };
+            right.push(row(&label, hits.to_string()));
+            lower = DEPTH_BUCKETS.get(i).map_or(lower, |upper| (upper * 100.0) as u32);
+        }
+        right.push(row("LAPS", String::new()));
+        for (i, lap) in self.laps.iter().take(MAX_RESULT_LAPS).enumerate() {
+            right.push(row(&format!("LAP {}", i + 1), format!("{}S", lap.time.round() as u32)));
+        }
+
+        // rows past what fits in the letters buffer are dropped, keeping room for the prompt
+        let room = MAX_LETTER_INSTS.saturating_sub(letters_data.len() + 1);
+        let rows = [(0.25, left), (0.75, right)].into_iter().flat_map(|(x, lines)| {
+            lines.into_iter().enumerate().map(move |(i, line)| letters_line(&line, vec2(x, 0.18 + 0.065 * i as f32), vec2(0.03, 0.045)))
+        });
+        letters_data.extend(rows.take(room));
+        if letters_data.len() < MAX_LETTER_INSTS {
+            letters_data.push(letters_line("CLICK FOR TITLE", vec2(0.5, 0.9), vec2(0.036, 0.048)));
+        }
+    }
+}
+
+// white text in the letters atlas, `char_size` is in fractions of the viewport height
+fn letters_line(text: &str, viewport_loc: Vec2, char_size: Vec2) -> SDFTextParams {
+    let line_glyphs = letter_glyphs(text);
+    SDFTextParams {
+        viewport_loc,
+        size_vh: vec2(char_size.x * line_glyphs.len() as f32, char_size.y),
+        shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+        color: vec4(1.0, 1.0, 1.0, 1.0),
+        shadow_size: 1.0,
+        margin_vh: 0.05,
+        sdf_rad: 12.0,
+        num_chars: line_glyphs.len() as u32,
+        chars: glyphs(&line_glyphs),
+    }
+}
+
 impl RenderObject for UIDisplay {
     fn draw_opaque<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut wgpu::RenderPass<'a>) { }
 
     fn draw_transparent<'a>(&'a self, gpu: &GPUContext, renderer: &DeferredRenderer, pass: &mut wgpu::RenderPass<'a>) {
+        let entering_initials = self.initials.is_some();
         match self.old_state {
-            GameState::Title {..} | GameState::Fade {..} => {
+            GameState::Title {..} | GameState::Fade {..} if !entering_initials => {
                 pass.set_pipeline(&self.text_pipeline);
                 pass.set_bind_group(1, &self.title_bg, &[]);
                 pass.draw(0..4, 0..1);
@@ -375,7 +603,7 @@ impl RenderObject for UIDisplay {
         }
 
         let maybe_state_data = match self.old_state {
-            GameState::Title {..} => Some(SDFTextParams {
+            GameState::Title {..} if !entering_initials => Some(SDFTextParams {
                 viewport_loc: vec2(0.5, 0.8),
                 size_vh: vec2(0.6, 0.15),
                 shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
@@ -384,7 +612,7 @@ impl RenderObject for UIDisplay {
                 margin_vh: 0.05,
                 sdf_rad: 20.0,
                 num_chars: 1,
-                chars: [0, 0, 0, 0],
+                chars: glyphs(&[0]),
             }),
             GameState::Playing => {
                 if self.cycle_time < 0.8 {
@@ -397,7 +625,7 @@ impl RenderObject for UIDisplay {
                         margin_vh: 0.05,
                         sdf_rad: 20.0,
                         num_chars: 1,
-                        chars: [1, 0, 0, 0],
+                        chars: glyphs(&[1]),
                     })
                 } else {None}
             }
@@ -410,7 +638,7 @@ impl RenderObject for UIDisplay {
                 margin_vh: 0.05,
                 sdf_rad: 20.0,
                 num_chars: 1,
-                chars: [2, 0, 0, 0],
+                chars: glyphs(&[2]),
             }),
             GameState::Finish {..} => Some(SDFTextParams {
                 viewport_loc: vec2(0.5, 0.5),
@@ -421,7 +649,7 @@ impl RenderObject for UIDisplay {
                 margin_vh: 0.05,
                 sdf_rad: 20.0,
                 num_chars: 1,
-                chars: [3, 0, 0, 0],
+                chars: glyphs(&[3]),
             }),
             _ => None
         };
@@ -433,33 +661,44 @@ impl RenderObject for UIDisplay {
         }
 
         let mut numbers_data = Vec::new();
-        let num_arrows = 999.min(self.arrows_shot);
+        // counts down in modes with limited arrows, and shows what is left in the quiver in modes with one
+        let num_arrows = 999.min(self.quiver.map(|q| q.arrows).or(self.arrows_left).unwrap_or(self.arrows_shot));
+        // greyed out while the next arrow is nocked, and red once the quiver is empty
+        let reload = self.quiver.map_or(1.0, |q| q.reload);
+        let arrows_color = match self.quiver {
+            Some(q) if q.arrows == 0 => vec4(1.0, 0.2, 0.2, 1.0),
+            _ => vec4(1.0, 1.0, 1.0, 1.0).lerp(vec4(0.4, 0.4, 0.4, 1.0), 1.0 - reload),
+        };
         numbers_data.push(SDFTextParams {
             viewport_loc: vec2(0.0, 0.0),
             size_vh: vec2(0.15, 0.05),
             shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
-            color: vec4(1.0, 1.0, 1.0, 1.0),
-            shadow_size: 1.0,
-            margin_vh: 0.05,
-            sdf_rad: 12.0,
-            num_chars: 4,
-            chars: [12, num_arrows / 100, (num_arrows / 10) % 10, num_arrows % 10],
-        });
-        let num_targets = 999.min(self.targets_hit);
-        numbers_data.push(SDFTextParams {
-            viewport_loc: vec2(1.0, 0.0),
-            size_vh: vec2(0.15, 0.05),
-            shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
-            color: vec4(1.0, 1.0, 1.0, 1.0),
+            color: arrows_color,
             shadow_size: 1.0,
             margin_vh: 0.05,
             sdf_rad: 12.0,
             num_chars: 4,
-            chars: [10, num_targets / 100, (num_targets / 10) % 10, num_targets % 10],
+            chars: glyphs(&[GLYPH_ARROW, num_arrows / 100, (num_arrows / 10) % 10, num_arrows % 10]),
         });
+        if self.is_scored {
+            let points = 999999.min(self.score.points);
+            let mut points_glyphs = vec![GLYPH_TARGET];
+            points_glyphs.extend(digit_glyphs(points as u64));
+            numbers_data.push(SDFTextParams {
+                viewport_loc: vec2(1.0, 0.0),
+                size_vh: vec2(0.0375 * points_glyphs.len() as f32, 0.05),
+                shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+                color: vec4(1.0, 1.0, 1.0, 1.0),
+                shadow_size: 1.0,
+                margin_vh: 0.05,
+                sdf_rad: 12.0,
+                num_chars: points_glyphs.len() as u32,
+                chars: glyphs(&points_glyphs),
+            });
+        }
 
         match self.old_state {
-            GameState::Playing | GameState::Paused => {
+            GameState::Playing | GameState::Paused if self.is_scored => {
                 numbers_data.push(SDFTextParams {
                     viewport_loc: vec2(0.5, 0.0),
                     size_vh: vec2(0.15, 0.05),
@@ -469,12 +708,46 @@ impl RenderObject for UIDisplay {
                     margin_vh: 0.05,
                     sdf_rad: 12.0,
                     num_chars: 4,
-                    chars: [11, self.secs_left / 100, (self.secs_left / 10) % 10, self.secs_left % 10],
+                    chars: glyphs(&[GLYPH_CLOCK, (self.clock_secs / 100) % 10, (self.clock_secs / 10) % 10, self.clock_secs % 10]),
                 });
             }
             _ => {}
         }
 
+        match self.old_state {
+            GameState::Title {..} | GameState::Finish {..} | GameState::Results if !entering_initials => {
+                // the seed is shown so that players can share a pond
+                let seed_glyphs = digit_glyphs(self.seed as u64);
+                numbers_data.push(SDFTextParams {
+                    viewport_loc: vec2(0.5, 1.0),
+                    size_vh: vec2(0.0375 * seed_glyphs.len() as f32, 0.05),
+                    shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+                    color: vec4(1.0, 1.0, 1.0, 1.0),
+                    shadow_size: 1.0,
+                    margin_vh: 0.05,
+                    sdf_rad: 12.0,
+                    num_chars: seed_glyphs.len() as u32,
+                    chars: glyphs(&seed_glyphs),
+                });
+            }
+            _ => {}
+        }
+
+        if let Some(entry) = &self.initials {
+            let score_glyphs = digit_glyphs(entry.score as u64);
+            numbers_data.push(SDFTextParams {
+                viewport_loc: vec2(0.5, 0.4),
+                size_vh: vec2(0.06 * score_glyphs.len() as f32, 0.08),
+                shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+                color: vec4(1.0, 1.0, 1.0, 1.0),
+                shadow_size: 1.0,
+                margin_vh: 0.05,
+                sdf_rad: 12.0,
+                num_chars: score_glyphs.len() as u32,
+                chars: glyphs(&score_glyphs),
+            });
+        }
+
         if let GameState::Countdown {..} = self.old_state {
             let countdown_num = (-self.cycle_time).ceil() as u32;
             if countdown_num > 0 && countdown_num <= 3 {
@@ -487,23 +760,26 @@ impl RenderObject for UIDisplay {
                     margin_vh: 0.05,
                     sdf_rad: 12.0,
                     num_chars: 1,
-                    chars: [countdown_num, 0, 0, 0],
+                    chars: glyphs(&[countdown_num]),
                 });
             }
         }
 
         match self.old_state {
             GameState::Playing | GameState::Countdown {..} => {
+                // the crosshair grows back in as the next arrow is nocked
+                let crosshair_scale = 0.6 + 0.4 * reload;
+                let crosshair_color = vec4(0.3196, 0.00723, 0.00535, 1.0).lerp(vec4(0.2, 0.2, 0.2, 0.6), 1.0 - reload);
                 numbers_data.push(SDFTextParams {
                     viewport_loc: vec2(0.5, 0.5),
-                    size_vh: vec2(0.06, 0.08),
+                    size_vh: crosshair_scale * vec2(0.06, 0.08),
                     shadow_color: vec4(0.3, 0.3, 0.3, 0.0),
-                    color: vec4(0.3196, 0.00723, 0.00535, 1.0),
+                    color: crosshair_color,
                     shadow_size: 0.3,
                     margin_vh: 0.05,
                     sdf_rad: 12.0,
                     num_chars: 1,
-                    chars: [13, 0, 0, 0],
+                    chars: glyphs(&[GLYPH_CROSSHAIR]),
                 });
             }
             _ => {}
@@ -516,6 +792,145 @@ impl RenderObject for UIDisplay {
             pass.draw(0..4, 0..(numbers_data.len() as u32));
         }
 
+        let mut letters_data = Vec::new();
+        let mode_y = match self.old_state {
+            GameState::Title {..} if entering_initials => Some(0.8),
+            GameState::Title {..} => Some(0.62), // between the title and the prompt
+            GameState::Finish {..} => Some(0.7),
+            _ => None,
+        };
+        if let Some(mode_y) = mode_y {
+            let mode_glyphs = letter_glyphs(self.mode.name());
+            letters_data.push(SDFTextParams {
+                viewport_loc: vec2(0.5, mode_y),
+                size_vh: vec2(0.054 * mode_glyphs.len() as f32, 0.072),
+                shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+                color: vec4(1.0, 1.0, 1.0, 1.0),
+                shadow_size: 1.0,
+                margin_vh: 0.05,
+                sdf_rad: 12.0,
+                num_chars: mode_glyphs.len() as u32,
+                chars: glyphs(&mode_glyphs),
+            });
+        }
+
+        if let Some(entry) = &self.initials {
+            let heading = letter_glyphs("NEW HIGH SCORE");
+            letters_data.push(SDFTextParams {
+                viewport_loc: vec2(0.5, 0.25),
+                size_vh: vec2(0.054 * heading.len() as f32, 0.072),
+                shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+                color: vec4(1.0, 1.0, 1.0, 1.0),
+                shadow_size: 1.0,
+                margin_vh: 0.05,
+                sdf_rad: 12.0,
+                num_chars: heading.len() as u32,
+                chars: glyphs(&heading),
+            });
+            // the letter being picked is drawn separately in its own color, in the same layout
+            let initials = letter_glyphs(std::str::from_utf8(&entry.initials).unwrap_or(""));
+            let space = letter_glyphs(" ")[0];
+            let mut others = [space; 5];
+            let mut current = [space; 5];
+            for (i, g) in initials.iter().enumerate() {
+                if i == entry.cursor {current[2 * i] = *g} else {others[2 * i] = *g}
+            }
+            for (chars, color) in [(others, vec4(1.0, 1.0, 1.0, 1.0)), (current, vec4(1.0, 0.8, 0.1, 1.0))] {
+                letters_data.push(SDFTextParams {
+                    viewport_loc: vec2(0.5, 0.6),
+                    size_vh: vec2(0.1 * 5.0, 0.133),
+                    shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+                    color,
+                    shadow_size: 1.0,
+                    margin_vh: 0.05,
+                    sdf_rad: 12.0,
+                    num_chars: 5,
+                    chars: glyphs(&chars),
+                });
+            }
+        } else if let GameState::Title {..} = self.old_state && !self.high_scores.is_empty() {
+            // best scores for the next run's pond, down the right side
+            let mut lines = vec!["HIGH SCORES".to_string()];
+            for (i, entry) in self.high_scores.iter().enumerate() {
+                lines.push(format!("{:>2} {} {:>7}", i + 1, entry.initials_str(), entry.score));
+            }
+            for (i, line) in lines.iter().enumerate() {
+                let line_glyphs = letter_glyphs(line);
+                letters_data.push(SDFTextParams {
+                    viewport_loc: vec2(1.0, 0.12 + 0.055 * i as f32),
+                    size_vh: vec2(0.027 * line_glyphs.len() as f32, 0.036),
+                    shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+                    color: vec4(1.0, 1.0, 1.0, 1.0),
+                    shadow_size: 1.0,
+                    margin_vh: 0.05,
+                    sdf_rad: 12.0,
+                    num_chars: line_glyphs.len() as u32,
+                    chars: glyphs(&line_glyphs),
+                });
+            }
+        }
+
+        if self.is_scored {
+            let accuracy = self.score.accuracy().map_or("-".into(), |a| format!("{}%", a));
+            match self.old_state {
+                // running totals under the score
+                GameState::Playing | GameState::Paused => {
+                    let mut lines = vec![accuracy];
+                    if self.score.streak >= 2 {
+                        lines.push(format!("STREAK {}", self.score.streak));
+                    }
+                    for (i, line) in lines.iter().enumerate() {
+                        let line_glyphs = letter_glyphs(line);
+                        letters_data.push(SDFTextParams {
+                            viewport_loc: vec2(1.0, 0.07 + 0.045 * i as f32),
+                            size_vh: vec2(0.027 * line_glyphs.len() as f32, 0.036),
+                            shadow_color: vec4(0.0, 0.0, 0.0, 0.7),
+                            color: vec4(1.0, 1.0, 1.0, 1.0),
+                            shadow_size: 1.0,
+                            margin_vh: 0.05,
+                            sdf_rad: 12.0,
+                            num_chars: line_glyphs.len() as u32,
+                            chars: glyphs(&line_glyphs),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let GameState::Results = self.old_state {
+            self.push_results(&mut letters_data);
+        }
+
+        // draw meter under the crosshair, dashes fill up from yellow to red at full draw
+        if let (GameState::Playing, Some(strength)) = (self.old_state, self.draw_strength) {
+            let filled = (strength * DRAW_METER_LEN as f32).round() as usize;
+            let dash = letter_glyphs("-")[0];
+            let space = letter_glyphs(" ")[0];
+            let fill_glyphs: Vec<u32> = (0..DRAW_METER_LEN).map(|i| if i < filled {dash} else {space}).collect();
+            let fill_color = vec4(1.0, 0.8, 0.1, 1.0).lerp(vec4(1.0, 0.15, 0.05, 1.0), strength);
+            for (chars, color) in [(vec![dash; DRAW_METER_LEN], vec4(0.3, 0.3, 0.3, 0.6)), (fill_glyphs, fill_color)] {
+                letters_data.push(SDFTextParams {
+                    viewport_loc: vec2(0.5, 0.6),
+                    size_vh: vec2(0.025 * DRAW_METER_LEN as f32, 0.04),
+                    shadow_color: vec4(0.0, 0.0, 0.0, 0.5),
+                    color,
+                    shadow_size: 1.0,
+                    margin_vh: 0.05,
+                    sdf_rad: 12.0,
+                    num_chars: DRAW_METER_LEN as u32,
+                    chars: glyphs(&chars),
+                });
+            }
+        }
+
+        if !letters_data.is_empty() {
+            gpu.queue.write_buffer(&self.letters_buf, 0, bytemuck::cast_slice(&letters_data));
+            pass.set_pipeline(&self.text_pipeline);
+            pass.set_bind_group(1, &self.letters_bg, &[]);
+            pass.draw(0..4, 0..(letters_data.len() as u32));
+        }
+
         let maybe_blackout = match self.old_state {
             GameState::Fade { done_at } => {
                 let t = (done_at - self.updated_at).as_secs_f32() / GameState::FADE_DURATION.as_secs_f32();